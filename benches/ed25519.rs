@@ -0,0 +1,29 @@
+use criterion::{criterion_group, criterion_main, Criterion};
+use genkey::{Ed25519, Ssh};
+
+fn public_key(c: &mut Criterion) {
+    let seed = vec![0x9d,0x61,0xb1,0x9d,0xef,0xfd,0x5a,0x60,0xba,0x84,0x4a,0xf4,0x92,0xec,0x2c,0xc4,0x44,0x49,0xc5,0x69,0x7b,0x32,0x69,0x19,0x70,0x3b,0xac,0x03,0x1c,0xae,0x7f,0x60];
+    let key = Ed25519::from_private_key(seed);
+
+    c.bench_function("ed25519 public key", |b| b.iter(|| key.gen_public_key()));
+
+    // 256 keys as a vanity search makes them, sharing one field inversion
+    let keys: Vec<Ed25519> = (0..256).map(|_| Ed25519::new()).collect();
+    c.bench_function("ed25519 public keys batch of 256", |b| b.iter(|| Ed25519::gen_public_keys(&keys)));
+
+    // the public key is derived twice: once for the public blob and once for the private section
+    c.bench_function("ed25519 ssh key", |b| b.iter(|| Ssh::with_checkint(&key, String::new(), 0)));
+}
+
+fn sign(c: &mut Criterion) {
+    let seed = vec![0x9d,0x61,0xb1,0x9d,0xef,0xfd,0x5a,0x60,0xba,0x84,0x4a,0xf4,0x92,0xec,0x2c,0xc4,0x44,0x49,0xc5,0x69,0x7b,0x32,0x69,0x19,0x70,0x3b,0xac,0x03,0x1c,0xae,0x7f,0x60];
+    let key = Ed25519::from_private_key(seed);
+    let public_key = key.gen_public_key();
+    let signature = key.sign(b"");
+
+    c.bench_function("ed25519 sign", |b| b.iter(|| key.sign(b"")));
+    c.bench_function("ed25519 verify", |b| b.iter(|| Ed25519::verify(&public_key, b"", &signature)));
+}
+
+criterion_group!(benches, public_key, sign);
+criterion_main!(benches);
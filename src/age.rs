@@ -0,0 +1,92 @@
+use crate::bech32;
+use crate::ssh;
+use crate::x509;
+use crate::ed25519::Ed25519;
+use crate::x25519::X25519;
+use crate::secret::Secret;
+
+// age v1 (age-encryption.org/v1) X25519 identity and recipient,
+// the private key and the public key in Bech32 as age-keygen writes them
+pub struct Age {
+    private_key: Secret,
+    public_key: Vec<u8>,
+}
+
+impl Age {
+    const IDENTITY_HRP: &'static str = "AGE-SECRET-KEY-";
+    const RECIPIENT_HRP: &'static str = "age";
+
+    // the private key is written clamped; age clamps it again when it is used
+    pub fn new(key: &X25519) -> Self {
+        Age{
+            private_key: key.clamped_private_key(),
+            public_key: key.gen_public_key(),
+        }
+    }
+
+    // the first identity of an age-keygen file, or a line of its own;
+    // blank lines and # comments are skipped as age does
+    pub fn from_identity(data: &[u8]) -> Option<Self> {
+        let line = std::str::from_utf8(data).ok()?.lines()
+            .map(|x| x.trim())
+            .find(|x| !x.is_empty() && !x.starts_with('#'))?;
+        let (hrp, private_key) = bech32::decode(line)?;
+        if hrp != Age::IDENTITY_HRP || private_key.len() != 32 {
+            return None;
+        }
+
+        Some(Age::new(&X25519::from_private_key(private_key.to_vec())))
+    }
+
+    // AGE-SECRET-KEY-1..., uppercase
+    pub fn identity(&self) -> Secret {
+        let mut out = bech32::encode(Age::IDENTITY_HRP, &self.private_key);
+        out.make_ascii_uppercase();
+
+        Secret::from(out)
+    }
+
+    // age1...
+    pub fn recipient(&self) -> String {
+        recipient(&self.public_key)
+    }
+
+    // the file of age-keygen, created is seconds since the epoch
+    // # created: 2006-01-02T15:04:05Z
+    // # public key: age1...
+    // AGE-SECRET-KEY-1...
+    pub fn to_keygen(&self, created: u64) -> Secret {
+        let [year, month, day, hour, minute, second] = x509::civil_time(created);
+
+        let mut out = Secret::new();
+        out.extend(format!("# created: {:04}-{:02}-{:02}T{:02}:{:02}:{:02}Z\n", year, month, day, hour, minute, second).as_bytes());
+        out.extend(format!("# public key: {}\n", self.recipient()).as_bytes());
+        out.extend(self.identity());
+        out.push(b'\n');
+
+        out
+    }
+}
+
+// age1... of an X25519 public key
+pub fn recipient(public_key: &[u8]) -> String {
+    assert_eq!(public_key.len(), 32, "an x25519 public key is 32 bytes");
+
+    String::from_utf8(bech32::encode(Age::RECIPIENT_HRP, public_key)).unwrap()
+}
+
+// the age1... recipient of an ssh-ed25519 public key blob, the X25519 key
+// of Ed25519::public_key_to_x25519 as ssh-to-age converts it.
+// age takes the ssh-ed25519 line itself as a recipient too, but only this one
+// goes where an X25519 recipient is expected, e.g. in a .sops.yaml.
+pub fn ssh_recipient(mut blob: &[u8]) -> Option<String> {
+    if ssh::read_string(&mut blob)? != b"ssh-ed25519" {
+        return None;
+    }
+    let public_key = ssh::read_string(&mut blob)?;
+    if !blob.is_empty() {
+        return None;
+    }
+
+    Some(recipient(&Ed25519::public_key_to_x25519(public_key)?))
+}
@@ -0,0 +1,102 @@
+use crate::secret::Secret;
+use zeroize::Zeroizing;
+
+// BIP 173 data characters, one for each 5 bit value
+const CHARSET: &[u8; 32] = b"qpzry9x8gf2tvdw0s3jn54khce6mua7l";
+
+// BIP 173, BCH code generator
+const GENERATOR: [u32; 5] = [0x3b6a57b2, 0x26508e6d, 0x1ea119fa, 0x3d4233dd, 0x2a1462b3];
+
+// BIP 173
+// hrp || '1' || data || 6 character checksum, lowercase.
+// There is no limit of 90 characters, as in age.
+pub fn encode(hrp: &str, data: &[u8]) -> Vec<u8> {
+    assert!(!hrp.is_empty() && hrp.bytes().all(|c| (33..=126).contains(&c)), "malformed bech32 human-readable part");
+    let hrp = hrp.to_ascii_lowercase();
+
+    // 8 bit -> 5 bit, the last group padded with zero bits
+    let mut values = Zeroizing::new(Vec::with_capacity((data.len() * 8).div_ceil(5) + 6));
+    let mut acc = 0u32;
+    let mut bits = 0;
+    for &x in data {
+        acc = acc << 8 | x as u32;
+        bits += 8;
+        while bits >= 5 {
+            bits -= 5;
+            values.push((acc >> bits) as u8 & 31);
+        }
+        acc &= (1 << bits) - 1;
+    }
+    if bits > 0 {
+        values.push((acc << (5 - bits)) as u8 & 31);
+    }
+
+    let checksum = polymod(&hrp, &values, &[0; 6]) ^ 1;
+    values.extend((0..6).map(|i| (checksum >> (5 * (5 - i))) as u8 & 31));
+
+    let mut out = Vec::with_capacity(hrp.len() + 1 + values.len());
+    out.extend(hrp.as_bytes());
+    out.push(b'1');
+    out.extend(values.iter().map(|&x| CHARSET[x as usize]));
+
+    out
+}
+
+// inverse of encode, the human-readable part as it was written.
+// Mixed case, a bad checksum or padding that is not zero bits give None.
+pub fn decode(data: &str) -> Option<(String, Secret)> {
+    if data.bytes().any(|c| c.is_ascii_lowercase()) && data.bytes().any(|c| c.is_ascii_uppercase()) {
+        return None;
+    }
+    let separator = data.rfind('1')?;
+    let (hrp, rest) = (&data[..separator], &data.as_bytes()[separator + 1..]);
+    if hrp.is_empty() || !hrp.bytes().all(|c| (33..=126).contains(&c)) || rest.len() < 6 {
+        return None;
+    }
+
+    let mut values = Zeroizing::new(Vec::with_capacity(rest.len()));
+    for &c in rest {
+        values.push(CHARSET.iter().position(|&x| x == c.to_ascii_lowercase())? as u8);
+    }
+    let (values, checksum) = values.split_at(rest.len() - 6);
+    if polymod(&hrp.to_ascii_lowercase(), values, checksum) != 1 {
+        return None;
+    }
+
+    // 5 bit -> 8 bit, at most 4 bits of zero padding left over
+    let mut out = Secret::with_capacity(values.len() * 5 / 8);
+    let mut acc = 0u32;
+    let mut bits = 0;
+    for &x in values {
+        acc = acc << 5 | x as u32;
+        bits += 5;
+        if bits >= 8 {
+            bits -= 8;
+            out.push((acc >> bits) as u8);
+        }
+        acc &= (1 << bits) - 1;
+    }
+    if bits >= 5 || acc != 0 {
+        return None;
+    }
+
+    Some((hrp.to_string(), out))
+}
+
+// BIP 173, checksum of the expanded hrp: high bits of each character, 0, low bits of each character
+fn polymod(hrp: &str, values: &[u8], checksum: &[u8]) -> u32 {
+    let expanded = hrp.bytes().map(|c| c >> 5).chain([0]).chain(hrp.bytes().map(|c| c & 31));
+
+    let mut chk = 1u32;
+    for x in expanded.chain(values.iter().copied()).chain(checksum.iter().copied()) {
+        let top = chk >> 25;
+        chk = (chk & 0x1ffffff) << 5 ^ x as u32;
+        for (i, g) in GENERATOR.iter().enumerate() {
+            if (top >> i) & 1 == 1 {
+                chk ^= g;
+            }
+        }
+    }
+
+    chk
+}
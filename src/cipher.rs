@@ -0,0 +1,88 @@
+use aes::Aes256;
+use aes::cipher::{Block, BlockEncrypt, BlockDecrypt, KeyInit};
+use des::TdesEde3;
+
+// block ciphers in CBC mode for encrypting keys at rest
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum Cipher {
+    Aes256Cbc,
+    DesEde3Cbc,
+}
+
+impl Cipher {
+    pub fn key_len(&self) -> usize {
+        match self {
+            Cipher::Aes256Cbc => 32,
+            Cipher::DesEde3Cbc => 24,
+        }
+    }
+
+    // also the length of the IV
+    pub fn block_len(&self) -> usize {
+        match self {
+            Cipher::Aes256Cbc => 16,
+            Cipher::DesEde3Cbc => 8,
+        }
+    }
+
+    // NIST SP 800-38A section 6.2 with the padding of RFC 5652 section 6.3:
+    // n bytes of value n fill the last block, a whole block of them when data is already aligned
+    pub fn encrypt(&self, key: &[u8], iv: &[u8], data: &[u8]) -> Vec<u8> {
+        let pad = self.block_len() - data.len() % self.block_len();
+        let mut out = data.to_vec();
+        out.resize(data.len() + pad, pad as u8);
+        self.encrypt_blocks(key, iv, &mut out);
+
+        out
+    }
+
+    // NIST SP 800-38A section 6.2 in place, for formats that pad on their own
+    pub fn encrypt_blocks(&self, key: &[u8], iv: &[u8], data: &mut [u8]) {
+        self.check(key, iv, data);
+        match self {
+            Cipher::Aes256Cbc => cbc_encrypt(&Aes256::new_from_slice(key).unwrap(), iv, data),
+            Cipher::DesEde3Cbc => cbc_encrypt(&TdesEde3::new_from_slice(key).unwrap(), iv, data),
+        }
+    }
+
+    // inverse of encrypt_blocks
+    pub fn decrypt_blocks(&self, key: &[u8], iv: &[u8], data: &mut [u8]) {
+        self.check(key, iv, data);
+        match self {
+            Cipher::Aes256Cbc => cbc_decrypt(&Aes256::new_from_slice(key).unwrap(), iv, data),
+            Cipher::DesEde3Cbc => cbc_decrypt(&TdesEde3::new_from_slice(key).unwrap(), iv, data),
+        }
+    }
+
+    fn check(&self, key: &[u8], iv: &[u8], data: &[u8]) {
+        assert_eq!(key.len(), self.key_len(), "wrong key length");
+        assert_eq!(iv.len(), self.block_len(), "wrong IV length");
+        assert!(data.len().is_multiple_of(self.block_len()), "data is not a whole number of blocks");
+    }
+}
+
+// C_i = E(P_i xor C_(i-1)), C_0 = IV
+fn cbc_encrypt<C: BlockEncrypt>(cipher: &C, iv: &[u8], data: &mut [u8]) {
+    let mut chain = Block::<C>::clone_from_slice(iv);
+    for chunk in data.chunks_mut(C::block_size()) {
+        for (x, y) in chain.iter_mut().zip(chunk.iter()) {
+            *x ^= y;
+        }
+        cipher.encrypt_block(&mut chain);
+        chunk.copy_from_slice(&chain);
+    }
+}
+
+// P_i = D(C_i) xor C_(i-1), C_0 = IV
+fn cbc_decrypt<C: BlockDecrypt>(cipher: &C, iv: &[u8], data: &mut [u8]) {
+    let mut chain = Block::<C>::clone_from_slice(iv);
+    for chunk in data.chunks_mut(C::block_size()) {
+        let next = Block::<C>::clone_from_slice(chunk);
+        let mut block = next.clone();
+        cipher.decrypt_block(&mut block);
+        for ((x, y), z) in chunk.iter_mut().zip(block.iter()).zip(chain.iter()) {
+            *x = y ^ z;
+        }
+        chain = next;
+    }
+}
@@ -0,0 +1,432 @@
+use std::ops::{Add, Sub, Mul, Neg};
+use zeroize::Zeroize;
+
+// GF(2^255 - 19)
+// radix 2^51: five limbs, each normally below 2^52 after reduction
+#[derive(Clone, Copy)]
+pub struct FieldElement([u64; 5]);
+
+const MASK: u64 = (1 << 51) - 1;
+
+impl FieldElement {
+    pub const ZERO: FieldElement = FieldElement([0, 0, 0, 0, 0]);
+    pub const ONE: FieldElement = FieldElement([1, 0, 0, 0, 0]);
+
+    // little-endian, the top bit is ignored (RFC 7748 section 5)
+    pub fn from_bytes(bytes: &[u8; 32]) -> Self {
+        let mut limbs = [0u64; 5];
+        let mut acc: u128 = 0;
+        let mut bits = 0;
+        let mut j = 0;
+        for byte in bytes.iter() {
+            acc |= (*byte as u128) << bits;
+            bits += 8;
+            if bits >= 51 {
+                limbs[j] = acc as u64 & MASK;
+                acc >>= 51;
+                bits -= 51;
+                j += 1;
+            }
+        }
+
+        FieldElement(limbs)
+    }
+
+    // canonical little-endian encoding, value fully reduced below p
+    pub fn to_bytes(self) -> [u8; 32] {
+        let mut limbs = reduce(self.0);
+
+        // q is 1 if the value is at least p, 0 otherwise
+        let mut q = (limbs[0] + 19) >> 51;
+        q = (limbs[1] + q) >> 51;
+        q = (limbs[2] + q) >> 51;
+        q = (limbs[3] + q) >> 51;
+        q = (limbs[4] + q) >> 51;
+
+        limbs[0] += 19 * q;
+        limbs[1] += limbs[0] >> 51;
+        limbs[0] &= MASK;
+        limbs[2] += limbs[1] >> 51;
+        limbs[1] &= MASK;
+        limbs[3] += limbs[2] >> 51;
+        limbs[2] &= MASK;
+        limbs[4] += limbs[3] >> 51;
+        limbs[3] &= MASK;
+        limbs[4] &= MASK;
+
+        let mut out = [0u8; 32];
+        let mut acc: u128 = 0;
+        let mut bits = 0;
+        let mut i = 0;
+        for limb in limbs.iter() {
+            acc |= (*limb as u128) << bits;
+            bits += 51;
+            while bits >= 8 {
+                out[i] = acc as u8;
+                acc >>= 8;
+                bits -= 8;
+                i += 1;
+            }
+        }
+        out[i] = acc as u8;
+
+        out
+    }
+
+    pub fn square(&self) -> FieldElement {
+        self * self
+    }
+
+    // self^(2^k)
+    pub fn pow2k(&self, k: u32) -> FieldElement {
+        let mut out = *self;
+        for _ in 0..k {
+            out = out.square();
+        }
+        out
+    }
+
+    // returns (self^(2^250 - 1), self^11)
+    fn pow22501(&self) -> (FieldElement, FieldElement) {
+        let t0 = self.square();
+        let t1 = t0.pow2k(2);
+        let t2 = self * &t1;
+        let t3 = &t0 * &t2;
+        let t4 = t3.square();
+        let t5 = &t2 * &t4;
+        let t6 = t5.pow2k(5);
+        let t7 = &t6 * &t5;
+        let t8 = t7.pow2k(10);
+        let t9 = &t8 * &t7;
+        let t10 = t9.pow2k(20);
+        let t11 = &t10 * &t9;
+        let t12 = t11.pow2k(10);
+        let t13 = &t12 * &t7;
+        let t14 = t13.pow2k(50);
+        let t15 = &t14 * &t13;
+        let t16 = t15.pow2k(100);
+        let t17 = &t16 * &t15;
+        let t18 = t17.pow2k(50);
+        let t19 = &t18 * &t13;
+
+        (t19, t3)
+    }
+
+    // self^(p - 2), zero maps to zero
+    pub fn invert(&self) -> FieldElement {
+        let (t19, t3) = self.pow22501();
+        &t19.pow2k(5) * &t3
+    }
+
+    // self^((p - 5) / 8), used for square roots
+    pub fn pow_p58(&self) -> FieldElement {
+        let (t19, _) = self.pow22501();
+        &t19.pow2k(2) * self
+    }
+
+    // RFC 8032 section 5.1.2: x is negative when its lowest bit is set
+    pub fn is_negative(&self) -> u8 {
+        self.to_bytes()[0] & 1
+    }
+
+    pub fn is_zero(&self) -> bool {
+        self.to_bytes() == [0u8; 32]
+    }
+
+    // swap a and b when choice is 1, without branching on choice
+    pub fn swap(a: &mut FieldElement, b: &mut FieldElement, choice: u8) {
+        let mask = 0u64.wrapping_sub(choice as u64);
+        for i in 0..5 {
+            let t = mask & (a.0[i] ^ b.0[i]);
+            a.0[i] ^= t;
+            b.0[i] ^= t;
+        }
+    }
+}
+
+impl PartialEq for FieldElement {
+    fn eq(&self, other: &FieldElement) -> bool {
+        self.to_bytes() == other.to_bytes()
+    }
+}
+
+fn reduce(mut limbs: [u64; 5]) -> [u64; 5] {
+    let c0 = limbs[0] >> 51;
+    let c1 = limbs[1] >> 51;
+    let c2 = limbs[2] >> 51;
+    let c3 = limbs[3] >> 51;
+    let c4 = limbs[4] >> 51;
+
+    limbs[0] &= MASK;
+    limbs[1] &= MASK;
+    limbs[2] &= MASK;
+    limbs[3] &= MASK;
+    limbs[4] &= MASK;
+
+    limbs[0] += c4 * 19;
+    limbs[1] += c0;
+    limbs[2] += c1;
+    limbs[3] += c2;
+    limbs[4] += c3;
+
+    limbs
+}
+
+impl Add<&FieldElement> for &FieldElement {
+    type Output = FieldElement;
+
+    fn add(self, other: &FieldElement) -> FieldElement {
+        let mut limbs = [0u64; 5];
+        for (i, limb) in limbs.iter_mut().enumerate() {
+            *limb = self.0[i] + other.0[i];
+        }
+
+        FieldElement(reduce(limbs))
+    }
+}
+
+impl Sub<&FieldElement> for &FieldElement {
+    type Output = FieldElement;
+
+    fn sub(self, other: &FieldElement) -> FieldElement {
+        // add 16p first so no limb underflows
+        FieldElement(reduce([
+            (self.0[0] + 36028797018963664) - other.0[0],
+            (self.0[1] + 36028797018963952) - other.0[1],
+            (self.0[2] + 36028797018963952) - other.0[2],
+            (self.0[3] + 36028797018963952) - other.0[3],
+            (self.0[4] + 36028797018963952) - other.0[4],
+        ]))
+    }
+}
+
+impl Mul<&FieldElement> for &FieldElement {
+    type Output = FieldElement;
+
+    fn mul(self, other: &FieldElement) -> FieldElement {
+        fn m(x: u64, y: u64) -> u128 {
+            (x as u128) * (y as u128)
+        }
+
+        let a = &self.0;
+        let b = &other.0;
+
+        // 2^255 = 19 mod p, so limbs that overflow past 2^255 come back times 19
+        let b1_19 = b[1] * 19;
+        let b2_19 = b[2] * 19;
+        let b3_19 = b[3] * 19;
+        let b4_19 = b[4] * 19;
+
+        let c0 = m(a[0], b[0]) + m(a[4], b1_19) + m(a[3], b2_19) + m(a[2], b3_19) + m(a[1], b4_19);
+        let mut c1 = m(a[1], b[0]) + m(a[0], b[1]) + m(a[4], b2_19) + m(a[3], b3_19) + m(a[2], b4_19);
+        let mut c2 = m(a[2], b[0]) + m(a[1], b[1]) + m(a[0], b[2]) + m(a[4], b3_19) + m(a[3], b4_19);
+        let mut c3 = m(a[3], b[0]) + m(a[2], b[1]) + m(a[1], b[2]) + m(a[0], b[3]) + m(a[4], b4_19);
+        let mut c4 = m(a[4], b[0]) + m(a[3], b[1]) + m(a[2], b[2]) + m(a[1], b[3]) + m(a[0], b[4]);
+
+        c1 += c0 >> 51;
+        c2 += c1 >> 51;
+        c3 += c2 >> 51;
+        c4 += c3 >> 51;
+        let carry = (c4 >> 51) as u64;
+
+        let mut limbs = [
+            c0 as u64 & MASK,
+            c1 as u64 & MASK,
+            c2 as u64 & MASK,
+            c3 as u64 & MASK,
+            c4 as u64 & MASK,
+        ];
+        limbs[0] += carry * 19;
+        limbs[1] += limbs[0] >> 51;
+        limbs[0] &= MASK;
+
+        FieldElement(limbs)
+    }
+}
+
+impl Neg for &FieldElement {
+    type Output = FieldElement;
+
+    fn neg(self) -> FieldElement {
+        &FieldElement::ZERO - self
+    }
+}
+
+// integers modulo the group order
+// L = 2^252 + 27742317777372353535851937790883648493
+// radix 2^52 with Montgomery multiplication, R = 2^260.
+// Every operation runs the same steps whatever the values are.
+pub struct Scalar([u64; 5]);
+
+const SCALAR_MASK: u64 = (1 << 52) - 1;
+
+impl Scalar {
+    const L: Scalar = Scalar([0x2631a5cf5d3ed, 0xdea2f79cd6581, 0x14def9, 0, 0x100000000000]);
+    // -L^-1 mod 2^52
+    const LFACTOR: u64 = 0x51da312547e1b;
+    // R mod L
+    const R: Scalar = Scalar([0xf48bd6721e6ed, 0x3bab5ac67e45a, 0xfffffeb35e51b, 0xfffffffffffff, 0xfffffffffff]);
+    // R^2 mod L
+    const RR: Scalar = Scalar([0x9d265e952d13b, 0xd63c715bea69f, 0x5be65cb687604, 0x3dceec73d217f, 0x9411b7c309a]);
+
+    // little-endian, reduced modulo L
+    pub fn from_bytes(bytes: &[u8; 32]) -> Scalar {
+        let mut wide = [0u8; 64];
+        wide[..32].copy_from_slice(bytes);
+        let out = Scalar::from_bytes_wide(&wide);
+        wide.zeroize();
+        out
+    }
+
+    // 512-bit little-endian value such as a SHA-512 digest, reduced modulo L
+    pub fn from_bytes_wide(bytes: &[u8; 64]) -> Scalar {
+        let mut limbs = [0u64; 10];
+        let mut acc: u128 = 0;
+        let mut bits = 0;
+        let mut j = 0;
+        for byte in bytes.iter() {
+            acc |= (*byte as u128) << bits;
+            bits += 8;
+            if bits >= 52 {
+                limbs[j] = acc as u64 & SCALAR_MASK;
+                acc >>= 52;
+                bits -= 52;
+                j += 1;
+            }
+        }
+        limbs[j] = acc as u64;
+
+        // lo + hi * 2^260 = lo * R / R + hi * R^2 / R
+        let lo = Scalar([limbs[0], limbs[1], limbs[2], limbs[3], limbs[4]]);
+        let hi = Scalar([limbs[5], limbs[6], limbs[7], limbs[8], limbs[9]]);
+        limbs.zeroize();
+        let lo = Scalar::montgomery_mul(&lo, &Scalar::R);
+        let hi = Scalar::montgomery_mul(&hi, &Scalar::RR);
+
+        &lo + &hi
+    }
+
+    // little-endian
+    pub fn to_bytes(&self) -> [u8; 32] {
+        let mut out = [0u8; 32];
+        let mut acc: u128 = 0;
+        let mut bits = 0;
+        let mut i = 0;
+        for limb in self.0.iter() {
+            acc |= (*limb as u128) << bits;
+            bits += 52;
+            while bits >= 8 && i < 32 {
+                out[i] = acc as u8;
+                acc >>= 8;
+                bits -= 8;
+                i += 1;
+            }
+        }
+
+        out
+    }
+
+    // a * b / R mod L
+    fn montgomery_mul(a: &Scalar, b: &Scalar) -> Scalar {
+        fn m(x: u64, y: u64) -> u128 {
+            (x as u128) * (y as u128)
+        }
+
+        let a = &a.0;
+        let b = &b.0;
+        let mut z = [0u128; 9];
+        for i in 0..5 {
+            for j in 0..5 {
+                z[i + j] += m(a[i], b[j]);
+            }
+        }
+
+        // add multiples of L until the low 260 bits are zero, then shift them out
+        let l = &Scalar::L.0;
+        let step = |sum: u128| -> (u128, u64) {
+            let n = (sum as u64).wrapping_mul(Scalar::LFACTOR) & SCALAR_MASK;
+            ((sum + m(n, l[0])) >> 52, n)
+        };
+        let (carry, n0) = step(z[0]);
+        let (carry, n1) = step(carry + z[1] + m(n0, l[1]));
+        let (carry, n2) = step(carry + z[2] + m(n0, l[2]) + m(n1, l[1]));
+        let (carry, n3) = step(carry + z[3] + m(n1, l[2]) + m(n2, l[1]));
+        let (carry, n4) = step(carry + z[4] + m(n0, l[4]) + m(n2, l[2]) + m(n3, l[1]));
+
+        let carry = carry + z[5] + m(n1, l[4]) + m(n3, l[2]) + m(n4, l[1]);
+        let r0 = carry as u64 & SCALAR_MASK;
+        let carry = (carry >> 52) + z[6] + m(n2, l[4]) + m(n4, l[2]);
+        let r1 = carry as u64 & SCALAR_MASK;
+        let carry = (carry >> 52) + z[7] + m(n3, l[4]);
+        let r2 = carry as u64 & SCALAR_MASK;
+        let carry = (carry >> 52) + z[8] + m(n4, l[4]);
+        let r3 = carry as u64 & SCALAR_MASK;
+        let r4 = (carry >> 52) as u64;
+        z.zeroize();
+
+        // below 2L, one conditional subtraction
+        &Scalar([r0, r1, r2, r3, r4]) - &Scalar::L
+    }
+}
+
+impl Zeroize for Scalar {
+    fn zeroize(&mut self) {
+        self.0.zeroize();
+    }
+}
+
+// secret scalars and nonces, and every temporary made from them
+impl Drop for Scalar {
+    fn drop(&mut self) {
+        self.zeroize();
+    }
+}
+
+impl Add<&Scalar> for &Scalar {
+    type Output = Scalar;
+
+    fn add(self, other: &Scalar) -> Scalar {
+        let mut limbs = [0u64; 5];
+        let mut carry = 0;
+        for (i, limb) in limbs.iter_mut().enumerate() {
+            carry = self.0[i] + other.0[i] + (carry >> 52);
+            *limb = carry & SCALAR_MASK;
+        }
+
+        &Scalar(limbs) - &Scalar::L
+    }
+}
+
+// for operands below L, the result is reduced
+impl Sub<&Scalar> for &Scalar {
+    type Output = Scalar;
+
+    fn sub(self, other: &Scalar) -> Scalar {
+        let mut limbs = [0u64; 5];
+        let mut borrow: u64 = 0;
+        for (i, limb) in limbs.iter_mut().enumerate() {
+            borrow = self.0[i].wrapping_sub(other.0[i] + (borrow >> 63));
+            *limb = borrow & SCALAR_MASK;
+        }
+
+        // add L back when the difference went negative
+        let mask = 0u64.wrapping_sub(borrow >> 63);
+        let mut carry = 0;
+        for (i, limb) in limbs.iter_mut().enumerate() {
+            carry = (carry >> 52) + *limb + (Scalar::L.0[i] & mask);
+            *limb = carry & SCALAR_MASK;
+        }
+
+        Scalar(limbs)
+    }
+}
+
+impl Mul<&Scalar> for &Scalar {
+    type Output = Scalar;
+
+    // (a * b / R) * R^2 / R
+    fn mul(self, other: &Scalar) -> Scalar {
+        let product = Scalar::montgomery_mul(self, other);
+        Scalar::montgomery_mul(&product, &Scalar::RR)
+    }
+}
@@ -1,53 +1,108 @@
+use crate::secret::Secret;
+
 #[allow(dead_code)]
+#[derive(Clone, Copy)]
 pub enum Tag {
+    Boolean,
     Integer,
+    BitString,
     OctetString,
     Null,
     ObjectIdentifier,
+    Utf8String,
+    PrintableString,
+    BmpString,
+    UtcTime,
+    GeneralizedTime,
     Sequence,
+    Set,
+    // [n] EXPLICIT, constructed context-specific
+    Context(u8),
+    // [n] IMPLICIT of a primitive type
+    ContextPrimitive(u8),
 }
 
 pub trait DerEncode {
-    fn to_der(&self) -> Vec<u8>;
+    fn to_der(&self) -> Secret;
 }
 
-pub fn encode(t: Tag, v: Vec<u8>) -> Vec<u8> {
-    let tag = match t {
+fn tag(t: Tag) -> u8 {
+    match t {
+        Tag::Boolean => 0x01,
         Tag::Integer => 0x02,
+        Tag::BitString => 0x03,
         Tag::OctetString => 0x04,
         Tag::Null => 0x05,
         Tag::ObjectIdentifier => 0x06,
+        Tag::Utf8String => 0x0c,
+        Tag::PrintableString => 0x13,
+        Tag::BmpString => 0x1e,
+        Tag::UtcTime => 0x17,
+        Tag::GeneralizedTime => 0x18,
         Tag::Sequence => 0x30,
-    };
+        Tag::Set => 0x31,
+        Tag::Context(n) => 0xa0 | n,
+        Tag::ContextPrimitive(n) => 0x80 | n,
+    }
+}
 
+fn header(t: Tag, l: usize) -> Vec<u8> {
+    let tag = tag(t);
+
+    // short form below 128, otherwise 0x80 | number of length bytes
     let mut length = Vec::new();
-    let l = v.len();
-    match l {
-        0..=127 => length.push(l as u8),
-        128..=255 => {
-            length.push(0x81);
-            length.push(l as u8);
-        },
-        256..=65535 => {
-            length.push(0x82);
-            length.push((l >> 8) as u8);
-            length.push(l as u8);
-        },
-        _ => panic!("too long data"),
+    if l < 128 {
+        length.push(l as u8);
+    } else {
+        assert!(l <= u32::MAX as usize, "too long data");
+        let bytes = (l as u32).to_be_bytes();
+        let start = bytes.iter().position(|b| *b != 0).unwrap();
+        length.push(0x80 | (4 - start) as u8);
+        length.extend(&bytes[start..]);
     }
 
-    let value = v;
-
     let mut out = Vec::new();
     out.push(tag);
     out.extend(length);
+
+    out
+}
+
+pub fn encode<V: AsRef<[u8]>>(t: Tag, v: V) -> Vec<u8> {
+    let value = v.as_ref();
+    let header = header(t, value.len());
+
+    let mut out = Vec::with_capacity(header.len() + value.len());
+    out.extend(header);
     out.extend(value);
 
     out
 }
 
-pub fn to_integer(v: Vec<u8>) -> Vec<u8> {
-    let mut out = Vec::new();
+// same as encode, for values holding key material
+pub fn encode_secret(t: Tag, v: &[u8]) -> Secret {
+    let header = header(t, v.len());
+
+    let mut out = Secret::with_capacity(header.len() + v.len());
+    out.extend(header);
+    out.extend(v);
+
+    out
+}
+
+pub fn to_integer(v: &[u8]) -> Vec<u8> {
+    let mut out = Vec::with_capacity(v.len() + 1);
+    if v[0] >= 0x80 {
+        out.push(0);
+    }
+    out.extend(v);
+
+    out
+}
+
+// same as to_integer, for values holding key material
+pub fn to_integer_secret(v: &[u8]) -> Secret {
+    let mut out = Secret::with_capacity(v.len() + 1);
     if v[0] >= 0x80 {
         out.push(0);
     }
@@ -55,3 +110,67 @@ pub fn to_integer(v: Vec<u8>) -> Vec<u8> {
 
     out
 }
+
+// INTEGER contents of a small non-negative value
+pub fn to_integer_u32(v: u32) -> Vec<u8> {
+    let bytes = v.to_be_bytes();
+    let start = bytes.iter().position(|b| *b != 0).unwrap_or(3);
+
+    to_integer(&bytes[start..])
+}
+
+// reverse of to_integer_u32, None when the value is negative or does not fit
+pub fn from_integer_u32(v: &[u8]) -> Option<u32> {
+    if *v.first()? >= 0x80 {
+        return None;
+    }
+    let start = v.iter().position(|b| *b != 0).unwrap_or(v.len());
+    let v = &v[start..];
+    if v.len() > 4 {
+        return None;
+    }
+
+    Some(v.iter().fold(0, |acc, b| (acc << 8) | *b as u32))
+}
+
+// BIT STRING contents of a named bit list (X.680 section 22.7):
+// bit 0 is the most significant bit of the first byte and trailing zero bits are dropped
+pub fn to_named_bits(bits: &[usize]) -> Vec<u8> {
+    let len = bits.iter().max().map_or(0, |x| x / 8 + 1);
+    let mut out = vec![0u8; len + 1];
+    for bit in bits {
+        out[1 + bit / 8] |= 0x80 >> (bit % 8);
+    }
+    out[0] = out.last().map_or(0, |x| x.trailing_zeros() as u8 % 8);
+
+    out
+}
+
+// BIT STRING contents with no unused bits
+pub fn to_bit_string(v: &[u8]) -> Vec<u8> {
+    let mut out = Vec::with_capacity(v.len() + 1);
+    out.push(0);
+    out.extend(v);
+
+    out
+}
+
+// reads one TLV of type t from the front of data
+// returns its value and the rest of data
+pub fn decode(t: Tag, data: &[u8]) -> Option<(&[u8], &[u8])> {
+    if *data.first()? != tag(t) {
+        return None;
+    }
+    let (l, header) = match *data.get(1)? {
+        l @ 0..=127 => (l as usize, 2),
+        n @ 0x81..=0x84 => {
+            let n = (n & 0x7f) as usize;
+            let bytes = data.get(2..2 + n)?;
+            (bytes.iter().fold(0, |acc, b| (acc << 8) | *b as usize), 2 + n)
+        },
+        _ => return None,
+    };
+    let value = data.get(header..header.checked_add(l)?)?;
+
+    Some((value, &data[header + l..]))
+}
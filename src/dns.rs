@@ -0,0 +1,176 @@
+use crate::pem;
+use crate::ssh;
+use crate::hash::Hash;
+use crate::der::DerEncode;
+use crate::rsa::RsaAlgorithm;
+use crate::key::PublicKey;
+
+// DNS records for keys, written as lines of a BIND zone file.
+// Names are written as given, so a name without the final dot is relative to the zone's $ORIGIN,
+// except the owner of a DNSKEY, which the DS digest covers as an absolute name.
+
+pub trait DnskeyFormat {
+    // RFC 8624 section 3.1 algorithm number, None when the key can not sign DNSSEC
+    fn dnssec_algorithm(&self) -> Option<u8>;
+    // the Public Key field of the DNSKEY RDATA
+    fn gen_dnskey(&self) -> Vec<u8>;
+}
+
+// RFC 4034 section 2, a DNSSEC public key
+pub struct Dnskey {
+    owner: String,
+    flags: u16,
+    algorithm: u8,
+    public_key: Vec<u8>,
+}
+
+impl Dnskey {
+    // RFC 4034 section 2.1.1: bit 7 Zone Key, bit 15 Secure Entry Point
+    pub const ZONE_KEY: u16 = 0x0100;
+    pub const SEP: u16 = 0x0001;
+    // RFC 4034 section 2.1.2
+    const PROTOCOL: u8 = 3;
+
+    // a zone signing key; with_sep makes it a key signing key, flags 257.
+    // The owner is made fully qualified, "example.com" is "example.com.".
+    // None when it is not a domain name or the key can not sign DNSSEC.
+    pub fn new(owner: &str, key: &dyn DnskeyFormat) -> Option<Self> {
+        let owner = if owner.ends_with('.') { owner.to_string() } else { format!("{}.", owner) };
+        to_wire(&owner)?;
+
+        Some(Dnskey{
+            owner,
+            flags: Dnskey::ZONE_KEY,
+            algorithm: key.dnssec_algorithm()?,
+            public_key: key.gen_dnskey(),
+        })
+    }
+
+    pub fn with_sep(mut self) -> Self {
+        self.flags |= Dnskey::SEP;
+        self
+    }
+
+    // Flags (2), Protocol (1), Algorithm (1), Public Key
+    pub fn rdata(&self) -> Vec<u8> {
+        let mut out = self.flags.to_be_bytes().to_vec();
+        out.push(Dnskey::PROTOCOL);
+        out.push(self.algorithm);
+        out.extend(&self.public_key);
+        out
+    }
+
+    // RFC 4034 Appendix B, the one's complement style sum of the RDATA
+    pub fn key_tag(&self) -> u16 {
+        let mut ac = 0u32;
+        for (i, x) in self.rdata().iter().enumerate() {
+            ac += if i & 1 == 0 { (*x as u32) << 8 } else { *x as u32 };
+        }
+        ac += (ac >> 16) & 0xffff;
+        ac as u16
+    }
+
+    // <owner> IN DNSKEY <flags> 3 <algorithm> <base64 public key> ; key id = <key tag>
+    pub fn to_record(&self) -> String {
+        format!(
+            "{} IN DNSKEY {} {} {} {} ; key id = {}\n",
+            self.owner,
+            self.flags,
+            Dnskey::PROTOCOL,
+            self.algorithm,
+            String::from_utf8(pem::base64(&self.public_key)).unwrap(),
+            self.key_tag(),
+        )
+    }
+
+    // RFC 4034 section 5, the DS record for the parent zone, digest type 2 with SHA-256
+    // or 4 with SHA-384 (RFC 6605) over the canonical owner name || DNSKEY RDATA.
+    // None for other hashes.
+    pub fn to_ds(&self, hash: Hash) -> Option<String> {
+        let digest_type = match hash {
+            Hash::Sha256 => 2,
+            Hash::Sha384 => 4,
+            _ => return None,
+        };
+        let mut data = to_wire(&self.owner).unwrap();
+        data.extend(self.rdata());
+
+        Some(format!(
+            "{} IN DS {} {} {} {}\n",
+            self.owner,
+            self.key_tag(),
+            self.algorithm,
+            digest_type,
+            to_hex(&hash.digest(&data)).to_uppercase(),
+        ))
+    }
+}
+
+// RFC 4255 section 3.1, SHA-256 fingerprints (RFC 6594) of ssh-rsa (1),
+// ecdsa-sha2-* (3, RFC 6594) and ssh-ed25519 (4, RFC 7479) public key blobs.
+// None for other key types.
+pub fn sshfp(name: &str, blob: &[u8]) -> Option<String> {
+    let mut data = blob;
+    let algorithm = match ssh::read_string(&mut data)? {
+        b"ssh-rsa" => 1,
+        b"ecdsa-sha2-nistp256" | b"ecdsa-sha2-nistp384" | b"ecdsa-sha2-nistp521" => 3,
+        b"ssh-ed25519" => 4,
+        _ => return None,
+    };
+
+    Some(format!("{} IN SSHFP {} 2 {}\n", name, algorithm, to_hex(&Hash::Sha256.digest(blob))))
+}
+
+// RFC 6376 section 3.6.1, the DKIM key record of a selector:
+// <selector>._domainkey.<domain> IN TXT "v=DKIM1; k=rsa; p=<base64 SubjectPublicKeyInfo>"
+// or k=ed25519 with the raw public key (RFC 8463 section 4).
+// None for an rsa key restricted to PSS, rsa-sha256 signing with PKCS #1 v1.5.
+pub fn dkim(selector: &str, domain: &str, key: &PublicKey) -> Option<String> {
+    let (k, p) = match key {
+        PublicKey::Rsa(rsa) if rsa.algorithm() != RsaAlgorithm::Rsa => return None,
+        PublicKey::Rsa(_) => ("rsa", pem::base64(key.to_spki().to_der())),
+        PublicKey::Ed25519(key) => ("ed25519", pem::base64(key)),
+    };
+    let value = format!("v=DKIM1; k={}; p={}", k, String::from_utf8(p).unwrap());
+
+    Some(format!("{}._domainkey.{} IN TXT {}\n", selector, domain, txt(&value)))
+}
+
+// RFC 6698 section 2.1, usage 3 DANE-EE, selector 1 SubjectPublicKeyInfo,
+// matching type 1 SHA-256, for a TLS service: _<port>._<protocol>.<host>
+pub fn tlsa(port: u16, protocol: &str, host: &str, spki: &[u8]) -> String {
+    format!("_{}._{}.{} IN TLSA 3 1 1 {}\n", port, protocol, host, to_hex(&Hash::Sha256.digest(spki)))
+}
+
+// RFC 4034 section 6.2, the canonical wire form of a name: lowercase labels
+// each after its length, then the root.
+// None when a label is not 1 to 63 characters or the name is over 255 bytes.
+fn to_wire(name: &str) -> Option<Vec<u8>> {
+    let mut out = Vec::new();
+    let labels = name.strip_suffix('.').unwrap_or(name);
+    for label in labels.split('.').filter(|_| !labels.is_empty()) {
+        if !(1..=63).contains(&label.len()) {
+            return None;
+        }
+        out.push(label.len() as u8);
+        out.extend(label.to_ascii_lowercase().as_bytes());
+    }
+    out.push(0);
+
+    (out.len() <= 255).then_some(out)
+}
+
+// RFC 1035 section 3.3.14, a character-string holds at most 255 bytes:
+// longer text is split into several strings, on several lines in parentheses
+fn txt(value: &str) -> String {
+    let strings: Vec<String> = value.as_bytes().chunks(255).map(|x| format!("\"{}\"", String::from_utf8_lossy(x))).collect();
+
+    match strings.len() {
+        1 => strings[0].clone(),
+        _ => format!("( {} )", strings.join("\n\t")),
+    }
+}
+
+fn to_hex(data: &[u8]) -> String {
+    data.iter().map(|x| format!("{:02x}", x)).collect()
+}
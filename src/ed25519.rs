@@ -1,50 +1,333 @@
-use std::ops::Add;
+use std::ops::{Add, Neg};
+use std::sync::OnceLock;
 use rand::prelude::*;
-use num_bigint::{BigUint, BigInt, Sign};
-use num_traits::{Zero, One};
 use sha2::{Sha512, Digest};
+use crate::curve25519::{FieldElement, Scalar};
 use crate::der;
 use crate::der::{Tag, DerEncode};
 use crate::ssh;
 use crate::ssh::SshFormat;
 use crate::pkcs8::Pkcs8Format;
+use crate::spki::SpkiFormat;
+use crate::pem;
+use crate::jwk::{Jwk, JwkFormat};
+use crate::openpgp;
+use crate::openpgp::OpenPgpFormat;
+use crate::x25519::X25519;
+use crate::secret::Secret;
+use zeroize::{Zeroize, Zeroizing};
 
-#[derive(Clone)]
+// extended twisted Edwards coordinates (Hisil, Wong, Carter, Dawson 2008)
+// x = X/Z, y = Y/Z, x*y = T/Z
+#[derive(Clone, Copy)]
 struct Point {
-    x: Vec<u8>,
-    y: Vec<u8>,
+    x: FieldElement,
+    y: FieldElement,
+    z: FieldElement,
+    t: FieldElement,
 }
 
-impl Point{
-    pub fn from_xy(x: Vec<u8>, y: Vec<u8>) -> Self {
+impl Point {
+    const IDENTITY: Point = Point{
+        x: FieldElement::ZERO,
+        y: FieldElement::ONE,
+        z: FieldElement::ONE,
+        t: FieldElement::ZERO,
+    };
+
+    pub fn from_xy(x: FieldElement, y: FieldElement) -> Self {
+        let z = FieldElement::ONE;
+        let t = &x * &y;
+
         Point{
             x,
             y,
+            z,
+            t,
+        }
+    }
+
+    // dbl-2008-hwcd with a = -1
+    pub fn double(&self) -> Point {
+        let a = self.x.square();
+        let b = self.y.square();
+        let c = &self.z.square() + &self.z.square();
+        let d = -&a;
+        let e = &(&(&self.x + &self.y).square() - &a) - &b;
+        let g = &d + &b;
+        let f = &g - &c;
+        let h = &d - &b;
+
+        Point{
+            x: &e * &f,
+            y: &g * &h,
+            z: &f * &g,
+            t: &e * &h,
+        }
+    }
+
+    // Montgomery ladder over all 256 bits of the little-endian scalar.
+    // Every bit costs one addition and one doubling and the points are
+    // swapped with masks, so the running time does not depend on the scalar.
+    pub fn mul(&self, scalar: &[u8; 32]) -> Point {
+        let mut r0 = Point::IDENTITY;
+        let mut r1 = *self;
+        for i in (0..256).rev() {
+            let bit = (scalar[i / 8] >> (i % 8)) & 1;
+            Point::swap(&mut r0, &mut r1, bit);
+            r1 = &r0 + &r1;
+            r0 = r0.double();
+            Point::swap(&mut r0, &mut r1, bit);
+        }
+        r0
+    }
+
+    // [scalar]B from the table of multiples of B, the scalar in 64 signed radix 16 digits
+    // e[i] in -8..=8 with scalar = sum e[i] 16^i (ref10 ge_scalarmult_base).
+    // The odd digits are added first and the sum multiplied by 16, then the even digits,
+    // so the table only needs [j 256^i]B. Runs in the same time for every scalar.
+    pub fn mul_base(scalar: &[u8; 32]) -> Point {
+        let mut e = [0i8; 64];
+        for (i, x) in scalar.iter().enumerate() {
+            e[2 * i] = (x & 15) as i8;
+            e[2 * i + 1] = (x >> 4) as i8;
+        }
+        // the top bit is clear, so the last digit stays at most 8
+        let mut carry = 0;
+        for x in e.iter_mut().take(63) {
+            *x += carry;
+            carry = (*x + 8) >> 4;
+            *x -= carry << 4;
+        }
+        e[63] += carry;
+
+        let table = Affine::base_table();
+        let mut h = Point::IDENTITY;
+        for i in (1..64).step_by(2) {
+            h = h.add_affine(&Affine::select(&table[i / 2], e[i]));
+        }
+        h = h.double().double().double().double();
+        for i in (0..64).step_by(2) {
+            h = h.add_affine(&Affine::select(&table[i / 2], e[i]));
+        }
+        e.zeroize();
+
+        h
+    }
+
+    // madd-2008-hwcd-3, add-2008-hwcd-3 with z2 = 1 and 2 d t2 precomputed
+    fn add_affine(&self, other: &Affine) -> Point {
+        let a = &(&self.y - &self.x) * &other.y_minus_x;
+        let b = &(&self.y + &self.x) * &other.y_plus_x;
+        let c = &self.t * &other.xy2d;
+        let d = &self.z + &self.z;
+        let e = &b - &a;
+        let f = &d - &c;
+        let g = &d + &c;
+        let h = &b + &a;
+
+        Point{
+            x: &e * &f,
+            y: &g * &h,
+            z: &f * &g,
+            t: &e * &h,
+        }
+    }
+
+    // encode many points with one inversion (Montgomery's trick):
+    // invert z0 z1 ... zn once and peel the single inverses off with the prefix products
+    pub fn encode_batch(points: &[Point]) -> Vec<[u8; 32]> {
+        let mut products = Vec::with_capacity(points.len());
+        let mut acc = FieldElement::ONE;
+        for point in points {
+            products.push(acc);
+            acc = &acc * &point.z;
+        }
+
+        let mut inv = acc.invert();
+        let mut out = vec![[0u8; 32]; points.len()];
+        for (i, point) in points.iter().enumerate().rev() {
+            let z_inv = &inv * &products[i];
+            inv = &inv * &point.z;
+
+            let x = &point.x * &z_inv;
+            let mut encoded = (&point.y * &z_inv).to_bytes();
+            encoded[31] |= x.is_negative() << 7;
+            out[i] = encoded;
+        }
+
+        out
+    }
+
+    fn swap(a: &mut Point, b: &mut Point, choice: u8) {
+        FieldElement::swap(&mut a.x, &mut b.x, choice);
+        FieldElement::swap(&mut a.y, &mut b.y, choice);
+        FieldElement::swap(&mut a.z, &mut b.z, choice);
+        FieldElement::swap(&mut a.t, &mut b.t, choice);
+    }
+
+    // RFC 8032 section 5.1.2
+    pub fn encode(&self) -> [u8; 32] {
+        let z_inv = self.z.invert();
+        let x = &self.x * &z_inv;
+        let y = &self.y * &z_inv;
+
+        let mut out = y.to_bytes();
+        out[31] |= x.is_negative() << 7;
+
+        out
+    }
+
+    // RFC 8032 section 5.1.3
+    pub fn decode(bytes: &[u8; 32]) -> Option<Point> {
+        let x_0 = bytes[31] >> 7;
+        let y = FieldElement::from_bytes(bytes);
+        // y must be below p
+        let mut canonical = *bytes;
+        canonical[31] &= 0x7f;
+        if y.to_bytes() != canonical {
+            return None;
+        }
+
+        // x^2 = (y^2 - 1) / (d y^2 + 1) = u / v
+        let d = FieldElement::from_bytes(&Ed25519::D);
+        let y2 = y.square();
+        let u = &y2 - &FieldElement::ONE;
+        let v = &(&d * &y2) + &FieldElement::ONE;
+
+        // candidate root x = u v^3 (u v^7)^((p - 5) / 8)
+        let v3 = &v.square() * &v;
+        let v7 = &v3.square() * &v;
+        let mut x = &(&u * &v3) * &(&u * &v7).pow_p58();
+
+        let vx2 = &v * &x.square();
+        if vx2 == -&u {
+            x = &x * &FieldElement::from_bytes(&Ed25519::SQRT_M1);
+        } else if vx2 != u {
+            return None;
+        }
+        if x.is_zero() && x_0 == 1 {
+            return None;
         }
+        if x.is_negative() != x_0 {
+            x = -&x;
+        }
+
+        Some(Point::from_xy(x, y))
+    }
+}
+
+// a point with z = 1 kept as y + x, y - x and 2 d x y, ready for add_affine
+#[derive(Clone, Copy)]
+struct Affine {
+    y_plus_x: FieldElement,
+    y_minus_x: FieldElement,
+    xy2d: FieldElement,
+}
+
+impl Affine {
+    const IDENTITY: Affine = Affine{
+        y_plus_x: FieldElement::ONE,
+        y_minus_x: FieldElement::ONE,
+        xy2d: FieldElement::ZERO,
+    };
+
+    fn new(point: &Point) -> Affine {
+        let z_inv = point.z.invert();
+        let x = &point.x * &z_inv;
+        let y = &point.y * &z_inv;
+        let xyd = &(&x * &y) * &FieldElement::from_bytes(&Ed25519::D);
+
+        Affine{
+            y_plus_x: &y + &x,
+            y_minus_x: &y - &x,
+            xy2d: &xyd + &xyd,
+        }
+    }
+
+    // table[i][j] = [(j + 1) 256^i]B, made on first use
+    fn base_table() -> &'static [[Affine; 8]; 32] {
+        static TABLE: OnceLock<[[Affine; 8]; 32]> = OnceLock::new();
+
+        TABLE.get_or_init(|| {
+            let mut table = [[Affine::IDENTITY; 8]; 32];
+            let mut base = Ed25519::base_point();
+            for row in table.iter_mut() {
+                let mut multiple = base;
+                for entry in row.iter_mut() {
+                    *entry = Affine::new(&multiple);
+                    multiple = &multiple + &base;
+                }
+                // 256 = 2^8
+                for _ in 0..8 {
+                    base = base.double();
+                }
+            }
+            table
+        })
+    }
+
+    // [digit] of the row's point for digit in -8..=8, reading every entry
+    // so the time does not depend on the digit
+    fn select(row: &[Affine; 8], digit: i8) -> Affine {
+        // mask is -1 for a negative digit, 0 otherwise
+        let mask = digit >> 7;
+        let abs = ((digit ^ mask) - mask) as u8;
+
+        let mut out = Affine::IDENTITY;
+        for (j, entry) in row.iter().enumerate() {
+            let choice = (((abs ^ (j as u8 + 1)) as u32).wrapping_sub(1) >> 31) as u8;
+            let mut candidate = *entry;
+            FieldElement::swap(&mut out.y_plus_x, &mut candidate.y_plus_x, choice);
+            FieldElement::swap(&mut out.y_minus_x, &mut candidate.y_minus_x, choice);
+            FieldElement::swap(&mut out.xy2d, &mut candidate.xy2d, choice);
+        }
+
+        // -(x, y) = (-x, y) swaps y + x with y - x and negates 2 d x y
+        let negative = (mask & 1) as u8;
+        let mut neg_xy2d = -&out.xy2d;
+        FieldElement::swap(&mut out.y_plus_x, &mut out.y_minus_x, negative);
+        FieldElement::swap(&mut out.xy2d, &mut neg_xy2d, negative);
+
+        out
     }
 }
 
-impl<'a, 'b> Add<&'b Point> for &'a Point {
+impl Neg for &Point {
+    type Output = Point;
+
+    fn neg(self) -> Point {
+        Point{
+            x: -&self.x,
+            y: self.y,
+            z: self.z,
+            t: -&self.t,
+        }
+    }
+}
+
+// add-2008-hwcd-3, complete for Ed25519 so doubling and the identity need no special case
+impl Add<&Point> for &Point {
     type Output = Point;
 
     fn add(self, other: &Point) -> Point {
-        let p = BigUint::new(vec![2]).pow(255) - 19u32;
-        let d = BigUint::from_bytes_be(&Ed25519::D);
-        let x1 = BigUint::from_bytes_be(&self.x);
-        let y1 = BigUint::from_bytes_be(&self.y);
-        let x2 = BigUint::from_bytes_be(&other.x);
-        let y2 = BigUint::from_bytes_be(&other.y);
-        
-        let tmp = d * &x1 * &x2 * &y1 * &y2 % &p;
-        let x3 = (&x1 * &y2 + &x2 * &y1) * inv(&(1u32 + &tmp), &p) % &p;
-        let y3 = (&y1 * &y2 + &x1 * &x2) * inv(&(1u32 + (&p - &tmp)), &p) % &p;
-
-        let x = x3.to_bytes_be();
-        let y = y3.to_bytes_be();
+        let d = FieldElement::from_bytes(&Ed25519::D);
+        let d2 = &d + &d;
+
+        let a = &(&self.y - &self.x) * &(&other.y - &other.x);
+        let b = &(&self.y + &self.x) * &(&other.y + &other.x);
+        let c = &(&self.t * &d2) * &other.t;
+        let d = &(&self.z + &self.z) * &other.z;
+        let e = &b - &a;
+        let f = &d - &c;
+        let g = &d + &c;
+        let h = &b + &a;
 
         Point{
-            x,
-            y,
+            x: &e * &f,
+            y: &g * &h,
+            z: &f * &g,
+            t: &e * &h,
         }
     }
 }
@@ -52,7 +335,7 @@ impl<'a, 'b> Add<&'b Point> for &'a Point {
 // RFC 8410 section 7
 // CurvePrivateKey ::= OCTET STRING
 pub struct Ed25519 {
-    private_key: Vec<u8>,
+    private_key: Secret,
 }
 
 impl Ed25519 {
@@ -68,18 +351,33 @@ impl Ed25519 {
     // Parameters are absent.
     pub const ID: [u8; 7] = [0x30,0x05,0x06,0x03,0x2b,0x65,0x70];
 
+    // little-endian
     // 15112221349535400772501151409588531511454012693041857206046113283949847762202,
     // 46316835694926478169428394003475163141307993866256225615783033603165251855960,
     const B: ([u8; 32], [u8; 32]) = (
-        [0x21,0x69,0x36,0xd3,0xcd,0x6e,0x53,0xfe,0xc0,0xa4,0xe2,0x31,0xfd,0xd6,0xdc,0x5c,0x69,0x2c,0xc7,0x60,0x95,0x25,0xa7,0xb2,0xc9,0x56,0x2d,0x60,0x8f,0x25,0xd5,0x1a],
-        [0x66,0x66,0x66,0x66,0x66,0x66,0x66,0x66,0x66,0x66,0x66,0x66,0x66,0x66,0x66,0x66,0x66,0x66,0x66,0x66,0x66,0x66,0x66,0x66,0x66,0x66,0x66,0x66,0x66,0x66,0x66,0x58],
+        [0x1a,0xd5,0x25,0x8f,0x60,0x2d,0x56,0xc9,0xb2,0xa7,0x25,0x95,0x60,0xc7,0x2c,0x69,0x5c,0xdc,0xd6,0xfd,0x31,0xe2,0xa4,0xc0,0xfe,0x53,0x6e,0xcd,0xd3,0x36,0x69,0x21],
+        [0x58,0x66,0x66,0x66,0x66,0x66,0x66,0x66,0x66,0x66,0x66,0x66,0x66,0x66,0x66,0x66,0x66,0x66,0x66,0x66,0x66,0x66,0x66,0x66,0x66,0x66,0x66,0x66,0x66,0x66,0x66,0x66],
     );
 
+    // little-endian
     // 37095705934669439343138083508754565189542113879843219016388785533085940283555
-    const D: [u8; 32] = [0x52,0x03,0x6c,0xee,0x2b,0x6f,0xfe,0x73,0x8c,0xc7,0x40,0x79,0x77,0x79,0xe8,0x98,0x00,0x70,0x0a,0x4d,0x41,0x41,0xd8,0xab,0x75,0xeb,0x4d,0xca,0x13,0x59,0x78,0xa3];
+    const D: [u8; 32] = [0xa3,0x78,0x59,0x13,0xca,0x4d,0xeb,0x75,0xab,0xd8,0x41,0x41,0x4d,0x0a,0x70,0x00,0x98,0xe8,0x79,0x77,0x79,0x40,0xc7,0x8c,0x73,0xfe,0x6f,0x2b,0xee,0x6c,0x03,0x52];
+
+    // little-endian
+    // sqrt(-1) = 2^((p - 1) / 4)
+    const SQRT_M1: [u8; 32] = [0xb0,0xa0,0x0e,0x4a,0x27,0x1b,0xee,0xc4,0x78,0xe4,0x2f,0xad,0x06,0x18,0x43,0x2f,0xa7,0xd7,0xfb,0x3d,0x99,0x00,0x4d,0x2b,0x0b,0xdf,0xc1,0x4f,0x80,0x24,0x83,0x2b];
+
+    // RFC 8032 section 2
+    // dom2(phflag, context) = "SigEd25519 no Ed25519 collisions" || octet(phflag) || octet(len(context)) || context
+    const DOM2: &'static [u8] = b"SigEd25519 no Ed25519 collisions";
+
+    pub const SIGNATURE_LENGTH: usize = 64;
+
+    // RFC 9580 section 9.2, Ed25519Legacy 1.3.6.1.4.1.11591.15.1 without the DER tag and length
+    const PGP_OID: [u8; 9] = [0x2b,0x06,0x01,0x04,0x01,0xda,0x47,0x0f,0x01];
 
     pub fn new() -> Self {
-        let mut private_key = Vec::new();
+        let mut private_key = Secret::with_capacity(32);
         let mut rng = rand::thread_rng();
         for _ in 0..32 {
             private_key.push(rng.gen());
@@ -90,48 +388,253 @@ impl Ed25519 {
     }
 
     pub fn from_private_key(private_key: Vec<u8>) -> Self {
+        let private_key = Secret::from(private_key);
         Ed25519{
             private_key
         }
     }
 
-    fn gen_public_key(&self) -> Vec<u8> {
-        let x = Ed25519::B.0.to_vec();
-        let y = Ed25519::B.1.to_vec();
-        let b = Point::from_xy(x, y);
-        let mut point = b.clone();
-        let mut bin = Vec::new();
-
-        let mut buffer = Sha512::new();
-        buffer.update(self.private_key.clone());
-        let buffer = &mut buffer.finalize()[..32];
-        buffer[0] = buffer[0] & 0b1111_1000;
-        buffer[31] = buffer[31] & 0b0111_1111 | 0b0100_0000;
-        'to_bin: for (i, v) in buffer.iter().enumerate() {
-            let mut byte = *v;
-            for _ in 0..8 {
-                if i == buffer.len() - 1 && byte == 1 {
-                    break 'to_bin;
-                }
-                bin.push(byte % 2);
-                byte = byte / 2;
-            }
+    // RFC 8410 section 7, CurvePrivateKey inside the PKCS #8 privateKey
+    pub fn from_der(data: &[u8]) -> Option<Self> {
+        let (private_key, _) = der::decode(Tag::OctetString, data)?;
+        if private_key.len() != 32 {
+            return None;
         }
-        let bin: Vec<u8> = bin.iter().rev().map(|&s| s).collect();
 
-        for v in bin.iter() {
-            point = &point + &point;
-            if *v == 1 {
-                point = &point + &b;
-            }
+        Some(Ed25519::from_private_key(private_key.to_vec()))
+    }
+
+    // PROTOCOL.key private key section after the checkints
+    // string "ssh-ed25519", string public key, string seed || public key
+    pub fn from_ssh(mut data: &[u8]) -> Option<Self> {
+        if ssh::read_string(&mut data)? != b"ssh-ed25519" {
+            return None;
+        }
+        ssh::read_string(&mut data)?;
+        let private_key = ssh::read_string(&mut data)?;
+        if private_key.len() != 64 {
+            return None;
+        }
+
+        Some(Ed25519::from_private_key(private_key[..32].to_vec()))
+    }
+
+    // RFC 8037 section 2, "crv" Ed25519 with the seed in "d".
+    // "x" must be the public key of the seed.
+    pub fn from_jwk(jwk: &Jwk) -> Option<Self> {
+        if jwk.get("kty")? != "OKP" || jwk.get("crv")? != "Ed25519" {
+            return None;
+        }
+        let d = jwk.get_bytes("d")?;
+        if d.len() != 32 {
+            return None;
+        }
+        let key = Ed25519::from_private_key(d.to_vec());
+        if jwk.get_bytes("x")?[..] != key.gen_public_key()[..] {
+            return None;
+        }
+
+        Some(key)
+    }
+
+    pub fn gen_public_key(&self) -> Vec<u8> {
+        let (scalar, _) = self.expand();
+
+        Point::mul_base(&scalar).encode().to_vec()
+    }
+
+    // the public keys of many keys at once, faster than one by one
+    // as the points share a single field inversion
+    pub fn gen_public_keys(keys: &[Ed25519]) -> Vec<Vec<u8>> {
+        let points: Vec<Point> = keys.iter().map(|key| Point::mul_base(&key.expand().0)).collect();
+
+        Point::encode_batch(&points).iter().map(|x| x.to_vec()).collect()
+    }
+
+    // RFC 8032 section 5.1.5, the clamped scalar || the prefix of SHA-512(seed),
+    // how tor keeps an Ed25519 secret key
+    pub fn to_expanded(&self) -> Secret {
+        let (scalar, prefix) = self.expand();
+        let mut out = Secret::with_capacity(64);
+        out.extend(*scalar);
+        out.extend(*prefix);
+        out
+    }
+
+    // seed || public key, the 64 byte secret key of libsodium and of minisign and signify files
+    pub fn to_keypair(&self) -> Secret {
+        let mut out = Secret::with_capacity(64);
+        out.extend(&self.private_key);
+        out.extend(self.gen_public_key());
+        out
+    }
+
+    // the X25519 key with the same secret scalar, the first half of SHA-512(seed),
+    // as libsodium's crypto_sign_ed25519_sk_to_curve25519 makes it
+    pub fn to_x25519(&self) -> X25519 {
+        let (scalar, _) = self.expand();
+
+        X25519::from_private_key(scalar.to_vec())
+    }
+
+    // RFC 7748 section 4.1, the birational map u = (1 + y) / (1 - y) from edwards25519
+    // to curve25519, the X25519 public key of to_x25519.
+    // None when the public key is not a point or is the identity.
+    pub fn public_key_to_x25519(public_key: &[u8]) -> Option<Vec<u8>> {
+        // decode gives affine coordinates, z = 1
+        let y = Point::decode(public_key.try_into().ok()?)?.y;
+        let denominator = &FieldElement::ONE - &y;
+        if denominator.is_zero() {
+            return None;
+        }
+
+        Some((&(&FieldElement::ONE + &y) * &denominator.invert()).to_bytes().to_vec())
+    }
+
+    // RFC 8032 section 5.1.6, PureEdDSA
+    pub fn sign(&self, message: &[u8]) -> Vec<u8> {
+        self.sign_with(&[], message)
+    }
+
+    // Ed25519ctx, None unless the context is 1 to 255 bytes
+    pub fn sign_ctx(&self, message: &[u8], context: &[u8]) -> Option<Vec<u8>> {
+        if context.is_empty() {
+            return None;
+        }
+        Some(self.sign_with(&Ed25519::dom2(0, context)?, message))
+    }
+
+    // Ed25519ph, signs SHA-512(message); the context may be empty, None when it is longer than 255 bytes
+    pub fn sign_ph(&self, message: &[u8], context: &[u8]) -> Option<Vec<u8>> {
+        Some(self.sign_with(&Ed25519::dom2(1, context)?, &Sha512::digest(message)))
+    }
+
+    // RFC 8032 section 5.1.7
+    pub fn verify(public_key: &[u8], message: &[u8], signature: &[u8]) -> bool {
+        Ed25519::verify_with(public_key, &[], message, signature)
+    }
+
+    pub fn verify_ctx(public_key: &[u8], message: &[u8], context: &[u8], signature: &[u8]) -> bool {
+        match Ed25519::dom2(0, context) {
+            Some(dom) if !context.is_empty() => Ed25519::verify_with(public_key, &dom, message, signature),
+            _ => false,
+        }
+    }
+
+    pub fn verify_ph(public_key: &[u8], message: &[u8], context: &[u8], signature: &[u8]) -> bool {
+        match Ed25519::dom2(1, context) {
+            Some(dom) => Ed25519::verify_with(public_key, &dom, &Sha512::digest(message), signature),
+            None => false,
+        }
+    }
+
+    fn base_point() -> Point {
+        let x = FieldElement::from_bytes(&Ed25519::B.0);
+        let y = FieldElement::from_bytes(&Ed25519::B.1);
+        Point::from_xy(x, y)
+    }
+
+    // None when the context is longer than 255 bytes
+    fn dom2(phflag: u8, context: &[u8]) -> Option<Vec<u8>> {
+        if context.len() > 255 {
+            return None;
+        }
+        let mut out = Ed25519::DOM2.to_vec();
+        out.push(phflag);
+        out.push(context.len() as u8);
+        out.extend(context);
+
+        Some(out)
+    }
+
+    // RFC 8032 section 5.1.5
+    // returns the clamped secret scalar s and the prefix used for the nonces
+    fn expand(&self) -> (Zeroizing<[u8; 32]>, Zeroizing<[u8; 32]>) {
+        let mut hash = Sha512::new();
+        hash.update(&self.private_key);
+        let mut hash = hash.finalize();
+        let mut scalar = Zeroizing::new([0u8; 32]);
+        let mut prefix = Zeroizing::new([0u8; 32]);
+        scalar.copy_from_slice(&hash[..32]);
+        prefix.copy_from_slice(&hash[32..]);
+        hash.as_mut_slice().zeroize();
+        scalar[0] &= 0b1111_1000;
+        scalar[31] = scalar[31] & 0b0111_1111 | 0b0100_0000;
+
+        (scalar, prefix)
+    }
+
+    // SHA-512(dom || parts...) mod L
+    fn hash_to_scalar(dom: &[u8], parts: &[&[u8]]) -> Scalar {
+        let mut hash = Sha512::new();
+        hash.update(dom);
+        for part in parts.iter() {
+            hash.update(part);
         }
+        let mut digest = [0u8; 64];
+        digest.copy_from_slice(&hash.finalize());
+        let out = Scalar::from_bytes_wide(&digest);
+        digest.zeroize();
+
+        out
+    }
+
+    fn sign_with(&self, dom: &[u8], message: &[u8]) -> Vec<u8> {
+        let (scalar, prefix) = self.expand();
+        let public_key = Point::mul_base(&scalar).encode();
 
-        let mut out = point.y;
-        out[0] = out[0] | (point.x[31] & 0x80);
+        // r = SHA-512(dom2 || prefix || PH(M)), R = [r]B
+        let r = Ed25519::hash_to_scalar(dom, &[&prefix[..], message]);
+        let r_bytes = Zeroizing::new(r.to_bytes());
+        let big_r = Point::mul_base(&r_bytes).encode();
 
-        out.iter().rev().map(|&s| s).collect()
+        // k = SHA-512(dom2 || R || A || PH(M)), S = (r + k * s) mod L
+        let k = Ed25519::hash_to_scalar(dom, &[&big_r, &public_key, message]);
+        let s = Scalar::from_bytes(&scalar);
+        let big_s = &r + &(&k * &s);
+
+        let mut out = Vec::with_capacity(Ed25519::SIGNATURE_LENGTH);
+        out.extend(big_r);
+        out.extend(big_s.to_bytes());
+
+        out
     }
 
+    fn verify_with(public_key: &[u8], dom: &[u8], message: &[u8], signature: &[u8]) -> bool {
+        let public_key: [u8; 32] = match public_key.try_into() {
+            Ok(x) => x,
+            Err(_) => return false,
+        };
+        if signature.len() != Ed25519::SIGNATURE_LENGTH {
+            return false;
+        }
+        let big_r: [u8; 32] = signature[..32].try_into().unwrap();
+        let big_s: [u8; 32] = signature[32..].try_into().unwrap();
+
+        let a = match Point::decode(&public_key) {
+            Some(a) => a,
+            None => return false,
+        };
+        if Point::decode(&big_r).is_none() {
+            return false;
+        }
+        // S must be below L
+        if Scalar::from_bytes(&big_s).to_bytes() != big_s {
+            return false;
+        }
+
+        // [S]B - [k]A = R
+        let k = Ed25519::hash_to_scalar(dom, &[&big_r, &public_key, message]).to_bytes();
+        let check = &Point::mul_base(&big_s) + &-&a.mul(&k);
+
+        check.encode() == big_r
+    }
+}
+
+impl Default for Ed25519 {
+    fn default() -> Self {
+        Ed25519::new()
+    }
 }
 
 impl SshFormat for Ed25519 {
@@ -145,22 +648,23 @@ impl SshFormat for Ed25519 {
         out
     }
 
-    fn gen_private_key(&self, checkint: u32, comment: String) -> Vec<u8> {
+    fn gen_private_key(&self, checkint: u32, comment: String) -> Secret {
         let checkint = checkint.to_be_bytes();
-        let comment = comment.as_bytes().to_vec();
+        let comment = comment.as_bytes();
         let public_key = self.gen_public_key();
-        let mut ssh_private_key = self.private_key.clone();
-        ssh_private_key.extend(public_key.clone());
+        let mut ssh_private_key = Secret::with_capacity(64);
+        ssh_private_key.extend(&self.private_key);
+        ssh_private_key.extend(&public_key);
 
-        let mut out = Vec::new();
-        out.extend(checkint.clone());
+        let mut out = Secret::new();
+        out.extend(checkint);
         out.extend(checkint);
         out.extend(ssh::to_string(b"ssh-ed25519"));
         out.extend(ssh::to_string(&public_key));
-        out.extend(ssh::to_string(&ssh_private_key));
-        out.extend(ssh::to_string(&comment));
+        out.extend(ssh::to_string_secret(&ssh_private_key));
+        out.extend(ssh::to_string(comment));
         for i in 1..8 {
-            if out.len() % 8 == 0 {
+            if out.len().is_multiple_of(8) {
                 break;
             }
             out.push(i as u8);
@@ -168,11 +672,56 @@ impl SshFormat for Ed25519 {
 
         out
     }
+
+    // RFC 8709 section 6
+    fn gen_signature(&self, data: &[u8]) -> Option<Vec<u8>> {
+        let mut out = Vec::new();
+        out.extend(ssh::to_string(b"ssh-ed25519"));
+        out.extend(ssh::to_string(&self.sign(data)));
+
+        Some(out)
+    }
+}
+
+// RFC 8037 section 2
+impl JwkFormat for Ed25519 {
+    fn gen_members(&self) -> Vec<(&'static str, Secret)> {
+        vec![
+            ("kty", Secret::from(b"OKP".to_vec())),
+            ("crv", Secret::from(b"Ed25519".to_vec())),
+            ("x", Secret::from(pem::base64url(self.gen_public_key()))),
+            ("d", Secret::from(pem::base64url(&self.private_key))),
+        ]
+    }
+}
+
+// RFC 9580 section 5.5.5.5, EdDSALegacy
+impl OpenPgpFormat for Ed25519 {
+    fn pgp_algorithm(&self) -> u8 {
+        openpgp::EDDSA_LEGACY
+    }
+
+    // one-octet OID length, OID, MPI of 0x40 || public key
+    fn gen_public_fields(&self) -> Vec<u8> {
+        let mut point = vec![0x40];
+        point.extend(self.gen_public_key());
+
+        let mut out = vec![Ed25519::PGP_OID.len() as u8];
+        out.extend(Ed25519::PGP_OID);
+        out.extend(openpgp::mpi(&point));
+
+        out
+    }
+
+    // MPI of the seed
+    fn gen_secret_fields(&self) -> Secret {
+        openpgp::mpi_secret(&self.private_key)
+    }
 }
 
 impl DerEncode for Ed25519 {
-    fn to_der(&self) -> Vec<u8> {
-        der::encode(Tag::OctetString, self.private_key.clone())
+    fn to_der(&self) -> Secret {
+        der::encode_secret(Tag::OctetString, &self.private_key)
     }
 }
 
@@ -181,27 +730,18 @@ impl Pkcs8Format for Ed25519 {
         Ed25519::ID.to_vec()
     }
 
-    fn gen_private_key(&self) -> Vec<u8> {
+    fn gen_private_key(&self) -> Secret {
         self.to_der()
     }
 }
 
-fn inv(a: &BigUint, b:&BigUint) -> BigUint {
-    let a = BigInt::from_biguint(Sign::Plus, a.clone());
-    let b = BigInt::from_biguint(Sign::Plus, b.clone());
-    let mut x = gcd(&a, &b).0;
-    while x < BigInt::zero() {
-        x = x + &b;
+// RFC 8410 section 4, the public key goes into the BIT STRING as is
+impl SpkiFormat for Ed25519 {
+    fn gen_algorithm_identifier(&self) -> Vec<u8> {
+        Ed25519::ID.to_vec()
     }
-    x.to_biguint().unwrap()
-}
 
-fn gcd(a: &BigInt, b:&BigInt) -> (BigInt, BigInt) {
-    if *b == BigInt::zero() {
-        return (BigInt::one(), BigInt::zero());
+    fn gen_public_key(&self) -> Vec<u8> {
+        self.gen_public_key()
     }
-    let d = gcd(b, &(a%b));
-    let x = d.1;
-    let y = d.0 - (a/b)*&x;
-    (x, y)
 }
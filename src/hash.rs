@@ -0,0 +1,119 @@
+use sha2::Digest;
+use crate::der;
+use crate::der::Tag;
+use crate::secret::Secret;
+
+// hash functions named by RSASSA-PSS parameters
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum Hash {
+    Sha1,
+    Sha256,
+    Sha384,
+    Sha512,
+}
+
+impl Hash {
+    // RFC 8017 Appendix A.2.1
+    // id-sha1 OBJECT IDENTIFIER ::= { 1.3.14.3.2.26 }
+    // id-sha256 OBJECT IDENTIFIER ::= { 2.16.840.1.101.3.4.2.1 }
+    // id-sha384 OBJECT IDENTIFIER ::= { 2.16.840.1.101.3.4.2.2 }
+    // id-sha512 OBJECT IDENTIFIER ::= { 2.16.840.1.101.3.4.2.3 }
+    const SHA1: [u8; 5] = [0x2b,0x0e,0x03,0x02,0x1a];
+    const SHA256: [u8; 9] = [0x60,0x86,0x48,0x01,0x65,0x03,0x04,0x02,0x01];
+    const SHA384: [u8; 9] = [0x60,0x86,0x48,0x01,0x65,0x03,0x04,0x02,0x02];
+    const SHA512: [u8; 9] = [0x60,0x86,0x48,0x01,0x65,0x03,0x04,0x02,0x03];
+
+    fn oid(&self) -> &'static [u8] {
+        match self {
+            Hash::Sha1 => &Hash::SHA1,
+            Hash::Sha256 => &Hash::SHA256,
+            Hash::Sha384 => &Hash::SHA384,
+            Hash::Sha512 => &Hash::SHA512,
+        }
+    }
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            Hash::Sha1 => "sha1",
+            Hash::Sha256 => "sha256",
+            Hash::Sha384 => "sha384",
+            Hash::Sha512 => "sha512",
+        }
+    }
+
+    // digest length in bytes
+    pub fn output_len(&self) -> usize {
+        match self {
+            Hash::Sha1 => 20,
+            Hash::Sha256 => 32,
+            Hash::Sha384 => 48,
+            Hash::Sha512 => 64,
+        }
+    }
+
+    // input block length of the compression function in bytes
+    pub fn block_len(&self) -> usize {
+        match self {
+            Hash::Sha1 | Hash::Sha256 => 64,
+            Hash::Sha384 | Hash::Sha512 => 128,
+        }
+    }
+
+    // RFC 2104
+    // HMAC(K, m) = H((K' xor opad) || H((K' xor ipad) || m)), K' is K padded to the block length
+    pub fn hmac(&self, key: &[u8], data: &[u8]) -> Vec<u8> {
+        let mut k = Secret::with_capacity(self.block_len());
+        if key.len() > self.block_len() {
+            k.extend(self.digest(key));
+        } else {
+            k.extend(key);
+        }
+        while k.len() < self.block_len() {
+            k.push(0);
+        }
+
+        let mut inner = Secret::with_capacity(self.block_len() + data.len());
+        for x in k.iter() {
+            inner.push(x ^ 0x36);
+        }
+        inner.extend(data);
+        let mut outer = Secret::with_capacity(self.block_len() + self.output_len());
+        for x in k.iter() {
+            outer.push(x ^ 0x5c);
+        }
+        outer.extend(self.digest(&inner));
+
+        self.digest(&outer)
+    }
+
+    // SHA-1 is there for the formats that still name it, never for new signatures
+    pub fn digest(&self, data: &[u8]) -> Vec<u8> {
+        match self {
+            Hash::Sha1 => sha1::Sha1::digest(data).to_vec(),
+            Hash::Sha256 => sha2::Sha256::digest(data).to_vec(),
+            Hash::Sha384 => sha2::Sha384::digest(data).to_vec(),
+            Hash::Sha512 => sha2::Sha512::digest(data).to_vec(),
+        }
+    }
+
+    // RFC 8017 Appendix A.2.1
+    // HashAlgorithm ::= AlgorithmIdentifier, parameters are NULL
+    pub fn gen_algorithm_identifier(&self) -> Vec<u8> {
+        let mut value = der::encode(Tag::ObjectIdentifier, self.oid());
+        value.extend(der::encode(Tag::Null, []));
+
+        der::encode(Tag::Sequence, value)
+    }
+
+    // parameters may be NULL or absent (RFC 5754 section 2)
+    pub fn from_algorithm_identifier(data: &[u8]) -> Option<Self> {
+        let (data, _) = der::decode(Tag::Sequence, data)?;
+        let (oid, rest) = der::decode(Tag::ObjectIdentifier, data)?;
+        if !rest.is_empty() && rest != [0x05, 0x00] {
+            return None;
+        }
+
+        [Hash::Sha1, Hash::Sha256, Hash::Sha384, Hash::Sha512].into_iter()
+            .find(|hash| hash.oid() == oid)
+    }
+}
@@ -0,0 +1,254 @@
+// RFC 8259 values, enough for JWKs and JWT headers and claims.
+// Numbers keep their text, objects keep their member order.
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub enum Json {
+    Null,
+    Bool(bool),
+    Number(String),
+    String(String),
+    Array(Vec<Json>),
+    Object(Vec<(String, Json)>),
+}
+
+impl Json {
+    // the whole input is one value, whitespace around it allowed
+    pub fn parse(data: &str) -> Option<Json> {
+        let mut rest = data.as_bytes();
+        let value = parse_value(&mut rest, 0)?;
+        skip_whitespace(&mut rest);
+        if !rest.is_empty() {
+            return None;
+        }
+
+        Some(value)
+    }
+
+    // member of an object, the first one when a name is repeated
+    pub fn get(&self, name: &str) -> Option<&Json> {
+        match self {
+            Json::Object(members) => members.iter().find(|(x, _)| x == name).map(|(_, v)| v),
+            _ => None,
+        }
+    }
+
+    pub fn as_str(&self) -> Option<&str> {
+        match self {
+            Json::String(x) => Some(x),
+            _ => None,
+        }
+    }
+
+    // a number without fraction or exponent, as in NumericDate claims
+    pub fn as_u64(&self) -> Option<u64> {
+        match self {
+            Json::Number(x) => x.parse().ok(),
+            _ => None,
+        }
+    }
+
+    // compact, no whitespace between tokens
+    pub fn to_json(&self) -> String {
+        match self {
+            Json::Null => "null".to_string(),
+            Json::Bool(x) => x.to_string(),
+            Json::Number(x) => x.clone(),
+            Json::String(x) => quote(x),
+            Json::Array(values) => format!("[{}]", values.iter().map(|x| x.to_json()).collect::<Vec<String>>().join(",")),
+            Json::Object(members) => format!("{{{}}}", members.iter().map(|(k, v)| format!("{}:{}", quote(k), v.to_json())).collect::<Vec<String>>().join(",")),
+        }
+    }
+}
+
+// RFC 8259 section 7, only '"', '\' and control characters are escaped
+pub fn quote(data: &str) -> String {
+    let mut out = String::with_capacity(data.len() + 2);
+    out.push('"');
+    for c in data.chars() {
+        match c {
+            '"' => out.push_str("\\\""),
+            '\\' => out.push_str("\\\\"),
+            '\n' => out.push_str("\\n"),
+            '\r' => out.push_str("\\r"),
+            '\t' => out.push_str("\\t"),
+            c if (c as u32) < 0x20 => out.push_str(&format!("\\u{:04x}", c as u32)),
+            c => out.push(c),
+        }
+    }
+    out.push('"');
+    out
+}
+
+// nesting beyond this is refused rather than overflowing the stack
+const MAX_DEPTH: usize = 64;
+
+fn skip_whitespace(data: &mut &[u8]) {
+    while let [b' ' | b'\t' | b'\r' | b'\n', rest @ ..] = data {
+        *data = rest;
+    }
+}
+
+fn parse_value(data: &mut &[u8], depth: usize) -> Option<Json> {
+    if depth > MAX_DEPTH {
+        return None;
+    }
+    skip_whitespace(data);
+    match data.first()? {
+        b'{' => {
+            *data = &data[1..];
+            let mut members = Vec::new();
+            skip_whitespace(data);
+            if let Some(rest) = data.strip_prefix(b"}") {
+                *data = rest;
+                return Some(Json::Object(members));
+            }
+            loop {
+                skip_whitespace(data);
+                let name = parse_string(data)?;
+                skip_whitespace(data);
+                *data = data.strip_prefix(b":")?;
+                members.push((name, parse_value(data, depth + 1)?));
+                skip_whitespace(data);
+                match data.first()? {
+                    b',' => *data = &data[1..],
+                    b'}' => {
+                        *data = &data[1..];
+                        return Some(Json::Object(members));
+                    },
+                    _ => return None,
+                }
+            }
+        },
+        b'[' => {
+            *data = &data[1..];
+            let mut values = Vec::new();
+            skip_whitespace(data);
+            if let Some(rest) = data.strip_prefix(b"]") {
+                *data = rest;
+                return Some(Json::Array(values));
+            }
+            loop {
+                values.push(parse_value(data, depth + 1)?);
+                skip_whitespace(data);
+                match data.first()? {
+                    b',' => *data = &data[1..],
+                    b']' => {
+                        *data = &data[1..];
+                        return Some(Json::Array(values));
+                    },
+                    _ => return None,
+                }
+            }
+        },
+        b'"' => Some(Json::String(parse_string(data)?)),
+        b't' => {
+            *data = data.strip_prefix(b"true")?;
+            Some(Json::Bool(true))
+        },
+        b'f' => {
+            *data = data.strip_prefix(b"false")?;
+            Some(Json::Bool(false))
+        },
+        b'n' => {
+            *data = data.strip_prefix(b"null")?;
+            Some(Json::Null)
+        },
+        _ => parse_number(data),
+    }
+}
+
+// RFC 8259 section 6
+// number = [ minus ] int [ frac ] [ exp ]
+fn parse_number(data: &mut &[u8]) -> Option<Json> {
+    let mut i = 0;
+    let digits = |data: &[u8], i: &mut usize| {
+        let start = *i;
+        while data.get(*i).is_some_and(|x| x.is_ascii_digit()) {
+            *i += 1;
+        }
+        *i > start
+    };
+
+    if data.first() == Some(&b'-') {
+        i += 1;
+    }
+    match data.get(i)? {
+        b'0' => i += 1,
+        b'1'..=b'9' => {
+            digits(data, &mut i);
+        },
+        _ => return None,
+    }
+    if data.get(i) == Some(&b'.') {
+        i += 1;
+        if !digits(data, &mut i) {
+            return None;
+        }
+    }
+    if matches!(data.get(i), Some(b'e' | b'E')) {
+        i += 1;
+        if matches!(data.get(i), Some(b'+' | b'-')) {
+            i += 1;
+        }
+        if !digits(data, &mut i) {
+            return None;
+        }
+    }
+
+    let number = std::str::from_utf8(&data[..i]).ok()?.to_string();
+    *data = &data[i..];
+    Some(Json::Number(number))
+}
+
+// RFC 8259 section 7, with surrogate pairs for characters beyond the BMP
+fn parse_string(data: &mut &[u8]) -> Option<String> {
+    *data = data.strip_prefix(b"\"")?;
+    let mut out = Vec::new();
+    loop {
+        let (&c, rest) = data.split_first()?;
+        *data = rest;
+        match c {
+            b'"' => return String::from_utf8(out).ok(),
+            b'\\' => {
+                let (&e, rest) = data.split_first()?;
+                *data = rest;
+                let c = match e {
+                    b'"' => '"',
+                    b'\\' => '\\',
+                    b'/' => '/',
+                    b'b' => '\u{8}',
+                    b'f' => '\u{c}',
+                    b'n' => '\n',
+                    b'r' => '\r',
+                    b't' => '\t',
+                    b'u' => {
+                        let high = parse_hex4(data)?;
+                        if (0xd800..0xdc00).contains(&high) {
+                            *data = data.strip_prefix(b"\\u")?;
+                            let low = parse_hex4(data)?;
+                            if !(0xdc00..0xe000).contains(&low) {
+                                return None;
+                            }
+                            char::from_u32(0x10000 + ((high - 0xd800) << 10) + (low - 0xdc00))?
+                        } else {
+                            char::from_u32(high)?
+                        }
+                    },
+                    _ => return None,
+                };
+                out.extend(c.to_string().as_bytes());
+            },
+            0x00..=0x1f => return None,
+            _ => out.push(c),
+        }
+    }
+}
+
+fn parse_hex4(data: &mut &[u8]) -> Option<u32> {
+    let hex = std::str::from_utf8(data.get(..4)?).ok()?;
+    let value = u32::from_str_radix(hex, 16).ok()?;
+    if !hex.bytes().all(|x| x.is_ascii_hexdigit()) {
+        return None;
+    }
+    *data = &data[4..];
+    Some(value)
+}
@@ -0,0 +1,166 @@
+use crate::hash::Hash;
+use crate::json;
+use crate::json::Json;
+use crate::pem;
+use crate::secret::Secret;
+
+pub trait JwkFormat {
+    // "kty" first, then the members of the key type (RFC 7518 section 6, RFC 8037 section 2),
+    // the public ones before the private ones.
+    // Binary values are base64url without padding.
+    fn gen_members(&self) -> Vec<(&'static str, Secret)>;
+}
+
+// RFC 7517 section 4, a key as a JSON object.
+// Only members with string values are kept, which is all RSA and OKP keys have.
+pub struct Jwk {
+    members: Vec<(String, Secret)>,
+}
+
+impl Jwk {
+    // RFC 7517 section 9.2, members that only a private key has
+    const PRIVATE: [&'static str; 7] = ["d", "p", "q", "dp", "dq", "qi", "oth"];
+
+    pub fn new(key: &dyn JwkFormat) -> Self {
+        Jwk{
+            members: key.gen_members().into_iter().map(|(k, v)| (k.to_string(), v)).collect(),
+        }
+    }
+
+    // the public key alone, as published in a JWKS
+    pub fn without_private_members(mut self) -> Self {
+        self.members.retain(|(k, _)| !Jwk::PRIVATE.contains(&k.as_str()));
+        self
+    }
+
+    // RFC 7517 section 4.2, "sig" or "enc"
+    pub fn with_use(self, key_use: &str) -> Self {
+        self.with_member("use", key_use)
+    }
+
+    // RFC 7517 section 4.4, the algorithm the key is meant for, e.g. "EdDSA" or "RS256"
+    pub fn with_alg(self, alg: &str) -> Self {
+        self.with_member("alg", alg)
+    }
+
+    // RFC 7517 section 4.5
+    pub fn with_kid(self, kid: &str) -> Self {
+        self.with_member("kid", kid)
+    }
+
+    // replaces the member if there is one already
+    fn with_member(mut self, name: &str, value: &str) -> Self {
+        self.members.retain(|(k, _)| k != name);
+        self.members.push((name.to_string(), Secret::from(value.as_bytes().to_vec())));
+        self
+    }
+
+    // value of a string member
+    pub fn get(&self, name: &str) -> Option<&str> {
+        let (_, value) = self.members.iter().find(|(k, _)| k == name)?;
+        std::str::from_utf8(value).ok()
+    }
+
+    // value of a base64url member, such as "n" or "d"
+    pub fn get_bytes(&self, name: &str) -> Option<Secret> {
+        pem::base64url_decode(self.get(name)?.as_bytes())
+    }
+
+    // RFC 7638 section 3, base64url of the SHA-256 over the required members
+    // in lexicographic order, without whitespace.
+    // None for a key type without a thumbprint definition or with a required member missing.
+    pub fn thumbprint(&self) -> Option<String> {
+        let required: &[&str] = match self.get("kty")? {
+            "RSA" => &["e", "kty", "n"],
+            "OKP" => &["crv", "kty", "x"],
+            "EC" => &["crv", "kty", "x", "y"],
+            _ => return None,
+        };
+        let mut members = Vec::new();
+        for name in required.iter() {
+            members.push((name.to_string(), Json::String(self.get(name)?.to_string())));
+        }
+        let digest = Hash::Sha256.digest(Json::Object(members).to_json().as_bytes());
+
+        String::from_utf8(pem::base64url(digest)).ok()
+    }
+
+    // a JSON object with string members, others are skipped
+    pub fn from_json(data: &str) -> Option<Self> {
+        Jwk::from_value(&Json::parse(data)?)
+    }
+
+    fn from_value(value: &Json) -> Option<Self> {
+        let members = match value {
+            Json::Object(members) => members,
+            _ => return None,
+        };
+        let members: Vec<(String, Secret)> = members.iter()
+            .filter_map(|(k, v)| Some((k.clone(), Secret::from(v.as_str()?.as_bytes().to_vec()))))
+            .collect();
+        // RFC 7517 section 4.1, "kty" MUST be present
+        members.iter().find(|(k, _)| k == "kty")?;
+
+        Some(Jwk{
+            members,
+        })
+    }
+
+    // compact JSON, members in the order they were added
+    pub fn to_json(&self) -> Secret {
+        let mut out = Secret::new();
+        out.push(b'{');
+        for (i, (name, value)) in self.members.iter().enumerate() {
+            if i > 0 {
+                out.push(b',');
+            }
+            out.extend(json::quote(name));
+            out.push(b':');
+            // base64url goes in as it is, so private members are never copied outside a Secret
+            if value.iter().all(|c| c.is_ascii_graphic() && *c != b'"' && *c != b'\\') {
+                out.push(b'"');
+                out.extend(value);
+                out.push(b'"');
+            } else {
+                out.extend(json::quote(std::str::from_utf8(value).unwrap()));
+            }
+        }
+        out.push(b'}');
+
+        out
+    }
+}
+
+// RFC 7517 section 5, {"keys":[...]}
+pub fn jwks(keys: &[Jwk]) -> Secret {
+    let mut out = Secret::new();
+    out.extend(b"{\"keys\":[");
+    for (i, key) in keys.iter().enumerate() {
+        if i > 0 {
+            out.push(b',');
+        }
+        out.extend(key.to_json());
+    }
+    out.extend(b"]}");
+
+    out
+}
+
+// the keys of a JWKS, or a single JWK on its own
+pub fn read_jwks(data: &str) -> Option<Vec<Jwk>> {
+    let value = Json::parse(data)?;
+    match value.get("keys") {
+        Some(Json::Array(keys)) => keys.iter().map(Jwk::from_value).collect(),
+        Some(_) => None,
+        None => Some(vec![Jwk::from_value(&value)?]),
+    }
+}
+
+// RFC 7518 section 2 Base64urlUInt, the unsigned big-endian value in as few octets as possible.
+// Zero, as no bytes or only zero bytes, is the single octet 0.
+pub fn base64url_uint(data: &[u8]) -> Secret {
+    match data.iter().position(|x| *x != 0) {
+        Some(start) => Secret::from(pem::base64url(&data[start..])),
+        None => Secret::from(pem::base64url([0u8])),
+    }
+}
@@ -0,0 +1,172 @@
+use crate::pem;
+use crate::json::Json;
+use crate::hash::Hash;
+use crate::rsa::{RsaAlgorithm, PssParams};
+use crate::ed25519::Ed25519;
+use crate::key::{Key, PublicKey};
+
+// RFC 7518 section 3.1 and RFC 8037 section 3.1 "alg" values.
+// "none" and the HMAC algorithms are left out on purpose, a JWS here is always signed by a key.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum JwsAlgorithm {
+    EdDsa,
+    Rs256,
+    Ps256,
+}
+
+impl JwsAlgorithm {
+    pub fn name(&self) -> &'static str {
+        match self {
+            JwsAlgorithm::EdDsa => "EdDSA",
+            JwsAlgorithm::Rs256 => "RS256",
+            JwsAlgorithm::Ps256 => "PS256",
+        }
+    }
+
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name {
+            "EdDSA" => Some(JwsAlgorithm::EdDsa),
+            "RS256" => Some(JwsAlgorithm::Rs256),
+            "PS256" => Some(JwsAlgorithm::Ps256),
+            _ => None,
+        }
+    }
+
+    // EdDSA for ed25519, PS256 for an rsa key restricted to PSS, RS256 for any other rsa key
+    pub fn for_key(key: &Key) -> Self {
+        match key {
+            Key::Ed25519(_) => JwsAlgorithm::EdDsa,
+            Key::Rsa(key) if key.algorithm() != RsaAlgorithm::Rsa => JwsAlgorithm::Ps256,
+            Key::Rsa(_) => JwsAlgorithm::Rs256,
+        }
+    }
+
+    // RFC 7518 section 3.3 and 3.5, rsa keys of 2048 bits or more;
+    // PS256 is SHA-256 with MGF1 SHA-256 and a 32 byte salt
+    pub fn fits(&self, key: &PublicKey) -> bool {
+        match (self, key) {
+            (JwsAlgorithm::EdDsa, PublicKey::Ed25519(_)) => true,
+            (JwsAlgorithm::Rs256, PublicKey::Rsa(key)) => key.bits() >= 2048 && key.algorithm() == RsaAlgorithm::Rsa,
+            (JwsAlgorithm::Ps256, PublicKey::Rsa(key)) => key.bits() >= 2048 && key.algorithm().allows_pss(&JwsAlgorithm::pss_params()),
+            _ => false,
+        }
+    }
+
+    fn pss_params() -> PssParams {
+        PssParams::new(Hash::Sha256, Hash::Sha256, 32)
+    }
+}
+
+// RFC 7515 section 7.1, a JWS in the compact serialization:
+// BASE64URL(UTF8(JWS Protected Header)) || '.' || BASE64URL(JWS Payload) || '.' || BASE64URL(JWS Signature)
+pub struct Jws {
+    encoded_header: String,
+    encoded_payload: String,
+    header: Json,
+    payload: Vec<u8>,
+    signature: Vec<u8>,
+}
+
+impl Jws {
+    // header holds the members besides "alg", e.g. ("typ", "JWT") or ("kid", ...).
+    // None when the algorithm does not fit the key.
+    pub fn new(key: &Key, algorithm: JwsAlgorithm, header: &[(&str, &str)], payload: &[u8]) -> Option<Self> {
+        if !algorithm.fits(&key.public_key()) {
+            return None;
+        }
+        assert!(header.iter().all(|(k, _)| *k != "alg"), "alg is set from the algorithm");
+        let mut members = vec![("alg".to_string(), Json::String(algorithm.name().to_string()))];
+        members.extend(header.iter().map(|(k, v)| (k.to_string(), Json::String(v.to_string()))));
+        let header = Json::Object(members);
+
+        let encoded_header = String::from_utf8(pem::base64url(header.to_json())).unwrap();
+        let encoded_payload = String::from_utf8(pem::base64url(payload)).unwrap();
+        let signing_input = format!("{}.{}", encoded_header, encoded_payload);
+        let signature = match (key, algorithm) {
+            (Key::Ed25519(key), JwsAlgorithm::EdDsa) => key.sign(signing_input.as_bytes()),
+            (Key::Rsa(key), JwsAlgorithm::Rs256) => key.sign_pkcs1_v1_5(Hash::Sha256, signing_input.as_bytes()),
+            (Key::Rsa(key), JwsAlgorithm::Ps256) => key.sign_pss(&JwsAlgorithm::pss_params(), signing_input.as_bytes()),
+            _ => unreachable!(),
+        };
+
+        Some(Jws{
+            encoded_header,
+            encoded_payload,
+            header,
+            payload: payload.to_vec(),
+            signature,
+        })
+    }
+
+    // three base64url parts; the header must be a JSON object with a string "alg"
+    pub fn from_compact(data: &str) -> Option<Self> {
+        let mut parts = data.trim().split('.');
+        let (encoded_header, encoded_payload, encoded_signature) = (parts.next()?, parts.next()?, parts.next()?);
+        if parts.next().is_some() {
+            return None;
+        }
+        let header = Json::parse(std::str::from_utf8(&pem::base64url_decode(encoded_header.as_bytes())?).ok()?)?;
+        header.get("alg")?.as_str()?;
+        let payload = pem::base64url_decode(encoded_payload.as_bytes())?.to_vec();
+        let signature = pem::base64url_decode(encoded_signature.as_bytes())?.to_vec();
+
+        Some(Jws{
+            encoded_header: encoded_header.to_string(),
+            encoded_payload: encoded_payload.to_string(),
+            header,
+            payload,
+            signature,
+        })
+    }
+
+    pub fn to_compact(&self) -> String {
+        format!("{}.{}.{}", self.encoded_header, self.encoded_payload, String::from_utf8(pem::base64url(&self.signature)).unwrap())
+    }
+
+    // the JOSE header, not yet verified
+    pub fn header(&self) -> &Json {
+        &self.header
+    }
+
+    // the payload, not yet verified
+    pub fn payload(&self) -> &[u8] {
+        &self.payload
+    }
+
+    // RFC 7515 section 5.2, the "alg" of the header must be one this key can sign with.
+    // "none" and unknown algorithms are never accepted, nor is a header with "crit" (section 4.1.11).
+    pub fn verify(&self, key: &PublicKey) -> bool {
+        let algorithm = match self.header.get("alg").and_then(|x| x.as_str()).and_then(JwsAlgorithm::from_name) {
+            Some(algorithm) => algorithm,
+            None => return false,
+        };
+        if !algorithm.fits(key) || self.header.get("crit").is_some() {
+            return false;
+        }
+
+        let signing_input = format!("{}.{}", self.encoded_header, self.encoded_payload);
+        match (key, algorithm) {
+            (PublicKey::Ed25519(key), JwsAlgorithm::EdDsa) => Ed25519::verify(key, signing_input.as_bytes(), &self.signature),
+            (PublicKey::Rsa(key), JwsAlgorithm::Rs256) => key.verify_pkcs1_v1_5(Hash::Sha256, signing_input.as_bytes(), &self.signature),
+            (PublicKey::Rsa(key), JwsAlgorithm::Ps256) => key.verify_pss(&JwsAlgorithm::pss_params(), signing_input.as_bytes(), &self.signature),
+            _ => false,
+        }
+    }
+}
+
+// RFC 7519 section 7.1 step 2: a JWT Claims Set is a JSON object, written compact
+pub fn claims(data: &str) -> Option<String> {
+    let claims = Json::parse(data)?;
+    match claims {
+        Json::Object(_) => Some(claims.to_json()),
+        _ => None,
+    }
+}
+
+// RFC 7519 section 4.1.4 and 4.1.5, "exp" and "nbf" against now, without leeway.
+// A claim that is not a whole number of seconds makes the token invalid.
+pub fn check_time(claims: &Json, now: u64) -> bool {
+    let exp = claims.get("exp").is_none_or(|x| x.as_u64().is_some_and(|x| now < x));
+    let nbf = claims.get("nbf").is_none_or(|x| x.as_u64().is_some_and(|x| now >= x));
+    exp && nbf
+}
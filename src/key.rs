@@ -0,0 +1,283 @@
+use crate::pem;
+use crate::pkcs8;
+use crate::spki;
+use crate::spki::{Spki, SpkiFormat};
+use crate::dns::DnskeyFormat;
+use crate::ssh;
+use crate::jwk::{Jwk, JwkFormat};
+use crate::openpgp::OpenPgpFormat;
+use crate::secret::Secret;
+use crate::hash::Hash;
+use crate::rsa::{Rsa2048, RsaAlgorithm, RsaPublicKey};
+use crate::ed25519::Ed25519;
+
+// private key read back from a file written by genkey (or by OpenSSL / ssh-keygen)
+#[allow(clippy::large_enum_variant)]
+pub enum Key {
+    Rsa(Rsa2048),
+    Ed25519(Ed25519),
+}
+
+// public key read back from a SubjectPublicKeyInfo file
+pub enum PublicKey {
+    Rsa(RsaPublicKey),
+    Ed25519(Vec<u8>),
+}
+
+impl Key {
+    // PKCS #8 "PRIVATE KEY", PKCS #1 "RSA PRIVATE KEY"
+    // or unencrypted "OPENSSH PRIVATE KEY"
+    pub fn from_pem(data: &[u8]) -> Option<Self> {
+        let (label, body) = pem::decapsulate(data)?;
+        match &label[..] {
+            b"PRIVATE KEY" => {
+                let (algorithm_identifier, private_key) = pkcs8::read_private_key(&body)?;
+                if algorithm_identifier == Ed25519::ID {
+                    Some(Key::Ed25519(Ed25519::from_der(private_key)?))
+                } else {
+                    let algorithm = RsaAlgorithm::from_algorithm_identifier(algorithm_identifier)?;
+                    let fips = pkcs8::read_attributes(&body)?.contains(&&Rsa2048::FIPS_ATTRIBUTE[..]);
+                    Some(Key::Rsa(Rsa2048::from_der(private_key)?.with_algorithm(algorithm).with_fips(fips)))
+                }
+            },
+            b"RSA PRIVATE KEY" => Some(Key::Rsa(Rsa2048::from_der(&body)?)),
+            b"OPENSSH PRIVATE KEY" => Key::from_ssh(ssh::read_private_key(&body)?),
+            _ => None,
+        }
+    }
+
+    // PROTOCOL.key private key section after the checkints, "ssh-rsa" or "ssh-ed25519"
+    pub fn from_ssh(data: &[u8]) -> Option<Self> {
+        let mut key_type = data;
+        match ssh::read_string(&mut key_type)? {
+            b"ssh-rsa" => Some(Key::Rsa(Rsa2048::from_ssh(data)?)),
+            b"ssh-ed25519" => Some(Key::Ed25519(Ed25519::from_ssh(data)?)),
+            _ => None,
+        }
+    }
+
+    // RFC 7517, "kty" RSA or OKP with "crv" Ed25519
+    pub fn from_jwk(jwk: &Jwk) -> Option<Self> {
+        match jwk.get("kty")? {
+            "RSA" => Some(Key::Rsa(Rsa2048::from_jwk(jwk)?)),
+            "OKP" => Some(Key::Ed25519(Ed25519::from_jwk(jwk)?)),
+            _ => None,
+        }
+    }
+
+    pub fn public_key(&self) -> PublicKey {
+        match self {
+            Key::Rsa(key) => PublicKey::Rsa(key.public_key()),
+            Key::Ed25519(key) => PublicKey::Ed25519(key.gen_public_key()),
+        }
+    }
+
+    pub fn to_spki(&self) -> Spki {
+        match self {
+            Key::Rsa(key) => Spki::new(key),
+            Key::Ed25519(key) => Spki::new(key),
+        }
+    }
+
+    // one "name: value" line per property
+    pub fn inspect(&self) -> String {
+        match self {
+            Key::Rsa(key) => format!(
+                "algorithm: rsa\nbits: {}\nprimes: {}\nvalid: {}\ngenerated in fips 186-5 mode: {}\n{}",
+                key.bits(),
+                key.primes(),
+                if key.validate() { "yes" } else { "no" },
+                if key.is_fips() { "yes" } else { "no" },
+                inspect_algorithm(key.algorithm()),
+            ),
+            Key::Ed25519(_) => "algorithm: ed25519\nbits: 256\n".to_string(),
+        }
+    }
+}
+
+impl PublicKey {
+    // "PUBLIC KEY", RFC 5280 SubjectPublicKeyInfo
+    pub fn from_pem(data: &[u8]) -> Option<Self> {
+        let (label, body) = pem::decapsulate(data)?;
+        if label != b"PUBLIC KEY" {
+            return None;
+        }
+        let (algorithm_identifier, public_key) = spki::read_public_key(&body)?;
+        if algorithm_identifier == Ed25519::ID {
+            if public_key.len() != 32 {
+                return None;
+            }
+            Some(PublicKey::Ed25519(public_key.to_vec()))
+        } else {
+            let algorithm = RsaAlgorithm::from_algorithm_identifier(algorithm_identifier)?;
+            Some(PublicKey::Rsa(RsaPublicKey::from_der(public_key, algorithm)?))
+        }
+    }
+
+    // RFC 4253 section 6.6 public key blob, "ssh-rsa" or "ssh-ed25519"
+    pub fn from_ssh(data: &[u8]) -> Option<Self> {
+        let mut rest = data;
+        match ssh::read_string(&mut rest)? {
+            b"ssh-rsa" => Some(PublicKey::Rsa(RsaPublicKey::from_ssh(data)?)),
+            b"ssh-ed25519" => {
+                let public_key = ssh::read_string(&mut rest)?;
+                if public_key.len() != 32 || !rest.is_empty() {
+                    return None;
+                }
+                Some(PublicKey::Ed25519(public_key.to_vec()))
+            },
+            _ => None,
+        }
+    }
+
+    // public members only, private ones are ignored
+    pub fn from_jwk(jwk: &Jwk) -> Option<Self> {
+        match (jwk.get("kty")?, jwk.get("crv")) {
+            ("RSA", _) => Some(PublicKey::Rsa(RsaPublicKey::from_jwk(jwk)?)),
+            ("OKP", Some("Ed25519")) => {
+                let public_key = jwk.get_bytes("x")?;
+                if public_key.len() != 32 {
+                    return None;
+                }
+                Some(PublicKey::Ed25519(public_key.to_vec()))
+            },
+            _ => None,
+        }
+    }
+
+    pub fn to_spki(&self) -> Spki {
+        Spki::new(self)
+    }
+
+    // inverse of from_ssh
+    pub fn to_ssh(&self) -> Vec<u8> {
+        match self {
+            PublicKey::Rsa(key) => key.to_ssh(),
+            PublicKey::Ed25519(key) => {
+                let mut out = Vec::new();
+                out.extend(ssh::to_string(b"ssh-ed25519"));
+                out.extend(ssh::to_string(key));
+                out
+            },
+        }
+    }
+
+    // checks an SSH signature blob over data,
+    // rsa-sha2-256 or rsa-sha2-512 (RFC 8332) and ssh-ed25519 (RFC 8709)
+    pub fn verify_ssh(&self, data: &[u8], mut signature: &[u8]) -> bool {
+        let (format, blob) = match (ssh::read_string(&mut signature), ssh::read_string(&mut signature)) {
+            (Some(format), Some(blob)) if signature.is_empty() => (format, blob),
+            _ => return false,
+        };
+        match (self, format) {
+            (PublicKey::Rsa(key), b"rsa-sha2-256") => key.verify_pkcs1_v1_5(Hash::Sha256, data, blob),
+            (PublicKey::Rsa(key), b"rsa-sha2-512") => key.verify_pkcs1_v1_5(Hash::Sha512, data, blob),
+            (PublicKey::Ed25519(key), b"ssh-ed25519") => Ed25519::verify(key, data, blob),
+            _ => false,
+        }
+    }
+
+    // same lines as Key::inspect, where they apply to a public key
+    pub fn inspect(&self) -> String {
+        match self {
+            PublicKey::Rsa(key) => format!(
+                "algorithm: rsa\nbits: {}\n{}",
+                key.bits(),
+                inspect_algorithm(key.algorithm()),
+            ),
+            PublicKey::Ed25519(_) => "algorithm: ed25519\nbits: 256\n".to_string(),
+        }
+    }
+}
+
+impl JwkFormat for Key {
+    fn gen_members(&self) -> Vec<(&'static str, Secret)> {
+        match self {
+            Key::Rsa(key) => key.gen_members(),
+            Key::Ed25519(key) => key.gen_members(),
+        }
+    }
+}
+
+impl JwkFormat for PublicKey {
+    fn gen_members(&self) -> Vec<(&'static str, Secret)> {
+        match self {
+            PublicKey::Rsa(key) => key.gen_members(),
+            PublicKey::Ed25519(key) => vec![
+                ("kty", Secret::from(b"OKP".to_vec())),
+                ("crv", Secret::from(b"Ed25519".to_vec())),
+                ("x", Secret::from(pem::base64url(key))),
+            ],
+        }
+    }
+}
+
+impl SpkiFormat for PublicKey {
+    fn gen_algorithm_identifier(&self) -> Vec<u8> {
+        match self {
+            PublicKey::Rsa(key) => key.gen_algorithm_identifier(),
+            PublicKey::Ed25519(_) => Ed25519::ID.to_vec(),
+        }
+    }
+
+    fn gen_public_key(&self) -> Vec<u8> {
+        match self {
+            PublicKey::Rsa(key) => SpkiFormat::gen_public_key(key),
+            PublicKey::Ed25519(key) => key.clone(),
+        }
+    }
+}
+
+// RFC 8080 section 3, Ed25519 is algorithm 15 with the raw public key
+impl DnskeyFormat for PublicKey {
+    fn dnssec_algorithm(&self) -> Option<u8> {
+        match self {
+            PublicKey::Rsa(key) => key.dnssec_algorithm(),
+            PublicKey::Ed25519(_) => Some(15),
+        }
+    }
+
+    fn gen_dnskey(&self) -> Vec<u8> {
+        match self {
+            PublicKey::Rsa(key) => key.gen_dnskey(),
+            PublicKey::Ed25519(key) => key.clone(),
+        }
+    }
+}
+
+impl OpenPgpFormat for Key {
+    fn pgp_algorithm(&self) -> u8 {
+        match self {
+            Key::Rsa(key) => key.pgp_algorithm(),
+            Key::Ed25519(key) => key.pgp_algorithm(),
+        }
+    }
+
+    fn gen_public_fields(&self) -> Vec<u8> {
+        match self {
+            Key::Rsa(key) => key.gen_public_fields(),
+            Key::Ed25519(key) => key.gen_public_fields(),
+        }
+    }
+
+    fn gen_secret_fields(&self) -> Secret {
+        match self {
+            Key::Rsa(key) => key.gen_secret_fields(),
+            Key::Ed25519(key) => key.gen_secret_fields(),
+        }
+    }
+}
+
+// only id-RSASSA-PSS keys get a line
+fn inspect_algorithm(algorithm: RsaAlgorithm) -> String {
+    match algorithm {
+        RsaAlgorithm::Rsa => String::new(),
+        RsaAlgorithm::Pss(None) => "pss: any parameters\n".to_string(),
+        RsaAlgorithm::Pss(Some(params)) => format!(
+            "pss: {}, mgf1 {}, salt length {}\n",
+            params.hash().name(),
+            params.mgf1_hash().name(),
+            params.salt_length(),
+        ),
+    }
+}
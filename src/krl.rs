@@ -0,0 +1,350 @@
+use std::collections::BTreeSet;
+use num_bigint::BigUint;
+use crate::pem;
+use crate::ssh;
+use crate::ssh::SshFormat;
+use crate::hash::Hash;
+use crate::key::PublicKey;
+use crate::sshcert::SshCertificate;
+
+// PROTOCOL.krl
+pub struct Krl {
+    version: u64,
+    generated_date: u64,
+    comment: String,
+    certificates: Vec<RevokedCertificates>,
+    keys: BTreeSet<Vec<u8>>,
+    // only read from KRLs made elsewhere, genkey revokes by SHA-256
+    sha1: BTreeSet<Vec<u8>>,
+    sha256: BTreeSet<Vec<u8>>,
+}
+
+// certificates of one CA, an empty CA key stands for any CA (key IDs only)
+struct RevokedCertificates {
+    ca_key: Vec<u8>,
+    // sorted, disjoint, inclusive ranges
+    serials: Vec<(u64, u64)>,
+    key_ids: BTreeSet<String>,
+}
+
+impl Krl {
+    const MAGIC: [u8; 8] = *b"SSHKRL\n\0";
+    const FORMAT_VERSION: u32 = 1;
+
+    const SECTION_CERTIFICATES: u8 = 1;
+    const SECTION_EXPLICIT_KEY: u8 = 2;
+    const SECTION_FINGERPRINT_SHA1: u8 = 3;
+    const SECTION_SIGNATURE: u8 = 4;
+    const SECTION_FINGERPRINT_SHA256: u8 = 5;
+
+    const CERT_SERIAL_LIST: u8 = 0x20;
+    const CERT_SERIAL_RANGE: u8 = 0x21;
+    const CERT_SERIAL_BITMAP: u8 = 0x22;
+    const CERT_KEY_ID: u8 = 0x23;
+
+    // an empty KRL, generated_date in seconds since the epoch
+    pub fn new(version: u64, generated_date: u64, comment: &str) -> Self {
+        Krl{
+            version,
+            generated_date,
+            comment: comment.to_string(),
+            certificates: Vec::new(),
+            keys: BTreeSet::new(),
+            sha1: BTreeSet::new(),
+            sha256: BTreeSet::new(),
+        }
+    }
+
+    // uint64  KRL_MAGIC
+    // uint32  KRL_FORMAT_VERSION
+    // uint64  krl_version
+    // uint64  generated_date
+    // uint64  flags
+    // string  reserved
+    // string  comment
+    // followed by sections (byte section_type, string section_data)
+    // and signatures (byte KRL_SECTION_SIGNATURE, string key, string signature).
+    // Every signature must verify over the data before it, its key included; None otherwise.
+    pub fn from_bytes(data: &[u8]) -> Option<Self> {
+        let mut rest = data.strip_prefix(&Krl::MAGIC[..])?;
+        if ssh::read_u32(&mut rest)? != Krl::FORMAT_VERSION {
+            return None;
+        }
+        let version = ssh::read_u64(&mut rest)?;
+        let generated_date = ssh::read_u64(&mut rest)?;
+        ssh::read_u64(&mut rest)?;
+        ssh::read_string(&mut rest)?;
+        let comment = String::from_utf8(ssh::read_string(&mut rest)?.to_vec()).ok()?;
+
+        let mut krl = Krl::new(version, generated_date, &comment);
+        let mut signed = false;
+        while !rest.is_empty() {
+            let section_type = rest[0];
+            rest = &rest[1..];
+            if section_type == Krl::SECTION_SIGNATURE {
+                let public_key = PublicKey::from_ssh(ssh::read_string(&mut rest)?)?;
+                let signed_data = &data[..data.len() - rest.len()];
+                if !public_key.verify_ssh(signed_data, ssh::read_string(&mut rest)?) {
+                    return None;
+                }
+                signed = true;
+                continue;
+            }
+            // signatures come last
+            if signed {
+                return None;
+            }
+            let mut section = ssh::read_string(&mut rest)?;
+            match section_type {
+                Krl::SECTION_CERTIFICATES => krl.read_certificates(section)?,
+                Krl::SECTION_EXPLICIT_KEY => while !section.is_empty() {
+                    krl.keys.insert(ssh::read_string(&mut section)?.to_vec());
+                },
+                Krl::SECTION_FINGERPRINT_SHA1 => while !section.is_empty() {
+                    krl.sha1.insert(ssh::read_string(&mut section)?.to_vec());
+                },
+                Krl::SECTION_FINGERPRINT_SHA256 => while !section.is_empty() {
+                    krl.sha256.insert(ssh::read_string(&mut section)?.to_vec());
+                },
+                _ => return None,
+            }
+        }
+
+        Some(krl)
+    }
+
+    // string  ca_key
+    // string  reserved
+    // followed by cert sections (byte cert_section_type, string cert_section_data)
+    fn read_certificates(&mut self, mut data: &[u8]) -> Option<()> {
+        let ca_key = ssh::read_string(&mut data)?.to_vec();
+        ssh::read_string(&mut data)?;
+        while !data.is_empty() {
+            let section_type = data[0];
+            data = &data[1..];
+            let mut section = ssh::read_string(&mut data)?;
+            // as refused by revoke_serials
+            if section_type != Krl::CERT_KEY_ID && ca_key.is_empty() {
+                return None;
+            }
+            match section_type {
+                // uint64 serial ...
+                Krl::CERT_SERIAL_LIST => while !section.is_empty() {
+                    let serial = ssh::read_u64(&mut section)?;
+                    if serial == 0 {
+                        return None;
+                    }
+                    self.revoke_serials(&ca_key, serial, serial);
+                },
+                // uint64 serial_min, uint64 serial_max
+                Krl::CERT_SERIAL_RANGE => {
+                    let low = ssh::read_u64(&mut section)?;
+                    let high = ssh::read_u64(&mut section)?;
+                    if low == 0 || low > high || !section.is_empty() {
+                        return None;
+                    }
+                    self.revoke_serials(&ca_key, low, high);
+                },
+                // uint64 serial_offset, mpint revoked_keys_bitmap
+                Krl::CERT_SERIAL_BITMAP => {
+                    let offset = ssh::read_u64(&mut section)?;
+                    let bitmap = BigUint::from_bytes_be(ssh::read_string(&mut section)?);
+                    if !section.is_empty() {
+                        return None;
+                    }
+                    for i in 0..bitmap.bits() {
+                        if bitmap.bit(i) {
+                            let serial = offset.checked_add(i).filter(|x| *x != 0)?;
+                            self.revoke_serials(&ca_key, serial, serial);
+                        }
+                    }
+                },
+                // string key_id ...
+                Krl::CERT_KEY_ID => while !section.is_empty() {
+                    let key_id = String::from_utf8(ssh::read_string(&mut section)?.to_vec()).ok()?;
+                    self.revoke_key_id(&ca_key, &key_id);
+                },
+                _ => return None,
+            }
+        }
+
+        Some(())
+    }
+
+    pub fn version(&self) -> u64 {
+        self.version
+    }
+
+    // for an updated KRL, a later version and date
+    pub fn with_version(mut self, version: u64, generated_date: u64) -> Self {
+        self.version = version;
+        self.generated_date = generated_date;
+        self
+    }
+
+    // certificates with serial low..=high signed by ca_key (a public key blob).
+    // Serial 0 can not be revoked, OpenSSH reserves it for certificates without one.
+    pub fn revoke_serials(&mut self, ca_key: &[u8], low: u64, high: u64) {
+        assert!(!ca_key.is_empty(), "revoking serials needs a CA key");
+        assert!(0 < low && low <= high, "invalid serial range");
+        let serials = &mut self.ca(ca_key).serials;
+        serials.push((low, high));
+        serials.sort();
+
+        // merge overlapping and adjacent ranges
+        let mut merged: Vec<(u64, u64)> = Vec::with_capacity(serials.len());
+        for &(low, high) in serials.iter() {
+            match merged.last_mut() {
+                Some(last) if low <= last.1.saturating_add(1) => last.1 = last.1.max(high),
+                _ => merged.push((low, high)),
+            }
+        }
+        *serials = merged;
+    }
+
+    // certificates with this key ID signed by ca_key, by any CA when ca_key is empty
+    pub fn revoke_key_id(&mut self, ca_key: &[u8], key_id: &str) {
+        self.ca(ca_key).key_ids.insert(key_id.to_string());
+    }
+
+    // a plain public key blob, and every certificate for it
+    pub fn revoke_key(&mut self, public_key: &[u8]) {
+        self.keys.insert(public_key.to_vec());
+    }
+
+    // the SHA-256 of a plain public key blob, as in a SHA256: fingerprint
+    pub fn revoke_sha256(&mut self, digest: &[u8]) {
+        assert!(digest.len() == 32, "a SHA-256 fingerprint is 32 bytes");
+        self.sha256.insert(digest.to_vec());
+    }
+
+    // "SHA256:" and unpadded base64 as printed by ssh-keygen -l, None when malformed
+    pub fn revoke_fingerprint(&mut self, fingerprint: &str) -> Option<()> {
+        let digest = pem::base64_decode(fingerprint.strip_prefix("SHA256:")?.as_bytes())?;
+        if digest.len() != 32 {
+            return None;
+        }
+        self.revoke_sha256(&digest);
+
+        Some(())
+    }
+
+    // krl.c ssh_krl_check_key: a certificate is revoked with its key, its CA key,
+    // its serial or its key ID
+    pub fn is_revoked(&self, public_key: &[u8]) -> bool {
+        let (certificate, ca_key) = match SshCertificate::from_ssh(public_key) {
+            Some(x) => x,
+            None => return self.is_key_revoked(public_key),
+        };
+        if self.is_key_revoked(certificate.public_key()) || self.is_key_revoked(&ca_key) {
+            return true;
+        }
+
+        self.certificates.iter().any(|x| {
+            (x.ca_key.is_empty() || x.ca_key == ca_key) && (
+                x.key_ids.contains(certificate.key_id())
+                || x.serials.iter().any(|&(low, high)| low <= certificate.serial() && certificate.serial() <= high)
+            )
+        })
+    }
+
+    fn is_key_revoked(&self, public_key: &[u8]) -> bool {
+        self.keys.contains(public_key)
+            || self.sha1.contains(&Hash::Sha1.digest(public_key))
+            || self.sha256.contains(&Hash::Sha256.digest(public_key))
+    }
+
+    fn ca(&mut self, ca_key: &[u8]) -> &mut RevokedCertificates {
+        let position = match self.certificates.iter().position(|x| x.ca_key == ca_key) {
+            Some(position) => position,
+            None => {
+                self.certificates.push(RevokedCertificates{
+                    ca_key: ca_key.to_vec(),
+                    serials: Vec::new(),
+                    key_ids: BTreeSet::new(),
+                });
+                self.certificates.len() - 1
+            },
+        };
+
+        &mut self.certificates[position]
+    }
+
+    // the KRL, signed by each of signers in turn
+    pub fn to_bytes(&self, signers: &[&dyn SshFormat]) -> Vec<u8> {
+        let mut out = Vec::new();
+        out.extend(Krl::MAGIC);
+        out.extend(Krl::FORMAT_VERSION.to_be_bytes());
+        out.extend(self.version.to_be_bytes());
+        out.extend(self.generated_date.to_be_bytes());
+        out.extend(0u64.to_be_bytes());
+        out.extend(ssh::to_string(b""));
+        out.extend(ssh::to_string(self.comment.as_bytes()));
+
+        for certificates in self.certificates.iter() {
+            out.push(Krl::SECTION_CERTIFICATES);
+            out.extend(ssh::to_string(&certificates.to_bytes()));
+        }
+        let blobs = [
+            (Krl::SECTION_EXPLICIT_KEY, &self.keys),
+            (Krl::SECTION_FINGERPRINT_SHA1, &self.sha1),
+            (Krl::SECTION_FINGERPRINT_SHA256, &self.sha256),
+        ];
+        for (section_type, blobs) in blobs {
+            if blobs.is_empty() {
+                continue;
+            }
+            let mut section = Vec::new();
+            for blob in blobs.iter() {
+                section.extend(ssh::to_string(blob));
+            }
+            out.push(section_type);
+            out.extend(ssh::to_string(&section));
+        }
+
+        for signer in signers {
+            out.push(Krl::SECTION_SIGNATURE);
+            out.extend(ssh::to_string(&signer.gen_public_key()));
+            let signature = signer.gen_signature(&out).expect("the signing key can not make ssh signatures");
+            out.extend(ssh::to_string(&signature));
+        }
+
+        out
+    }
+}
+
+impl RevokedCertificates {
+    // single serials go in one list, runs as ranges
+    fn to_bytes(&self) -> Vec<u8> {
+        let mut out = Vec::new();
+        out.extend(ssh::to_string(&self.ca_key));
+        out.extend(ssh::to_string(b""));
+
+        let mut list = Vec::new();
+        for &(low, high) in self.serials.iter() {
+            if low == high {
+                list.extend(low.to_be_bytes());
+            } else {
+                let mut range = Vec::new();
+                range.extend(low.to_be_bytes());
+                range.extend(high.to_be_bytes());
+                out.push(Krl::CERT_SERIAL_RANGE);
+                out.extend(ssh::to_string(&range));
+            }
+        }
+        if !list.is_empty() {
+            out.push(Krl::CERT_SERIAL_LIST);
+            out.extend(ssh::to_string(&list));
+        }
+        if !self.key_ids.is_empty() {
+            let mut key_ids = Vec::new();
+            for key_id in self.key_ids.iter() {
+                key_ids.extend(ssh::to_string(key_id.as_bytes()));
+            }
+            out.push(Krl::CERT_KEY_ID);
+            out.extend(ssh::to_string(&key_ids));
+        }
+
+        out
+    }
+}
@@ -1,15 +1,71 @@
 mod ssh;
+mod sshsig;
+mod sshcert;
+mod krl;
+mod x509;
+mod pkcs10;
+mod jwk;
+mod jws;
+mod ppk;
+mod openpgp;
+mod wireguard;
+mod age;
+mod bcrypt;
+mod scrypt;
+mod minisign;
+mod signify;
+mod onion;
+mod vanity;
+mod sha3;
+mod dns;
+mod bech32;
+mod json;
+mod pkcs12;
+mod pkcs5;
+mod cipher;
 mod pkcs8;
+mod spki;
 mod ed25519;
 mod rsa;
+mod prime;
+mod pkcs1;
+mod hash;
 mod pem;
 mod der;
+mod curve25519;
+mod x25519;
+mod secret;
+mod key;
 
 pub use crate::pem::PemEncode;
+pub use crate::der::DerEncode;
 pub use crate::ed25519::Ed25519;
-pub use crate::rsa::Rsa2048;
+pub use crate::x25519::X25519;
+pub use crate::rsa::{Rsa2048, RsaAlgorithm, RsaPublicKey, PssParams};
+pub use crate::hash::Hash;
 pub use crate::pkcs8::Pkcs8;
-pub use crate::ssh::Ssh;
+pub use crate::spki::Spki;
+pub use crate::ssh::{Ssh, read_public_key_line, public_key_line, read_private_key_comment};
+pub use crate::sshsig::{SshSig, AllowedSigners};
+pub use crate::sshcert::{SshCertificate, CertType, parse_validity};
+pub use crate::krl::Krl;
+pub use crate::x509::{TbsCertificate, Certificate, Name, GeneralName, KeyUsage};
+pub use crate::pkcs10::{CertificationRequestInfo, CertificationRequest};
+pub use crate::pkcs12::Pkcs12;
+pub use crate::ppk::Ppk;
+pub use crate::openpgp::{TransferableSecretKey, armor};
+pub use crate::wireguard::WireGuard;
+pub use crate::age::{Age, ssh_recipient};
+pub use crate::minisign::Minisign;
+pub use crate::signify::Signify;
+pub use crate::onion::{OnionService, onion_address};
+pub use crate::dns::{Dnskey, sshfp, dkim, tlsa};
+pub use crate::vanity::{Pattern, VanityTarget, search};
+pub use crate::jwk::{Jwk, jwks, read_jwks};
+pub use crate::jws::{Jws, JwsAlgorithm, claims, check_time};
+pub use crate::json::Json;
+pub use crate::key::{Key, PublicKey};
+pub use crate::secret::enable_mlock;
 
 #[cfg(test)]
 mod tests;
@@ -1,7 +1,11 @@
+use std::fs;
 use std::fs::File;
 use std::io::{stdout, Write};
-use clap::{Parser, ArgEnum};
-use genkey::{PemEncode, Pkcs8, Ssh, Ed25519, Rsa2048};
+use std::num::NonZeroUsize;
+use clap::{Parser, Subcommand, ArgEnum, ArgGroup};
+use rand::thread_rng;
+use std::time::{SystemTime, UNIX_EPOCH};
+use genkey::{PemEncode, Pkcs8, Ssh, SshSig, AllowedSigners, SshCertificate, CertType, parse_validity, Krl, TbsCertificate, Name, GeneralName, KeyUsage, CertificationRequestInfo, Certificate, Pkcs12, Ppk, TransferableSecretKey, armor, WireGuard, Age, ssh_recipient, OnionService, onion_address, Pattern, VanityTarget, Dnskey, sshfp, dkim, tlsa, Minisign, Signify, Jwk, jwks, read_jwks, Jws, JwsAlgorithm, Json, claims, check_time, DerEncode, read_public_key_line, public_key_line, read_private_key_comment, Ed25519, X25519, Rsa2048, RsaAlgorithm, PssParams, Hash, Key, PublicKey, enable_mlock};
 
 #[derive(Parser)]
 #[clap(
@@ -11,6 +15,9 @@ use genkey::{PemEncode, Pkcs8, Ssh, Ed25519, Rsa2048};
     about = "generate key of rsa or ed25519"
 )]
 struct Args {
+    #[clap(subcommand)]
+    command: Option<Command>,
+
     //key name and file name.
     #[clap(short, long)]
     name: Option<String>,
@@ -19,13 +26,592 @@ struct Args {
     #[clap(short, long, arg_enum, default_value = "rsa")]
     algorithm: Algorithm,
 
-    //key format. ssh or pkcs8.
+    //key format. ssh, pkcs8, pkcs1 (rsa only), jwk, ppk (PuTTY), wireguard, age, minisign or signify.
+    //wireguard is always an x25519 key: the private key, the public key and a preshared key,
+    //one a line, or in <name>, <name>.pub and <name>.psk.
+    //age is an x25519 identity file as age-keygen writes it.
+    //minisign and signify are always ed25519 keys: the secret key and the public key,
+    //in <name> and <name>.pub (a signify <name>.sec goes with <name>.pub), encrypted with --password.
     #[clap(short, long, arg_enum, default_value = "ssh")]
     format: Format,
 
+    //with -f wireguard, a wg-quick configuration to edit instead of the bare keys.
+    #[clap(long)]
+    wg_config: bool,
+
+    //key size in bits for rsa.
+    #[clap(short, long, default_value = "2048")]
+    bits: usize,
+
+    //rsa key generation following FIPS 186-5 Appendix A.1.3, pkcs8 output records it for inspect.
+    #[clap(long)]
+    fips: bool,
+
+    //number of prime factors for rsa, 3 to 5 make a multi-prime key.
+    #[clap(long, default_value = "2")]
+    primes: usize,
+
+    //restrict the rsa key to PSS signatures with this hash, pkcs8 only.
+    #[clap(long, arg_enum)]
+    pss: Option<HashName>,
+
     //comment for ssh key.
     #[clap(short, long, default_value = "")]
     comment: String,
+
+    //with -f minisign or -f signify, the password the secret key is encrypted with.
+    //an empty password leaves it unencrypted.
+    #[clap(long)]
+    password: Option<String>,
+
+    //lock secret key material into memory so it is never swapped out.
+    #[clap(long)]
+    mlock: bool,
+}
+
+#[derive(Subcommand)]
+enum Command {
+    //print the algorithm and parameters of a private or public key file.
+    Inspect {
+        file: String,
+    },
+    //print the SubjectPublicKeyInfo of a private key file.
+    Public {
+        file: String,
+    },
+    //sign a message file with a private key file.
+    Sign {
+        key: String,
+        message: String,
+        //signature file, stdout by default.
+        #[clap(short, long)]
+        output: Option<String>,
+        #[clap(flatten)]
+        options: SignOptions,
+    },
+    //verify a signature over a message file with a private or public key file.
+    Verify {
+        key: String,
+        message: String,
+        signature: String,
+        #[clap(flatten)]
+        options: SignOptions,
+    },
+    //encrypt a file with RSAES-OAEP (SHA-256) to an rsa private or public key file.
+    Encrypt {
+        key: String,
+        input: String,
+        //ciphertext file, stdout by default.
+        #[clap(short, long)]
+        output: Option<String>,
+        //OAEP label.
+        #[clap(long, default_value = "")]
+        label: String,
+    },
+    //decrypt a file with an rsa private key file in pkcs8 or pkcs1.
+    Decrypt {
+        key: String,
+        input: String,
+        //plaintext file, stdout by default.
+        #[clap(short, long)]
+        output: Option<String>,
+        //OAEP label.
+        #[clap(long, default_value = "")]
+        label: String,
+        //RSAES-PKCS1-v1_5 instead of OAEP, for legacy ciphertexts.
+        //bad padding gives a random-looking message rather than an error.
+        #[clap(long, conflicts_with = "label")]
+        pkcs1: bool,
+    },
+    //sign a file in the SSHSIG format of ssh-keygen -Y sign.
+    SshSign {
+        key: String,
+        message: String,
+        //namespace the signature is made for, e.g. git or file.
+        #[clap(short, long)]
+        namespace: String,
+        //signature file, stdout by default.
+        #[clap(short, long)]
+        output: Option<String>,
+        //hash of the message, sha256 or sha512.
+        #[clap(long, arg_enum, default_value = "sha512")]
+        hash: HashName,
+    },
+    //verify an SSHSIG signature against an allowed_signers file, as ssh-keygen -Y verify.
+    SshVerify {
+        allowed_signers: String,
+        message: String,
+        signature: String,
+        //principal the signer must be listed as.
+        #[clap(short = 'I', long)]
+        identity: String,
+        //namespace the signature must have been made for.
+        #[clap(short, long)]
+        namespace: String,
+    },
+    //certify an OpenSSH public key with a CA key, as ssh-keygen -s.
+    //writes <public key>-cert.pub next to the public key file.
+    SshCert {
+        //CA private key file.
+        ca: String,
+        //public key file (.pub) or private key file to certify.
+        public_key: String,
+        //certificate file, <public key>-cert.pub by default.
+        #[clap(short, long)]
+        output: Option<String>,
+        #[clap(flatten)]
+        options: CertOptions,
+    },
+    //OpenSSH key revocation lists, as ssh-keygen -k and -Q.
+    Krl {
+        #[clap(subcommand)]
+        command: KrlCommand,
+    },
+    //write a PKCS #10 certificate signing request for a private key file.
+    Csr {
+        //private key file.
+        #[clap(long)]
+        key: String,
+        //subject name, e.g. /C=JP/O=Example/CN=example.com.
+        #[clap(long)]
+        subject: String,
+        //subject alternative names, e.g. DNS:example.com,IP:192.0.2.1,email:user@example.com; may be repeated.
+        #[clap(long, multiple_occurrences = true, use_value_delimiter = true)]
+        san: Vec<String>,
+        //request file, stdout by default.
+        #[clap(short, long)]
+        output: Option<String>,
+    },
+    //write a private key file in another format.
+    #[clap(group(ArgGroup::new("format").required(true)))]
+    Export {
+        //private key file, PEM, JWK or PuTTY .ppk; a public key file for --jwk --public.
+        key: String,
+        //PKCS #12 (.p12/.pfx) protected by --password.
+        #[clap(long, group = "format", requires = "password")]
+        p12: bool,
+        //JSON Web Key (RFC 7517).
+        #[clap(long, group = "format")]
+        jwk: bool,
+        //OpenSSH private key.
+        #[clap(long, group = "format")]
+        ssh: bool,
+        //PKCS #8 private key.
+        #[clap(long, group = "format")]
+        pkcs8: bool,
+        //PKCS #1 rsa private key.
+        #[clap(long, group = "format")]
+        pkcs1: bool,
+        //PuTTY private key (.ppk), encrypted when --password is given.
+        #[clap(long, group = "format")]
+        ppk: bool,
+        //minisign secret key of an ed25519 key, encrypted when a --password is given that is not empty,
+        //and the public key in <output>.pub or after it on stdout.
+        #[clap(long, group = "format")]
+        minisign: bool,
+        //signify secret key of an ed25519 key, encrypted when a --password is given that is not empty,
+        //and the public key in <output>.pub, <output> without .sec, or after it on stdout.
+        #[clap(long, group = "format")]
+        signify: bool,
+        #[clap(flatten)]
+        options: ExportOptions,
+        #[clap(flatten)]
+        jwk_options: JwkOptions,
+        //output file, stdout by default.
+        #[clap(short, long)]
+        output: Option<String>,
+    },
+    //X.509 certificates.
+    Cert {
+        #[clap(subcommand)]
+        command: CertCommand,
+    },
+    //JSON Web Tokens in the JWS compact serialization.
+    Jwt {
+        #[clap(subcommand)]
+        command: JwtCommand,
+    },
+    //write a new OpenPGP secret key for GnuPG, armored, and print its fingerprint.
+    Pgp {
+        //User ID, e.g. "Alice <alice@example.com>".
+        #[clap(long)]
+        user_id: String,
+        //primary key algorithm, the key that certifies and signs.
+        #[clap(short, long, arg_enum, default_value = "ed25519")]
+        algorithm: Algorithm,
+        //encryption subkey algorithm, no subkey by default.
+        #[clap(long, arg_enum)]
+        subkey: Option<SubkeyAlgorithm>,
+        //key size in bits for rsa keys.
+        #[clap(short, long, default_value = "3072")]
+        bits: usize,
+        //days from now until the keys expire, never by default.
+        #[clap(long)]
+        days: Option<u64>,
+        //passphrase the secret keys are encrypted with.
+        #[clap(long)]
+        password: Option<String>,
+        //key file, stdout by default.
+        #[clap(short, long)]
+        output: Option<String>,
+    },
+    //minisign signatures, as minisign -S and -V.
+    Minisign {
+        #[clap(subcommand)]
+        command: MinisignCommand,
+    },
+    //signify signatures, as signify -S and -V.
+    Signify {
+        #[clap(subcommand)]
+        command: SignifyCommand,
+    },
+    //print the age recipient (age1...) of an age identity file, an ed25519 private key
+    //or an ssh-ed25519 public key, as age-keygen -y and ssh-to-age do.
+    Age {
+        file: String,
+        //write the age identity file of an ed25519 private key instead.
+        #[clap(long)]
+        identity: bool,
+    },
+    //DNS records for keys, as lines of a BIND zone file.
+    Dns {
+        #[clap(subcommand)]
+        command: DnsCommand,
+    },
+    //search for an ed25519 ssh key whose public key or fingerprint matches a pattern, on every core.
+    //prints how long the pattern is expected to take and the keys tried a second while it runs.
+    Vanity {
+        //base64 characters the key should start or end with, or a regex.
+        #[clap(long)]
+        pattern: String,
+        //prefix, suffix or regex.
+        #[clap(long = "match", arg_enum, default_value = "prefix")]
+        kind: PatternKind,
+        //key matches the base64 of the public key after the 25 characters
+        //AAAAC3NzaC1lZDI1NTE5AAAAI all ed25519 keys start with, fingerprint the
+        //SHA256 fingerprint after "SHA256:".
+        #[clap(long, arg_enum, default_value = "key")]
+        target: PatternTarget,
+        //threads of the search, every core by default.
+        #[clap(long)]
+        threads: Option<NonZeroUsize>,
+        //comment of the ssh key.
+        #[clap(short, long, default_value = "")]
+        comment: String,
+        //private key file, with the public key in <output>.pub; stdout by default.
+        #[clap(short, long)]
+        output: Option<String>,
+    },
+    //tor v3 onion service keys, hs_ed25519_secret_key, hs_ed25519_public_key and hostname
+    //written to a HiddenServiceDir, which is created if missing.
+    Onion {
+        dir: String,
+        //search for an address starting with these base32 characters, [a-z2-7].
+        //every character makes the search 32 times longer.
+        #[clap(long)]
+        vanity_prefix: Option<String>,
+        //threads of the vanity search, every core by default.
+        #[clap(long)]
+        threads: Option<NonZeroUsize>,
+    },
+}
+
+#[derive(Subcommand)]
+enum JwtCommand {
+    //sign a claims file with a private key file, PEM or JWK.
+    Sign {
+        //JSON object of claims.
+        #[clap(long)]
+        claims: String,
+        //private key file.
+        #[clap(long)]
+        key: String,
+        //signature algorithm, EdDSA for ed25519 and RS256 for rsa by default.
+        #[clap(long, arg_enum)]
+        alg: Option<JwtAlgorithm>,
+        //kid header, the key the verifier should pick.
+        #[clap(long)]
+        kid: Option<String>,
+        //token file, stdout by default.
+        #[clap(short, long)]
+        output: Option<String>,
+    },
+    //verify a token with a private or public key file, PEM or JWK, and print its claims.
+    //exits 1 if the signature is bad or exp/nbf rule the token out now.
+    Verify {
+        //public or private key file.
+        #[clap(long)]
+        key: String,
+        //token file.
+        token: String,
+    },
+}
+
+#[derive(Subcommand)]
+enum MinisignCommand {
+    //sign a file with a minisign secret key.
+    Sign {
+        //secret key file.
+        #[clap(long)]
+        key: String,
+        message: String,
+        //password of an encrypted secret key.
+        #[clap(long)]
+        password: Option<String>,
+        //signed comment, timestamp:<now>\tfile:<message file name>\thashed by default.
+        #[clap(short, long)]
+        trusted_comment: Option<String>,
+        //signature file, <message>.minisig by default.
+        #[clap(short, long)]
+        output: Option<String>,
+    },
+    //verify a signature with a minisign public key file and print its trusted comment.
+    //exits 1 if the signature is bad.
+    Verify {
+        //public key file, or the public key itself.
+        #[clap(long)]
+        key: String,
+        message: String,
+        //signature file, <message>.minisig by default.
+        #[clap(short = 'x', long)]
+        signature: Option<String>,
+    },
+}
+
+#[derive(Subcommand)]
+enum DnsCommand {
+    //SSHFP records of ssh public keys or private keys, as ssh-keygen -r writes them.
+    Sshfp {
+        //host name the records are for.
+        name: String,
+        #[clap(required = true)]
+        keys: Vec<String>,
+    },
+    //the DKIM TXT record of an rsa or ed25519 key: a private key, a public key,
+    //an ssh public key or a raw 32 byte ed25519 public key.
+    Dkim {
+        #[clap(short, long)]
+        selector: String,
+        #[clap(short, long)]
+        domain: String,
+        key: String,
+    },
+    //the DNSKEY record of a zone key, rsa (algorithm 8) or ed25519 (algorithm 15).
+    Dnskey {
+        zone: String,
+        key: String,
+        //key signing key, flags 257 instead of 256.
+        #[clap(long)]
+        ksk: bool,
+    },
+    //the DS record of a zone key for the parent zone.
+    Ds {
+        zone: String,
+        key: String,
+        //key signing key, flags 257 instead of 256.
+        #[clap(long)]
+        ksk: bool,
+        //sha256 (digest type 2) or sha384 (digest type 4).
+        #[clap(long, arg_enum, default_value = "sha256")]
+        digest: HashName,
+    },
+    //the TLSA 3 1 1 record of a key or a certificate.
+    Tlsa {
+        host: String,
+        file: String,
+        #[clap(long, default_value = "443")]
+        port: u16,
+        #[clap(long, default_value = "tcp")]
+        protocol: String,
+    },
+}
+
+#[derive(Subcommand)]
+enum SignifyCommand {
+    //sign a file with a signify secret key.
+    Sign {
+        //secret key file.
+        #[clap(long)]
+        key: String,
+        message: String,
+        //password of an encrypted secret key.
+        #[clap(long)]
+        password: Option<String>,
+        //signature file, <message>.sig by default.
+        #[clap(short, long)]
+        output: Option<String>,
+    },
+    //verify a signature with a signify public key file.
+    //exits 1 if the signature is bad.
+    Verify {
+        //public key file.
+        #[clap(long)]
+        key: String,
+        message: String,
+        //signature file, <message>.sig by default.
+        #[clap(short = 'x', long)]
+        signature: Option<String>,
+    },
+}
+
+#[derive(Subcommand)]
+enum CertCommand {
+    //write a self-signed certificate for a private key file.
+    SelfSign {
+        key: String,
+        //subject name, e.g. /C=JP/O=Example/CN=example.com.
+        #[clap(long)]
+        subject: String,
+        //subject alternative names, e.g. DNS:example.com,IP:192.0.2.1,email:user@example.com; may be repeated.
+        #[clap(long, multiple_occurrences = true, use_value_delimiter = true)]
+        san: Vec<String>,
+        //days the certificate is valid for from now.
+        #[clap(long, default_value = "30")]
+        days: u64,
+        //certificate of a CA that may sign other certificates.
+        #[clap(long)]
+        ca: bool,
+        //certificate file, stdout by default.
+        #[clap(short, long)]
+        output: Option<String>,
+    },
+}
+
+#[derive(Subcommand)]
+enum KrlCommand {
+    //write a new KRL.
+    Create {
+        krl: String,
+        #[clap(flatten)]
+        options: KrlOptions,
+    },
+    //add to an existing KRL and raise its version.
+    Update {
+        krl: String,
+        #[clap(flatten)]
+        options: KrlOptions,
+    },
+    //check public keys or certificates against a KRL, exits 1 if any is revoked.
+    Test {
+        krl: String,
+        #[clap(required = true)]
+        keys: Vec<String>,
+    },
+}
+
+#[derive(clap::Args)]
+struct KrlOptions {
+    //CA public key file the --serial and --key-id certificates are signed by.
+    #[clap(long)]
+    ca: Option<String>,
+    //certificate serial or serial range (low-high) to revoke, needs --ca; may be repeated.
+    #[clap(long, multiple_occurrences = true)]
+    serial: Vec<String>,
+    //certificate key ID to revoke, from any CA without --ca; may be repeated.
+    #[clap(long, multiple_occurrences = true)]
+    key_id: Vec<String>,
+    //public key file to revoke along with its certificates; may be repeated.
+    #[clap(long, multiple_occurrences = true)]
+    key: Vec<String>,
+    //public key file or SHA256:... fingerprint to revoke by SHA256 fingerprint; may be repeated.
+    #[clap(long, multiple_occurrences = true)]
+    sha256: Vec<String>,
+    //private key file to sign the KRL with.
+    #[clap(long)]
+    sign: Option<String>,
+    //comment stored in a new KRL.
+    #[clap(long, default_value = "")]
+    comment: String,
+}
+
+#[derive(clap::Args)]
+struct ExportOptions {
+    //password the key is encrypted and a PKCS #12 bundle authenticated with.
+    //an encrypted .ppk key file is read with it too.
+    #[clap(long)]
+    password: Option<String>,
+    //certificate file to include with the key.
+    #[clap(long)]
+    cert: Option<String>,
+    //friendly name shown for the key when it is imported.
+    #[clap(long)]
+    friendly_name: Option<String>,
+    //3DES and SHA-1 instead of AES-256 and SHA-256 for PKCS #12,
+    //PPK version 2 instead of 3, for older importers.
+    #[clap(long)]
+    legacy: bool,
+}
+
+#[derive(clap::Args)]
+struct JwkOptions {
+    //public key only.
+    #[clap(long)]
+    public: bool,
+    //a JWK set, {"keys":[...]}.
+    #[clap(long)]
+    jwks: bool,
+    //set kid to the RFC 7638 thumbprint.
+    #[clap(long)]
+    kid: bool,
+    //intended use of the key, sig or enc.
+    #[clap(long = "use", arg_enum)]
+    key_use: Option<KeyUse>,
+    //algorithm the key is meant for, e.g. EdDSA, RS256 or PS256.
+    #[clap(long)]
+    alg: Option<String>,
+}
+
+// ed25519: RFC 8032 section 5.1, Ed25519 with neither, Ed25519ctx with a context, Ed25519ph with prehash.
+// rsa: RSASSA-PKCS1-v1_5, or RSASSA-PSS with --pss or when the key is restricted to PSS.
+#[derive(clap::Args)]
+struct SignOptions {
+    //context string for Ed25519ctx or Ed25519ph.
+    #[clap(long)]
+    context: Option<String>,
+
+    //sign the SHA-512 of the message (Ed25519ph).
+    #[clap(long)]
+    prehash: bool,
+
+    //hash for rsa signatures.
+    #[clap(long, arg_enum, default_value = "sha256")]
+    hash: HashName,
+
+    //RSASSA-PSS instead of PKCS #1 v1.5, with a salt as long as the hash.
+    #[clap(long)]
+    pss: bool,
+}
+
+// PROTOCOL.certkeys fields, the defaults are those of ssh-keygen -s
+#[derive(clap::Args)]
+struct CertOptions {
+    //key identifier, logged by sshd when the certificate is used.
+    #[clap(short = 'I', long)]
+    key_id: String,
+    //host certificate instead of a user certificate.
+    #[clap(long)]
+    host: bool,
+    //comma-separated user or host names, any principal when empty.
+    #[clap(short = 'n', long, default_value = "")]
+    principals: String,
+    //validity interval, e.g. +52w, -1d:+4w, 20240101:20250101 or always:forever.
+    #[clap(short = 'V', long, default_value = "always:forever", allow_hyphen_values = true)]
+    validity: String,
+    //serial number.
+    #[clap(short = 'z', long, default_value = "0")]
+    serial: u64,
+    //command forced on login (critical option force-command).
+    #[clap(long)]
+    force_command: Option<String>,
+    //comma-separated CIDR addresses the certificate may be used from (critical option source-address).
+    #[clap(long)]
+    source_address: Option<String>,
+    //extension to add, name or name=value; may be repeated.
+    #[clap(long, multiple_occurrences = true)]
+    extension: Vec<String>,
+    //leave out the default permit-* extensions of a user certificate.
+    #[clap(long)]
+    clear: bool,
 }
 
 #[derive(ArgEnum, Clone)]
@@ -34,20 +620,205 @@ enum Algorithm {
     Ed25519,
 }
 
+#[derive(ArgEnum, Clone)]
+enum SubkeyAlgorithm {
+    Rsa,
+    X25519,
+}
+
+#[derive(ArgEnum, Clone)]
+enum HashName {
+    Sha256,
+    Sha384,
+    Sha512,
+}
+
+impl HashName {
+    fn to_hash(&self) -> Hash {
+        match self {
+            HashName::Sha256 => Hash::Sha256,
+            HashName::Sha384 => Hash::Sha384,
+            HashName::Sha512 => Hash::Sha512,
+        }
+    }
+}
+
+#[derive(ArgEnum, Clone)]
+enum PatternKind {
+    Prefix,
+    Suffix,
+    Regex,
+}
+
+#[derive(ArgEnum, Clone)]
+enum PatternTarget {
+    Key,
+    Fingerprint,
+}
+
+impl PatternTarget {
+    fn to_target(&self) -> VanityTarget {
+        match self {
+            PatternTarget::Key => VanityTarget::Key,
+            PatternTarget::Fingerprint => VanityTarget::Fingerprint,
+        }
+    }
+}
+
 #[derive(ArgEnum, Clone)]
 enum Format {
     Ssh,
     Pkcs8,
+    Pkcs1,
+    Jwk,
+    Ppk,
+    Wireguard,
+    Age,
+    Minisign,
+    Signify,
+}
+
+#[derive(ArgEnum, Clone)]
+enum JwtAlgorithm {
+    #[clap(name = "EdDSA")]
+    EdDsa,
+    #[clap(name = "RS256")]
+    Rs256,
+    #[clap(name = "PS256")]
+    Ps256,
+}
+
+#[derive(ArgEnum, Clone)]
+enum KeyUse {
+    Sig,
+    Enc,
 }
 
 fn main() {
     let args = Args::parse();
 
-    let out = match (args.format, args.algorithm) {
-        (Format::Ssh, Algorithm::Rsa) => Ssh::new(&Rsa2048::new(), args.comment).to_pem(),
-        (Format::Ssh, Algorithm::Ed25519) => Ssh::new(&Ed25519::new(), args.comment).to_pem(),
-        (Format::Pkcs8, Algorithm::Rsa) => Pkcs8::new(Pkcs8::V1, &Rsa2048::new()).to_pem(),
+    if args.mlock {
+        enable_mlock();
+    }
+
+    match args.command {
+        Some(Command::Inspect{ref file}) => inspect(file),
+        Some(Command::Public{ref file}) => public(file),
+        Some(Command::Sign{ref key, ref message, ref output, ref options}) => sign(key, message, output, options),
+        Some(Command::Verify{ref key, ref message, ref signature, ref options}) => verify(key, message, signature, options),
+        Some(Command::Encrypt{ref key, ref input, ref output, ref label}) => encrypt(key, input, output, label),
+        Some(Command::Decrypt{ref key, ref input, ref output, ref label, pkcs1}) => decrypt(key, input, output, label, pkcs1),
+        Some(Command::SshSign{ref key, ref message, ref namespace, ref output, ref hash}) => ssh_sign(key, message, namespace, output, hash),
+        Some(Command::SshVerify{ref allowed_signers, ref message, ref signature, ref identity, ref namespace}) => ssh_verify(allowed_signers, message, signature, identity, namespace),
+        Some(Command::SshCert{ref ca, ref public_key, ref output, ref options}) => ssh_cert(ca, public_key, output, options),
+        Some(Command::Krl{command: KrlCommand::Create{ref krl, ref options}}) => krl_create(krl, options, None),
+        Some(Command::Krl{command: KrlCommand::Update{ref krl, ref options}}) => {
+            let data = fs::read(krl).unwrap();
+            krl_create(krl, options, Some(Krl::from_bytes(&data).expect("malformed or badly signed KRL")));
+        },
+        Some(Command::Krl{command: KrlCommand::Test{ref krl, ref keys}}) => krl_test(krl, keys),
+        Some(Command::Csr{ref key, ref subject, ref san, ref output}) => csr(key, subject, san, output),
+        Some(Command::Export{ref key, p12: true, ref options, ref output, ..}) => export_p12(key, options, output),
+        Some(Command::Export{ref key, jwk: true, ref options, ref jwk_options, ref output, ..}) => export_jwk(key, options, jwk_options, output),
+        Some(Command::Export{ref key, minisign: true, ref options, ref output, ..}) => export_signing_key(key, Format::Minisign, options, output),
+        Some(Command::Export{ref key, signify: true, ref options, ref output, ..}) => export_signing_key(key, Format::Signify, options, output),
+        Some(Command::Export{ref key, ssh, pkcs8, pkcs1, ref options, ref output, ..}) => {
+            let format = if ssh { Format::Ssh } else if pkcs8 { Format::Pkcs8 } else if pkcs1 { Format::Pkcs1 } else { Format::Ppk };
+            export_key(key, format, options, output);
+        },
+        Some(Command::Cert{command: CertCommand::SelfSign{ref key, ref subject, ref san, days, ca, ref output}}) => self_sign(key, subject, san, days, ca, output),
+        Some(Command::Jwt{command: JwtCommand::Sign{ref claims, ref key, ref alg, ref kid, ref output}}) => jwt_sign(claims, key, alg, kid, output),
+        Some(Command::Jwt{command: JwtCommand::Verify{ref key, ref token}}) => jwt_verify(key, token),
+        Some(Command::Pgp{ref user_id, ref algorithm, ref subkey, bits, days, ref password, ref output}) => pgp(user_id, algorithm, subkey, bits, days, password, output),
+        Some(Command::Minisign{command: MinisignCommand::Sign{ref key, ref message, ref password, ref trusted_comment, ref output}}) => minisign_sign(key, message, password, trusted_comment, output),
+        Some(Command::Minisign{command: MinisignCommand::Verify{ref key, ref message, ref signature}}) => minisign_verify(key, message, signature),
+        Some(Command::Signify{command: SignifyCommand::Sign{ref key, ref message, ref password, ref output}}) => signify_sign(key, message, password, output),
+        Some(Command::Signify{command: SignifyCommand::Verify{ref key, ref message, ref signature}}) => signify_verify(key, message, signature),
+        Some(Command::Age{ref file, identity}) => age_convert(file, identity),
+        Some(Command::Dns{command: DnsCommand::Sshfp{ref name, ref keys}}) => dns_sshfp(name, keys),
+        Some(Command::Dns{command: DnsCommand::Dkim{ref selector, ref domain, ref key}}) => print!("{}", dkim(selector, domain, &read_public_key(key)).unwrap_or_else(|| fail("an rsa key restricted to PSS can not sign DKIM"))),
+        Some(Command::Dns{command: DnsCommand::Dnskey{ref zone, ref key, ksk}}) => print!("{}", dnskey(zone, key, ksk).to_record()),
+        Some(Command::Dns{command: DnsCommand::Ds{ref zone, ref key, ksk, ref digest}}) => print!("{}", dnskey(zone, key, ksk).to_ds(digest.to_hash()).unwrap_or_else(|| fail("DS digests are sha256 or sha384"))),
+        Some(Command::Dns{command: DnsCommand::Tlsa{ref host, ref file, port, ref protocol}}) => dns_tlsa(host, file, port, protocol),
+        Some(Command::Vanity{ref pattern, ref kind, ref target, threads, ref comment, ref output}) => vanity(pattern, kind, target, threads, comment, output),
+        Some(Command::Onion{ref dir, ref vanity_prefix, threads}) => onion(dir, vanity_prefix, threads),
+        None if matches!(args.format, Format::Wireguard) => wireguard(&args.name, args.wg_config),
+        None if matches!(args.format, Format::Age) => age(&args.name),
+        None if matches!(args.format, Format::Minisign | Format::Signify) => signing_key(Ed25519::new(), &args.format, &args.comment, args.password.as_deref(), &args.name),
+        None => generate(args),
+    }
+}
+
+fn generate(args: Args) {
+    if matches!(args.algorithm, Algorithm::Rsa) && args.bits < Rsa2048::MIN_BITS {
+        fail(&format!("rsa keys need --bits of at least {}", Rsa2048::MIN_BITS));
+    }
+
+    if args.fips && (args.bits < 2048 || !args.bits.is_multiple_of(2)) {
+        fail("--fips needs an even number of --bits, at least 2048");
+    }
+
+    if args.primes != 2 {
+        if args.fips {
+            fail("FIPS 186-5 keys have exactly two primes");
+        }
+        if matches!(args.format, Format::Ssh | Format::Ppk) {
+            fail("multi-prime rsa keys can not be written in the openssh or ppk format");
+        }
+        if matches!(args.format, Format::Jwk) {
+            fail("multi-prime rsa keys can not be written as a JWK");
+        }
+        // same limit as OpenSSL, which refuses keys with more primes than this
+        let max_primes = match args.bits {
+            0..=1023 => 2,
+            1024..=4095 => 3,
+            4096..=8191 => 4,
+            _ => 5,
+        };
+        if !(2..=max_primes).contains(&args.primes) {
+            fail(&format!("a {} bit modulus can have 2 to {} primes", args.bits, max_primes));
+        }
+    }
+
+    if args.pss.is_some() && !matches!(args.format, Format::Pkcs8) {
+        fail("only pkcs8 can restrict a key to PSS");
+    }
+
+    if args.wg_config {
+        fail("--wg-config goes with -f wireguard");
+    }
+
+    if args.password.is_some() {
+        fail("--password goes with -f minisign or -f signify, see export for the other formats");
+    }
+
+    let algorithm = match args.pss {
+        None => RsaAlgorithm::Rsa,
+        Some(ref hash) => RsaAlgorithm::Pss(Some(pss_params(hash.to_hash()))),
+    };
+
+    let rsa = || if args.fips {
+        Rsa2048::generate_fips(args.bits, &mut thread_rng())
+    } else if args.primes != 2 {
+        Rsa2048::generate_multi_prime(args.bits, args.primes, &mut thread_rng())
+    } else {
+        Rsa2048::generate(args.bits, &mut thread_rng())
+    }.with_algorithm(algorithm);
+
+    let out = match (&args.format, &args.algorithm) {
+        (Format::Ssh, Algorithm::Rsa) => Ssh::new(&rsa(), args.comment.clone()).to_pem(),
+        (Format::Ssh, Algorithm::Ed25519) => Ssh::new(&Ed25519::new(), args.comment.clone()).to_pem(),
+        (Format::Pkcs8, Algorithm::Rsa) => Pkcs8::new(Pkcs8::V1, &rsa()).to_pem(),
         (Format::Pkcs8, Algorithm::Ed25519) => Pkcs8::new(Pkcs8::V1, &Ed25519::new()).to_pem(),
+        (Format::Pkcs1, Algorithm::Rsa) => rsa().to_pem(),
+        (Format::Pkcs1, Algorithm::Ed25519) => fail("pkcs1 is only defined for rsa"),
+        (Format::Jwk, Algorithm::Rsa) => text_line(&Jwk::new(&rsa()).to_json()),
+        (Format::Jwk, Algorithm::Ed25519) => text_line(&Jwk::new(&Ed25519::new()).to_json()),
+        (Format::Ppk, Algorithm::Rsa) => Ppk::new(Ppk::V3, &rsa(), args.comment.clone()).to_ppk(None, &mut thread_rng()),
+        (Format::Ppk, Algorithm::Ed25519) => Ppk::new(Ppk::V3, &Ed25519::new(), args.comment.clone()).to_ppk(None, &mut thread_rng()),
+        (Format::Wireguard, _) => unreachable!("written by wireguard"),
+        (Format::Age, _) => unreachable!("written by age"),
+        (Format::Minisign | Format::Signify, _) => unreachable!("written by signing_key"),
     };
 
     match args.name {
@@ -55,3 +826,873 @@ fn main() {
         None => stdout().write_all(&out).unwrap(),
     };
 }
+
+// wg genkey, wg pubkey and wg genpsk in one go
+fn wireguard(name: &Option<String>, config: bool) {
+    let keys = WireGuard::new(&X25519::new(), &mut thread_rng());
+
+    match (name, config) {
+        (Some(x), true) => File::create(x).unwrap().write_all(&keys.to_config()).unwrap(),
+        (None, true) => stdout().write_all(&keys.to_config()).unwrap(),
+        (Some(x), false) => {
+            File::create(x).unwrap().write_all(&keys.private_key()).unwrap();
+            File::create(format!("{}.pub", x)).unwrap().write_all(&keys.public_key()).unwrap();
+            File::create(format!("{}.psk", x)).unwrap().write_all(&keys.preshared_key()).unwrap();
+        },
+        (None, false) => {
+            stdout().write_all(&keys.private_key()).unwrap();
+            stdout().write_all(&keys.public_key()).unwrap();
+            stdout().write_all(&keys.preshared_key()).unwrap();
+        },
+    };
+}
+
+// the public key goes to stderr when the identity is written to a file, as age-keygen -o does
+fn age(name: &Option<String>) {
+    let key = Age::new(&X25519::new());
+    let now = SystemTime::now().duration_since(UNIX_EPOCH).unwrap().as_secs();
+
+    match name {
+        Some(x) => {
+            File::create(x).unwrap().write_all(&key.to_keygen(now)).unwrap();
+            eprintln!("Public key: {}", key.recipient());
+        },
+        None => stdout().write_all(&key.to_keygen(now)).unwrap(),
+    };
+}
+
+fn age_convert(file: &str, identity: bool) {
+    let data = fs::read(file).unwrap();
+
+    if identity {
+        let key = match Key::from_pem(&data) {
+            Some(Key::Ed25519(key)) => key,
+            _ => fail("an age identity can only be made from an ed25519 private key"),
+        };
+        let now = SystemTime::now().duration_since(UNIX_EPOCH).unwrap().as_secs();
+        stdout().write_all(&Age::new(&key.to_x25519()).to_keygen(now)).unwrap();
+        return;
+    }
+
+    let recipient = match Age::from_identity(&data) {
+        Some(key) => key.recipient(),
+        None => {
+            let (blob, _) = read_ssh_public_key(file);
+            ssh_recipient(&blob).expect("not an ssh-ed25519 key")
+        },
+    };
+    println!("{}", recipient);
+}
+
+fn dns_sshfp(name: &str, keys: &[String]) {
+    for file in keys {
+        let (blob, _) = read_ssh_public_key(file);
+        print!("{}", sshfp(name, &blob).expect("SSHFP has no algorithm for this key type"));
+    }
+}
+
+fn dnskey(zone: &str, key: &str, ksk: bool) -> Dnskey {
+    let key = read_public_key(key);
+    if matches!(key, PublicKey::Rsa(ref x) if x.algorithm() != RsaAlgorithm::Rsa) {
+        fail("an rsa key restricted to PSS can not sign DNSSEC");
+    }
+    let key = Dnskey::new(zone, &key).unwrap_or_else(|| fail(&format!("{} is not a domain name", zone)));
+    if ksk { key.with_sep() } else { key }
+}
+
+// the certificate's subjectPublicKeyInfo, or the key's
+fn dns_tlsa(host: &str, file: &str, port: u16, protocol: &str) {
+    let data = fs::read(file).unwrap();
+    let spki = match Certificate::from_pem(&data) {
+        Some(certificate) => certificate.public_key().expect("malformed certificate").to_vec(),
+        None => read_public_key(file).to_spki().to_der().to_vec(),
+    };
+
+    print!("{}", tlsa(port, protocol, host, &spki));
+}
+
+// the chance of a regex is sampled on random keys, too rare to be seen in a million is reported as such
+fn vanity(pattern: &str, kind: &PatternKind, target: &PatternTarget, threads: Option<NonZeroUsize>, comment: &str, output: &Option<String>) {
+    let pattern = match kind {
+        PatternKind::Prefix => Pattern::prefix(pattern, target.to_target()).expect("no key can start with this pattern"),
+        PatternKind::Suffix => Pattern::suffix(pattern, target.to_target()).expect("no key can end with this pattern"),
+        PatternKind::Regex => Pattern::regex(pattern, target.to_target()).expect("invalid regex"),
+    };
+    let probability = pattern.probability().unwrap_or_else(|| pattern.sample(1_000_000, &mut thread_rng()));
+    let threads = threads.or_else(|| std::thread::available_parallelism().ok()).map_or(1, |x| x.get());
+    let expected = 1.0 / probability;
+    if probability > 0.0 {
+        eprintln!("1 in {:.0} keys matches", expected);
+    } else {
+        eprintln!("none of a million random keys matches, the search may never end");
+    }
+
+    let start = std::time::Instant::now();
+    let (key, attempts) = genkey::search(threads, |public_key| pattern.matches(public_key), |attempts| {
+        let rate = attempts as f64 / start.elapsed().as_secs_f64();
+        eprint!("\r{} keys tried, {:.0} keys/s, {} expected in all\x1b[K", attempts, rate, duration(expected / rate));
+    });
+    eprintln!("\r{} keys tried in {}\x1b[K", attempts, duration(start.elapsed().as_secs_f64()));
+
+    let ssh = Ssh::new(&key, comment.to_string());
+    let public_key = public_key_line(ssh.public_key(), comment);
+    match output {
+        Some(x) => {
+            File::create(x).unwrap().write_all(&ssh.to_pem()).unwrap();
+            File::create(format!("{}.pub", x)).unwrap().write_all(&public_key).unwrap();
+        },
+        None => stdout().write_all(&ssh.to_pem()).unwrap(),
+    };
+    stdout().write_all(&public_key).unwrap();
+}
+
+// seconds as the largest unit they make at least one of, or forever past a thousand years
+fn duration(seconds: f64) -> String {
+    const UNITS: [(&str, f64); 5] = [("years", 31557600.0), ("days", 86400.0), ("hours", 3600.0), ("minutes", 60.0), ("seconds", 1.0)];
+
+    if !seconds.is_finite() || seconds > 1000.0 * UNITS[0].1 {
+        return "forever".to_string();
+    }
+
+    match UNITS.iter().find(|(_, x)| seconds >= *x) {
+        Some((name, x)) => format!("{:.1} {}", seconds / x, name),
+        None => format!("{:.1} seconds", seconds),
+    }
+}
+
+// the files tor would write for a new onion service, the directory only readable by its owner
+// as tor requires of a HiddenServiceDir
+fn onion(dir: &str, vanity_prefix: &Option<String>, threads: Option<NonZeroUsize>) {
+    let key = match vanity_prefix {
+        Some(prefix) => {
+            let prefix = prefix.to_ascii_lowercase();
+            if prefix.len() > 56 {
+                fail("an onion address has 56 characters");
+            }
+            if !prefix.bytes().all(|x| matches!(x, b'a'..=b'z' | b'2'..=b'7')) {
+                fail("an onion address is made of a-z and 2-7");
+            }
+            let threads = threads.or_else(|| std::thread::available_parallelism().ok()).map_or(1, |x| x.get());
+            let (key, attempts) = genkey::search(threads, |public_key| onion_address(public_key).starts_with(&prefix), |_| ());
+            eprintln!("{} keys tried", attempts);
+            key
+        },
+        None => Ed25519::new(),
+    };
+    let service = OnionService::new(&key);
+
+    let mut builder = fs::DirBuilder::new();
+    builder.recursive(true);
+    #[cfg(unix)]
+    std::os::unix::fs::DirBuilderExt::mode(&mut builder, 0o700);
+    builder.create(dir).unwrap();
+
+    let dir = std::path::Path::new(dir);
+    File::create(dir.join("hs_ed25519_secret_key")).unwrap().write_all(&service.to_secret_key()).unwrap();
+    File::create(dir.join("hs_ed25519_public_key")).unwrap().write_all(&service.to_public_key()).unwrap();
+    File::create(dir.join("hostname")).unwrap().write_all(&service.to_hostname()).unwrap();
+    println!("{}", service.address());
+}
+
+// minisign -G or signify -G, the secret key in <name> and the public key in <name>.pub,
+// or for signify in <name> without .sec and .pub as signify names its keys.
+// An empty comment is "signify" as with signify; minisign keys have no comment.
+fn signing_key(key: Ed25519, format: &Format, comment: &str, password: Option<&str>, name: &Option<String>) {
+    let (secret_key, public_key) = match format {
+        Format::Minisign => {
+            let key = Minisign::new(key, &mut thread_rng());
+            (key.to_secret_key(password, &mut thread_rng()), key.to_public_key())
+        },
+        Format::Signify => {
+            let comment = if comment.is_empty() { "signify" } else { comment };
+            let key = Signify::new(key, comment.to_string(), &mut thread_rng());
+            (key.to_secret_key(password, &mut thread_rng()), key.to_public_key())
+        },
+        _ => unreachable!("not a minisign or signify format"),
+    };
+
+    match name {
+        Some(x) => {
+            let public_name = match (format, x.strip_suffix(".sec")) {
+                (Format::Signify, Some(stem)) => format!("{}.pub", stem),
+                _ => format!("{}.pub", x),
+            };
+            File::create(x).unwrap().write_all(&secret_key).unwrap();
+            File::create(public_name).unwrap().write_all(&public_key).unwrap();
+        },
+        None => {
+            stdout().write_all(&secret_key).unwrap();
+            stdout().write_all(&public_key).unwrap();
+        },
+    };
+}
+
+fn minisign_sign(key: &str, message: &str, password: &Option<String>, trusted_comment: &Option<String>, output: &Option<String>) {
+    let key = Minisign::from_secret_key(&fs::read(key).unwrap(), password.as_deref()).expect("unsupported minisign secret key or wrong password");
+    let trusted_comment = match trusted_comment {
+        Some(x) => x.clone(),
+        None => {
+            let now = SystemTime::now().duration_since(UNIX_EPOCH).unwrap().as_secs();
+            let file = std::path::Path::new(message).file_name().unwrap().to_string_lossy();
+            format!("timestamp:{}\tfile:{}\thashed", now, file)
+        },
+    };
+
+    let signature = key.sign(&fs::read(message).unwrap(), &trusted_comment);
+    let output = output.clone().unwrap_or(format!("{}.minisig", message));
+    File::create(output).unwrap().write_all(&signature).unwrap();
+}
+
+fn minisign_verify(key: &str, message: &str, signature: &Option<String>) {
+    // the public key itself when there is no such file
+    let key = fs::read(key).unwrap_or(key.as_bytes().to_vec());
+    let signature = signature.clone().unwrap_or(format!("{}.minisig", message));
+
+    match Minisign::verify(&key, &fs::read(message).unwrap(), &fs::read(signature).unwrap()) {
+        Some(trusted_comment) => {
+            println!("signature ok");
+            println!("trusted comment: {}", trusted_comment);
+        },
+        None => {
+            println!("bad signature");
+            std::process::exit(1);
+        },
+    };
+}
+
+// the untrusted comment points at the public key, <key>.pub or <key>.sec renamed to .pub
+fn signify_sign(key: &str, message: &str, password: &Option<String>, output: &Option<String>) {
+    let secret_key = Signify::from_secret_key(&fs::read(key).unwrap(), password.as_deref()).expect("unsupported signify secret key or wrong password");
+    let key = std::path::Path::new(key).file_name().unwrap().to_string_lossy();
+    let public_key = match key.strip_suffix(".sec") {
+        Some(x) => format!("{}.pub", x),
+        None => format!("{}.pub", key),
+    };
+
+    let signature = secret_key.sign(&fs::read(message).unwrap(), &public_key);
+    let output = output.clone().unwrap_or(format!("{}.sig", message));
+    File::create(output).unwrap().write_all(&signature).unwrap();
+}
+
+fn signify_verify(key: &str, message: &str, signature: &Option<String>) {
+    let signature = signature.clone().unwrap_or(format!("{}.sig", message));
+
+    if Signify::verify(&fs::read(key).unwrap(), &fs::read(message).unwrap(), &fs::read(signature).unwrap()) {
+        println!("signature ok");
+    } else {
+        println!("bad signature");
+        std::process::exit(1);
+    }
+}
+
+fn inspect(file: &str) {
+    let data = fs::read(file).unwrap();
+    let out = match Key::from_pem(&data) {
+        Some(key) => key.inspect(),
+        None => PublicKey::from_pem(&data).expect("unsupported key file").inspect(),
+    };
+
+    stdout().write_all(out.as_bytes()).unwrap();
+}
+
+fn public(file: &str) {
+    let data = fs::read(file).unwrap();
+    let key = Key::from_pem(&data).expect("unsupported key file");
+
+    stdout().write_all(&key.to_spki().to_pem()).unwrap();
+}
+
+fn sign(key: &str, message: &str, output: &Option<String>, options: &SignOptions) {
+    let data = fs::read(key).unwrap();
+    let key = Key::from_pem(&data).expect("unsupported key file");
+    let message = fs::read(message).unwrap();
+
+    let signature = match key {
+        Key::Ed25519(key) => match (options.prehash, &options.context) {
+            (false, None) => key.sign(&message),
+            (false, Some(context)) => key.sign_ctx(&message, context.as_bytes())
+                .unwrap_or_else(|| fail("an Ed25519ctx --context is 1 to 255 bytes")),
+            (true, context) => key.sign_ph(&message, context.as_deref().unwrap_or("").as_bytes())
+                .unwrap_or_else(|| fail("an Ed25519ph --context is at most 255 bytes")),
+        },
+        Key::Rsa(key) => match rsa_scheme(key.algorithm(), options) {
+            Some(params) => key.sign_pss(&params, &message),
+            None => key.sign_pkcs1_v1_5(options.hash.to_hash(), &message),
+        },
+    };
+
+    match output {
+        Some(x) => File::create(x).unwrap().write_all(&signature).unwrap(),
+        None => stdout().write_all(&signature).unwrap(),
+    };
+}
+
+fn verify(key: &str, message: &str, signature: &str, options: &SignOptions) {
+    let data = fs::read(key).unwrap();
+    let key = match Key::from_pem(&data) {
+        Some(key) => key.public_key(),
+        None => PublicKey::from_pem(&data).expect("unsupported key file"),
+    };
+    let message = fs::read(message).unwrap();
+    let signature = fs::read(signature).unwrap();
+
+    let ok = match key {
+        PublicKey::Ed25519(key) => match (options.prehash, &options.context) {
+            (false, None) => Ed25519::verify(&key, &message, &signature),
+            (false, Some(context)) => Ed25519::verify_ctx(&key, &message, context.as_bytes(), &signature),
+            (true, context) => Ed25519::verify_ph(&key, &message, context.as_deref().unwrap_or("").as_bytes(), &signature),
+        },
+        PublicKey::Rsa(key) => match rsa_scheme(key.algorithm(), options) {
+            Some(params) => key.verify_pss(&params, &message, &signature),
+            None => key.verify_pkcs1_v1_5(options.hash.to_hash(), &message, &signature),
+        },
+    };
+
+    if ok {
+        println!("signature ok");
+    } else {
+        println!("bad signature");
+        std::process::exit(1);
+    }
+}
+
+fn encrypt(key: &str, input: &str, output: &Option<String>, label: &str) {
+    let data = fs::read(key).unwrap();
+    let key = match Key::from_pem(&data) {
+        Some(key) => key.public_key(),
+        None => PublicKey::from_pem(&data).expect("unsupported key file"),
+    };
+    let key = match key {
+        PublicKey::Rsa(key) => key,
+        PublicKey::Ed25519(_) => fail("encryption needs an rsa key"),
+    };
+    if key.algorithm() != RsaAlgorithm::Rsa {
+        fail("an rsa key restricted to PSS can not encrypt");
+    }
+    let input = fs::read(input).unwrap();
+
+    let ciphertext = key.encrypt_oaep(Hash::Sha256, label.as_bytes(), &input).unwrap_or_else(|| fail("message too long for the key"));
+
+    match output {
+        Some(x) => File::create(x).unwrap().write_all(&ciphertext).unwrap(),
+        None => stdout().write_all(&ciphertext).unwrap(),
+    };
+}
+
+fn decrypt(key: &str, input: &str, output: &Option<String>, label: &str, pkcs1: bool) {
+    let data = fs::read(key).unwrap();
+    let key = match Key::from_pem(&data).expect("unsupported key file") {
+        Key::Rsa(key) => key,
+        Key::Ed25519(_) => fail("decryption needs an rsa key"),
+    };
+    if key.algorithm() != RsaAlgorithm::Rsa {
+        fail("an rsa key restricted to PSS can not decrypt");
+    }
+    let input = fs::read(input).unwrap();
+
+    let plaintext = if pkcs1 {
+        key.decrypt_pkcs1_v1_5(&input)
+    } else {
+        key.decrypt_oaep(Hash::Sha256, label.as_bytes(), &input)
+    };
+    let plaintext = match plaintext {
+        Some(x) => x,
+        None => {
+            eprintln!("decryption error");
+            std::process::exit(1);
+        },
+    };
+
+    match output {
+        Some(x) => File::create(x).unwrap().write_all(&plaintext).unwrap(),
+        None => stdout().write_all(&plaintext).unwrap(),
+    };
+}
+
+fn ssh_sign(key: &str, message: &str, namespace: &str, output: &Option<String>, hash: &HashName) {
+    let data = fs::read(key).unwrap();
+    let key = Key::from_pem(&data).expect("unsupported key file");
+    let message = fs::read(message).unwrap();
+
+    let signature = match key {
+        Key::Rsa(ref key) if key.algorithm() != RsaAlgorithm::Rsa => fail("an rsa key restricted to PSS can not make ssh signatures"),
+        Key::Rsa(key) => SshSig::new(&key, namespace, hash.to_hash(), &message),
+        Key::Ed25519(key) => SshSig::new(&key, namespace, hash.to_hash(), &message),
+    };
+    let signature = signature.unwrap_or_else(|| fail("SSHSIG needs a non-empty --namespace and --hash sha256 or sha512")).to_pem();
+
+    match output {
+        Some(x) => File::create(x).unwrap().write_all(&signature).unwrap(),
+        None => stdout().write_all(&signature).unwrap(),
+    };
+}
+
+fn ssh_verify(allowed_signers: &str, message: &str, signature: &str, identity: &str, namespace: &str) {
+    let allowed_signers = fs::read_to_string(allowed_signers).unwrap();
+    let allowed_signers = AllowedSigners::parse(&allowed_signers).expect("malformed allowed signers file");
+    let message = fs::read(message).unwrap();
+    let signature = SshSig::from_pem(&fs::read(signature).unwrap()).expect("unsupported signature file");
+    let now = SystemTime::now().duration_since(UNIX_EPOCH).unwrap().as_secs();
+
+    if signature.verify(namespace, &message) && allowed_signers.is_allowed(identity, namespace, signature.public_key(), now) {
+        let key_type = match PublicKey::from_ssh(signature.public_key()) {
+            Some(PublicKey::Rsa(_)) => "RSA",
+            _ => "ED25519",
+        };
+        println!("Good \"{}\" signature for {} with {} key {}", namespace, identity, key_type, signature.fingerprint());
+    } else {
+        println!("bad signature");
+        std::process::exit(1);
+    }
+}
+
+fn ssh_cert(ca: &str, public_key: &str, output: &Option<String>, options: &CertOptions) {
+    let data = fs::read(ca).unwrap();
+    let ca = Key::from_pem(&data).expect("unsupported CA key file");
+    if matches!(ca, Key::Rsa(ref key) if key.algorithm() != RsaAlgorithm::Rsa) {
+        fail("an rsa key restricted to PSS can not sign ssh certificates");
+    }
+    let (blob, comment) = read_ssh_public_key(public_key);
+    let now = SystemTime::now().duration_since(UNIX_EPOCH).unwrap().as_secs();
+    let (valid_after, valid_before) = parse_validity(&options.validity, now).expect("invalid validity interval");
+
+    if options.host && (options.force_command.is_some() || options.source_address.is_some()) {
+        fail("host certificates have no critical options, --force-command and --source-address are for user certificates");
+    }
+
+    let cert_type = if options.host { CertType::Host } else { CertType::User };
+    let mut certificate = SshCertificate::new(&blob, cert_type, &options.key_id)
+        .with_serial(options.serial)
+        .with_principals(options.principals.split(',').filter(|x| !x.is_empty()).map(|x| x.to_string()).collect())
+        .with_validity(valid_after, valid_before);
+    if let Some(ref command) = options.force_command {
+        certificate = certificate.with_critical_option("force-command", Some(command));
+    }
+    if let Some(ref addresses) = options.source_address {
+        certificate = certificate.with_critical_option("source-address", Some(addresses));
+    }
+    if options.clear {
+        certificate = certificate.without_extensions();
+    }
+    for extension in options.extension.iter() {
+        certificate = match extension.split_once('=') {
+            Some((name, value)) => certificate.with_extension(name, Some(value)),
+            None => certificate.with_extension(extension, None),
+        };
+    }
+
+    let signed = match ca {
+        Key::Rsa(key) => certificate.sign(&key),
+        Key::Ed25519(key) => certificate.sign(&key),
+    };
+
+    let output = match output {
+        Some(x) => x.clone(),
+        None => format!("{}-cert.pub", public_key.strip_suffix(".pub").unwrap_or(public_key)),
+    };
+    File::create(output).unwrap().write_all(&public_key_line(&signed, &comment)).unwrap();
+}
+
+fn self_sign(key: &str, subject: &str, san: &[String], days: u64, ca: bool, output: &Option<String>) {
+    let data = fs::read(key).unwrap();
+    let key = Key::from_pem(&data).expect("unsupported key file");
+    let subject = Name::parse(subject).expect("invalid subject name");
+    // the subject is also the issuer, which can not be empty
+    if subject.is_empty() {
+        fail("a self-signed certificate needs a subject, e.g. /CN=example.com");
+    }
+    let alt_names = san.iter().map(|x| GeneralName::parse(x).expect("invalid subject alternative name")).collect();
+    let now = SystemTime::now().duration_since(UNIX_EPOCH).unwrap().as_secs();
+    let not_after = days.checked_mul(86400).and_then(|x| now.checked_add(x)).unwrap_or_else(|| fail("--days is too large"));
+
+    // keyEncipherment only where the key can encrypt, an rsa key not restricted to PSS
+    let key_usage = match (&key, ca) {
+        (_, true) => vec![KeyUsage::DigitalSignature, KeyUsage::KeyCertSign, KeyUsage::CrlSign],
+        (Key::Rsa(x), false) if x.algorithm() == RsaAlgorithm::Rsa => vec![KeyUsage::DigitalSignature, KeyUsage::KeyEncipherment],
+        _ => vec![KeyUsage::DigitalSignature],
+    };
+    let certificate = TbsCertificate::new(subject, &key.to_spki())
+        .with_validity(now, not_after)
+        .with_subject_alt_names(alt_names)
+        .with_basic_constraints(ca, None)
+        .with_key_usage(&key_usage)
+        .sign(&key)
+        .to_pem();
+
+    match output {
+        Some(x) => File::create(x).unwrap().write_all(&certificate).unwrap(),
+        None => stdout().write_all(&certificate).unwrap(),
+    };
+}
+
+fn csr(key: &str, subject: &str, san: &[String], output: &Option<String>) {
+    let data = fs::read(key).unwrap();
+    let key = Key::from_pem(&data).expect("unsupported key file");
+    let subject = Name::parse(subject).expect("invalid subject name");
+    let alt_names = san.iter().map(|x| GeneralName::parse(x).expect("invalid subject alternative name")).collect();
+
+    let request = CertificationRequestInfo::new(subject, &key.to_spki())
+        .with_subject_alt_names(alt_names)
+        .sign(&key)
+        .to_pem();
+
+    match output {
+        Some(x) => File::create(x).unwrap().write_all(&request).unwrap(),
+        None => stdout().write_all(&request).unwrap(),
+    };
+}
+
+fn export_key(key: &str, format: Format, options: &ExportOptions, output: &Option<String>) {
+    let comment = read_key_comment(&fs::read(key).unwrap(), options.password.as_deref());
+    let key = read_key(key, options.password.as_deref());
+    let version = if options.legacy { Ppk::V2 } else { Ppk::V3 };
+    let out = match (format, &key) {
+        (Format::Ssh | Format::Ppk, Key::Rsa(key)) if key.primes() > 2 => fail("multi-prime rsa keys can not be written in the openssh or ppk format"),
+        (Format::Ssh, Key::Rsa(key)) => Ssh::new(key, comment.clone()).to_pem(),
+        (Format::Ssh, Key::Ed25519(key)) => Ssh::new(key, comment.clone()).to_pem(),
+        (Format::Pkcs8, Key::Rsa(key)) => Pkcs8::new(Pkcs8::V1, key).to_pem(),
+        (Format::Pkcs8, Key::Ed25519(key)) => Pkcs8::new(Pkcs8::V1, key).to_pem(),
+        (Format::Pkcs1, Key::Rsa(key)) => key.to_pem(),
+        (Format::Pkcs1, Key::Ed25519(_)) => fail("pkcs1 is only defined for rsa"),
+        (Format::Jwk, key) => text_line(&Jwk::new(key).to_json()),
+        (Format::Ppk, Key::Rsa(key)) => Ppk::new(version, key, comment.clone()).to_ppk(options.password.as_deref(), &mut thread_rng()),
+        (Format::Ppk, Key::Ed25519(key)) => Ppk::new(version, key, comment.clone()).to_ppk(options.password.as_deref(), &mut thread_rng()),
+        (Format::Wireguard, _) => fail("a wireguard key is x25519, which rsa and ed25519 keys can not be exported to"),
+        (Format::Age, _) => fail("an age identity is x25519, see the age command for ed25519 keys"),
+        (Format::Minisign | Format::Signify, _) => unreachable!("written by export_signing_key"),
+    };
+
+    match output {
+        Some(x) => File::create(x).unwrap().write_all(&out).unwrap(),
+        None => stdout().write_all(&out).unwrap(),
+    };
+}
+
+fn export_signing_key(key: &str, format: Format, options: &ExportOptions, output: &Option<String>) {
+    let key = match read_key(key, options.password.as_deref()) {
+        Key::Ed25519(key) => key,
+        Key::Rsa(_) => fail("minisign and signify keys are ed25519"),
+    };
+
+    signing_key(key, &format, "", options.password.as_deref(), output);
+}
+
+fn export_jwk(key: &str, export_options: &ExportOptions, options: &JwkOptions, output: &Option<String>) {
+    let data = fs::read(key).unwrap();
+    let mut jwk = match (parse_key(&data, export_options.password.as_deref()), options.public) {
+        // RFC 7518 section 6.3.2.7 "oth" is an array, which a Jwk does not hold
+        (Some(Key::Rsa(ref key)), false) if key.primes() > 2 => fail("multi-prime rsa keys can not be written as a JWK, use --public for the public key"),
+        (Some(key), false) => Jwk::new(&key),
+        (Some(key), true) => Jwk::new(&key.public_key()),
+        (None, true) => Jwk::new(&PublicKey::from_pem(&data).expect("unsupported key file")),
+        (None, false) => fail("unsupported private key file, use --public for a public key"),
+    };
+    if let Some(ref key_use) = options.key_use {
+        jwk = jwk.with_use(match key_use {
+            KeyUse::Sig => "sig",
+            KeyUse::Enc => "enc",
+        });
+    }
+    if let Some(ref alg) = options.alg {
+        jwk = jwk.with_alg(alg);
+    }
+    if options.kid {
+        let kid = jwk.thumbprint().unwrap();
+        jwk = jwk.with_kid(&kid);
+    }
+
+    let out = if options.jwks { jwks(&[jwk]) } else { jwk.to_json() };
+    let out = text_line(&out);
+    match output {
+        Some(x) => File::create(x).unwrap().write_all(&out).unwrap(),
+        None => stdout().write_all(&out).unwrap(),
+    };
+}
+
+fn export_p12(key: &str, options: &ExportOptions, output: &Option<String>) {
+    let key = read_key(key, options.password.as_deref());
+    let private_key = match key {
+        Key::Rsa(ref key) => Pkcs8::new(Pkcs8::V1, key),
+        Key::Ed25519(ref key) => Pkcs8::new(Pkcs8::V1, key),
+    };
+
+    let mut pkcs12 = Pkcs12::new(&private_key, &key.to_spki());
+    if let Some(ref cert) = options.cert {
+        let certificate = Certificate::from_pem(&fs::read(cert).unwrap()).expect("unsupported certificate file");
+        if certificate.public_key() != Some(&key.to_spki().to_der()[..]) {
+            fail("the certificate is not for this key");
+        }
+        pkcs12 = pkcs12.with_certificate(&certificate);
+    }
+    if let Some(ref friendly_name) = options.friendly_name {
+        pkcs12 = pkcs12.with_friendly_name(friendly_name);
+    }
+    if options.legacy {
+        pkcs12 = pkcs12.with_legacy_encryption();
+    }
+    let out = pkcs12.to_pfx(options.password.as_deref().unwrap(), &mut thread_rng());
+
+    match output {
+        Some(x) => File::create(x).unwrap().write_all(&out).unwrap(),
+        None => stdout().write_all(&out).unwrap(),
+    };
+}
+
+fn jwt_sign(claims_file: &str, key: &str, alg: &Option<JwtAlgorithm>, kid: &Option<String>, output: &Option<String>) {
+    let payload = claims(&fs::read_to_string(claims_file).unwrap()).expect("the claims must be a JSON object");
+    let key = read_key(key, None);
+    let algorithm = match alg {
+        Some(JwtAlgorithm::EdDsa) => JwsAlgorithm::EdDsa,
+        Some(JwtAlgorithm::Rs256) => JwsAlgorithm::Rs256,
+        Some(JwtAlgorithm::Ps256) => JwsAlgorithm::Ps256,
+        None => JwsAlgorithm::for_key(&key),
+    };
+    let mut header = vec![("typ", "JWT")];
+    if let Some(ref kid) = kid {
+        header.push(("kid", kid));
+    }
+
+    let token = match Jws::new(&key, algorithm, &header, payload.as_bytes()) {
+        Some(jws) => jws.to_compact(),
+        None => fail(&format!("{} can not be used with this key", algorithm.name())),
+    };
+    let out = text_line(token.as_bytes());
+    match output {
+        Some(x) => File::create(x).unwrap().write_all(&out).unwrap(),
+        None => stdout().write_all(&out).unwrap(),
+    };
+}
+
+fn jwt_verify(key: &str, token: &str) {
+    let jws = Jws::from_compact(&fs::read_to_string(token).unwrap()).expect("malformed token");
+    let kid = jws.header().get("kid").and_then(Json::as_str);
+    let data = fs::read(key).unwrap();
+    let key = match std::str::from_utf8(&data).ok().and_then(read_jwks) {
+        Some(keys) => match select_jwk(&keys, kid) {
+            Some(jwk) => PublicKey::from_jwk(jwk).unwrap_or_else(|| fail("unsupported key in the JWK set")),
+            None if kid.is_some() => fail("no key in the JWK set has the kid of the token"),
+            None => fail("the token has no kid to pick a key of the JWK set"),
+        },
+        None => match parse_key(&data, None) {
+            Some(key) => key.public_key(),
+            None => PublicKey::from_pem(&data).expect("unsupported key file"),
+        },
+    };
+    let claims = std::str::from_utf8(jws.payload()).ok().and_then(Json::parse);
+    let now = SystemTime::now().duration_since(UNIX_EPOCH).unwrap().as_secs();
+
+    if !jws.verify(&key) {
+        println!("bad signature");
+        std::process::exit(1);
+    }
+    match claims {
+        Some(ref claims) if check_time(claims, now) => {
+            println!("signature ok");
+            println!("{}", claims.to_json());
+        },
+        Some(_) => {
+            println!("token expired or not yet valid");
+            std::process::exit(1);
+        },
+        None => {
+            println!("the claims are not JSON");
+            std::process::exit(1);
+        },
+    }
+}
+
+fn pgp(user_id: &str, algorithm: &Algorithm, subkey: &Option<SubkeyAlgorithm>, bits: usize, days: Option<u64>, password: &Option<String>, output: &Option<String>) {
+    if (matches!(algorithm, Algorithm::Rsa) || matches!(subkey, Some(SubkeyAlgorithm::Rsa))) && bits < Rsa2048::MIN_BITS {
+        fail(&format!("rsa keys need --bits of at least {}", Rsa2048::MIN_BITS));
+    }
+
+    let primary = match algorithm {
+        Algorithm::Rsa => Key::Rsa(Rsa2048::generate(bits, &mut thread_rng())),
+        Algorithm::Ed25519 => Key::Ed25519(Ed25519::new()),
+    };
+    let now = SystemTime::now().duration_since(UNIX_EPOCH).unwrap().as_secs();
+
+    let mut key = TransferableSecretKey::new(primary, user_id, now).unwrap_or_else(|| fail("an rsa key restricted to PSS can not be an OpenPGP key"));
+    if let Some(days) = days {
+        // OpenPGP keeps the expiration in 32 bits of seconds
+        match days.checked_mul(86400).filter(|x| (1..=u32::MAX as u64).contains(x)) {
+            Some(seconds) => key = key.with_expiration(seconds),
+            None => fail(&format!("--days must be 1 to {}", u32::MAX as u64 / 86400)),
+        }
+    }
+    key = match subkey {
+        Some(SubkeyAlgorithm::Rsa) => key.with_subkey(&Rsa2048::generate(bits, &mut thread_rng())),
+        Some(SubkeyAlgorithm::X25519) => key.with_subkey(&X25519::new()),
+        None => key,
+    };
+    let out = armor("PGP PRIVATE KEY BLOCK", &key.to_pgp(password.as_deref(), &mut thread_rng()));
+
+    // as GnuPG shows it, in groups of four with a wider gap in the middle
+    let fingerprint: Vec<String> = key.fingerprint().chunks(2).map(|x| format!("{:02X}{:02X}", x[0], x[1])).collect();
+    let fingerprint = format!("{}  {}", fingerprint[..5].join(" "), fingerprint[5..].join(" "));
+    match output {
+        Some(x) => {
+            File::create(x).unwrap().write_all(&out).unwrap();
+            println!("{}", fingerprint);
+        },
+        // the key alone goes to stdout
+        None => {
+            stdout().write_all(&out).unwrap();
+            eprintln!("{}", fingerprint);
+        },
+    };
+}
+
+// writes a KRL with what options revoke, on top of krl when updating
+fn krl_create(file: &str, options: &KrlOptions, krl: Option<Krl>) {
+    let signer = options.sign.as_ref().map(|x| Key::from_pem(&fs::read(x).unwrap()).expect("unsupported key file"));
+    if matches!(signer, Some(Key::Rsa(ref key)) if key.algorithm() != RsaAlgorithm::Rsa) {
+        fail("an rsa key restricted to PSS can not sign a KRL");
+    }
+    let now = SystemTime::now().duration_since(UNIX_EPOCH).unwrap().as_secs();
+    let mut krl = match krl {
+        Some(krl) => {
+            let version = krl.version() + 1;
+            krl.with_version(version, now)
+        },
+        None => Krl::new(1, now, &options.comment),
+    };
+
+    let ca = options.ca.as_ref().map(|x| read_ssh_public_key(x).0).unwrap_or_default();
+    if !options.serial.is_empty() && ca.is_empty() {
+        fail("revoking serials needs --ca");
+    }
+    for serial in options.serial.iter() {
+        let (low, high) = serial.split_once('-').unwrap_or((serial, serial));
+        match (low.parse(), high.parse()) {
+            (Ok(low), Ok(high)) if 0 < low && low <= high => krl.revoke_serials(&ca, low, high),
+            _ => fail(&format!("invalid serial {}, serials start at 1", serial)),
+        }
+    }
+    for key_id in options.key_id.iter() {
+        krl.revoke_key_id(&ca, key_id);
+    }
+    for key in options.key.iter() {
+        krl.revoke_key(&read_ssh_public_key(key).0);
+    }
+    for key in options.sha256.iter() {
+        if key.starts_with("SHA256:") {
+            krl.revoke_fingerprint(key).expect("invalid fingerprint");
+        } else {
+            krl.revoke_sha256(&Hash::Sha256.digest(&read_ssh_public_key(key).0));
+        }
+    }
+
+    let out = match signer {
+        Some(Key::Rsa(key)) => krl.to_bytes(&[&key]),
+        Some(Key::Ed25519(key)) => krl.to_bytes(&[&key]),
+        None => krl.to_bytes(&[]),
+    };
+    File::create(file).unwrap().write_all(&out).unwrap();
+}
+
+fn krl_test(krl: &str, keys: &[String]) {
+    let krl = Krl::from_bytes(&fs::read(krl).unwrap()).expect("malformed or badly signed KRL");
+
+    let mut revoked = false;
+    for file in keys.iter() {
+        if krl.is_revoked(&read_ssh_public_key(file).0) {
+            println!("{}: REVOKED", file);
+            revoked = true;
+        } else {
+            println!("{}: ok", file);
+        }
+    }
+    if revoked {
+        std::process::exit(1);
+    }
+}
+
+// private key file in PEM, a PuTTY .ppk (encrypted with passphrase) or a JWK or a JWK set with a single key
+fn read_key(file: &str, passphrase: Option<&str>) -> Key {
+    let data = fs::read(file).unwrap();
+    parse_key(&data, passphrase).expect("unsupported key file or wrong password")
+}
+
+// the comment of an OpenSSH or PuTTY private key file, empty for the other formats
+fn read_key_comment(data: &[u8], passphrase: Option<&str>) -> String {
+    if data.starts_with(b"PuTTY-User-Key-File-") {
+        return Ppk::from_ppk(data, passphrase).map(|x| x.comment().to_string()).unwrap_or_default();
+    }
+    read_private_key_comment(data).unwrap_or_default()
+}
+
+fn parse_key(data: &[u8], passphrase: Option<&str>) -> Option<Key> {
+    if let Some(key) = Key::from_pem(data) {
+        return Some(key);
+    }
+    if data.starts_with(b"PuTTY-User-Key-File-") {
+        return Ppk::from_ppk(data, passphrase)?.to_key();
+    }
+    match read_jwks(std::str::from_utf8(data).ok()?)?.as_slice() {
+        [jwk] => Key::from_jwk(jwk),
+        _ => None,
+    }
+}
+
+// the key of a JWK set that signed a token: the one with the token's kid,
+// or the only key of the set when the token has no kid
+fn select_jwk<'a>(keys: &'a [Jwk], kid: Option<&str>) -> Option<&'a Jwk> {
+    match (kid, keys) {
+        (Some(kid), _) => keys.iter().find(|x| x.get("kid") == Some(kid)),
+        (None, [jwk]) => Some(jwk),
+        (None, _) => None,
+    }
+}
+
+// JSON and token text end with a line feed in a file
+fn text_line(json: &[u8]) -> Vec<u8> {
+    let mut out = json.to_vec();
+    out.push(b'\n');
+    out
+}
+
+// public key blob and comment of an OpenSSH public key or certificate file,
+// or of any private key file
+fn read_ssh_public_key(file: &str) -> (Vec<u8>, String) {
+    let data = fs::read(file).unwrap();
+    match Key::from_pem(&data) {
+        Some(key) => (key.public_key().to_ssh(), String::new()),
+        None => read_public_key_line(&data).expect("unsupported public key file"),
+    }
+}
+
+// the public key of a private key, a public key, an ssh public key line
+// or a raw 32 byte ed25519 public key
+fn read_public_key(file: &str) -> PublicKey {
+    let data = fs::read(file).unwrap();
+    match Key::from_pem(&data) {
+        Some(key) => key.public_key(),
+        None if data.len() == 32 => PublicKey::Ed25519(data),
+        None => PublicKey::from_pem(&data)
+            .or_else(|| PublicKey::from_ssh(&read_public_key_line(&data)?.0))
+            .expect("unsupported public key file"),
+    }
+}
+
+// RSASSA-PSS parameters to sign or verify with, None for PKCS #1 v1.5.
+// A key restricted to PSS brings its own parameters.
+fn rsa_scheme(algorithm: RsaAlgorithm, options: &SignOptions) -> Option<PssParams> {
+    match algorithm {
+        RsaAlgorithm::Pss(Some(params)) => Some(params),
+        RsaAlgorithm::Pss(None) => Some(pss_params(options.hash.to_hash())),
+        RsaAlgorithm::Rsa if options.pss => Some(pss_params(options.hash.to_hash())),
+        RsaAlgorithm::Rsa => None,
+    }
+}
+
+// hash and MGF1 hash are the same, the salt is as long as the hash (RFC 8017 section 9.1)
+fn pss_params(hash: Hash) -> PssParams {
+    PssParams::new(hash, hash, hash.output_len() as u32)
+}
+
+// an error in what was asked for: the message on stderr and exit status 1
+fn fail(message: &str) -> ! {
+    eprintln!("error: {}", message);
+    std::process::exit(1);
+}
@@ -0,0 +1,250 @@
+use rand::Rng;
+use blake2::{Blake2b, Blake2b512, Digest};
+use blake2::digest::consts::U32;
+use crate::pem;
+use crate::scrypt;
+use crate::ed25519::Ed25519;
+use crate::secret::Secret;
+
+// minisign keys and signatures (jedisct1.github.io/minisign), Ed25519 keys
+// named by a random 8 byte key ID that signatures carry too
+pub struct Minisign {
+    key: Ed25519,
+    key_id: [u8; 8],
+    opslimit: u64,
+    memlimit: u64,
+}
+
+impl Minisign {
+    // Ed25519 over the message, legacy; over its BLAKE2b-512 hash, what minisign signs with
+    const ED25519: [u8; 2] = *b"Ed";
+    const ED25519_HASHED: [u8; 2] = *b"ED";
+    const KDF_SCRYPT: [u8; 2] = *b"Sc";
+    const KDF_NONE: [u8; 2] = [0, 0];
+    const CHECKSUM_BLAKE2B: [u8; 2] = *b"B2";
+
+    // crypto_pwhash_scryptsalsa208sha256 OPSLIMIT_SENSITIVE and MEMLIMIT_SENSITIVE of libsodium
+    // as minisign -G uses them: N = 2^20, r = 8, p = 1, a gigabyte of memory
+    pub const OPSLIMIT: u64 = 33554432;
+    pub const MEMLIMIT: u64 = 1073741824;
+
+    pub fn new<R: Rng + ?Sized>(key: Ed25519, rng: &mut R) -> Self {
+        Minisign{
+            key,
+            key_id: rng.gen(),
+            opslimit: Minisign::OPSLIMIT,
+            memlimit: Minisign::MEMLIMIT,
+        }
+    }
+
+    // scrypt limits an encrypted secret key is written with, libsodium's
+    // opslimit and memlimit; minisign reads them back from the key
+    pub fn with_scrypt_limits(mut self, opslimit: u64, memlimit: u64) -> Self {
+        self.opslimit = opslimit;
+        self.memlimit = memlimit;
+        self
+    }
+
+    pub fn key(&self) -> &Ed25519 {
+        &self.key
+    }
+
+    // as minisign prints it, the key ID read as a little-endian number in uppercase hex
+    pub fn key_id(&self) -> String {
+        format!("{:016X}", u64::from_le_bytes(self.key_id))
+    }
+
+    // untrusted comment line, then base64 of
+    // signature algorithm "Ed", KDF algorithm "Sc" or 0 0, checksum algorithm "B2",
+    // KDF salt (32), opslimit (8, little-endian), memlimit (8, little-endian),
+    // then encrypted: key ID (8), secret key seed || public key (64),
+    // checksum BLAKE2b-256(signature algorithm || key ID || secret key) (32).
+    // Without a password, or with an empty one, the KDF algorithm is 0 0 and the KDF fields are zero.
+    pub fn to_secret_key<R: Rng + ?Sized>(&self, password: Option<&str>, rng: &mut R) -> Secret {
+        let password = password.filter(|x| !x.is_empty());
+        let mut keys = Secret::with_capacity(104);
+        keys.extend(self.key_id);
+        keys.extend(self.key.to_keypair());
+        keys.extend(self.checksum());
+
+        let mut blob = Secret::with_capacity(158);
+        blob.extend(Minisign::ED25519);
+        match password {
+            Some(password) => {
+                let salt: [u8; 32] = rng.gen();
+                blob.extend(Minisign::KDF_SCRYPT);
+                blob.extend(Minisign::CHECKSUM_BLAKE2B);
+                blob.extend(salt);
+                blob.extend(self.opslimit.to_le_bytes());
+                blob.extend(self.memlimit.to_le_bytes());
+                let stream = kdf(password, &salt, self.opslimit, self.memlimit).expect("scrypt limits too low");
+                blob.extend(keys.iter().zip(stream.iter()).map(|(x, y)| x ^ y).collect::<Vec<u8>>());
+            },
+            None => {
+                blob.extend(Minisign::KDF_NONE);
+                blob.extend(Minisign::CHECKSUM_BLAKE2B);
+                blob.extend([0; 48]);
+                blob.extend(&keys);
+            },
+        }
+
+        let mut out = Secret::new();
+        out.extend(b"untrusted comment: minisign ");
+        if password.is_some() {
+            out.extend(b"encrypted ");
+        }
+        out.extend(b"secret key\n");
+        out.extend(pem::base64(&blob));
+        out.push(b'\n');
+
+        out
+    }
+
+    // None when the key is encrypted and the password is missing or wrong
+    pub fn from_secret_key(data: &[u8], password: Option<&str>) -> Option<Self> {
+        let blob = read_file(data)?;
+        if blob.len() != 158 || blob[..2] != Minisign::ED25519 || blob[4..6] != Minisign::CHECKSUM_BLAKE2B {
+            return None;
+        }
+        let salt = &blob[6..38];
+        let opslimit = u64::from_le_bytes(blob[38..46].try_into().unwrap());
+        let memlimit = u64::from_le_bytes(blob[46..54].try_into().unwrap());
+        let keys = match blob[2..4].try_into().unwrap() {
+            Minisign::KDF_NONE => Secret::from(blob[54..].to_vec()),
+            Minisign::KDF_SCRYPT => {
+                let stream = kdf(password?, salt, opslimit, memlimit)?;
+                Secret::from(blob[54..].iter().zip(stream.iter()).map(|(x, y)| x ^ y).collect::<Vec<u8>>())
+            },
+            _ => return None,
+        };
+
+        let key = Ed25519::from_private_key(keys[8..40].to_vec());
+        if key.gen_public_key() != keys[40..72] {
+            return None;
+        }
+        let out = Minisign{
+            key,
+            key_id: keys[..8].try_into().unwrap(),
+            opslimit,
+            memlimit,
+        };
+        if out.checksum()[..] != keys[72..] {
+            return None;
+        }
+
+        Some(out)
+    }
+
+    // untrusted comment line with the key ID, then base64 of "Ed" || key ID || public key
+    pub fn to_public_key(&self) -> Vec<u8> {
+        let mut blob = Minisign::ED25519.to_vec();
+        blob.extend(self.key_id);
+        blob.extend(self.key.gen_public_key());
+
+        let mut out = format!("untrusted comment: minisign public key {}\n", self.key_id()).into_bytes();
+        out.extend(pem::base64(&blob));
+        out.push(b'\n');
+        out
+    }
+
+    // minisign -S, the signature file (.minisig)
+    // untrusted comment: signature from minisign secret key
+    // base64("ED" || key ID || Ed25519(BLAKE2b-512(message)))
+    // trusted comment: <trusted comment>
+    // base64(Ed25519(signature || trusted comment))
+    pub fn sign(&self, message: &[u8], trusted_comment: &str) -> Vec<u8> {
+        assert!(!trusted_comment.contains(['\r', '\n']), "a trusted comment is one line");
+        let signature = self.key.sign(&Blake2b512::digest(message));
+        let mut blob = Minisign::ED25519_HASHED.to_vec();
+        blob.extend(self.key_id);
+        blob.extend(&signature);
+
+        let mut global = signature;
+        global.extend(trusted_comment.as_bytes());
+
+        let mut out = b"untrusted comment: signature from minisign secret key\n".to_vec();
+        out.extend(pem::base64(&blob));
+        out.extend(format!("\ntrusted comment: {}\n", trusted_comment).as_bytes());
+        out.extend(pem::base64(self.key.sign(&global)));
+        out.push(b'\n');
+        out
+    }
+
+    // minisign -V, the trusted comment when both signatures are good.
+    // The public key may be the file or its base64 line, as with minisign -P.
+    pub fn verify(public_key: &[u8], message: &[u8], signature: &[u8]) -> Option<String> {
+        let public_key = match read_file(public_key) {
+            Some(x) => x,
+            None => pem::base64_decode(std::str::from_utf8(public_key).ok()?.trim().as_bytes())?,
+        };
+        if public_key.len() != 42 || public_key[..2] != Minisign::ED25519 {
+            return None;
+        }
+        let (key_id, public_key) = public_key[2..].split_at(8);
+
+        let mut lines = std::str::from_utf8(signature).ok()?.lines();
+        lines.next()?.strip_prefix("untrusted comment: ")?;
+        let blob = pem::base64_decode(lines.next()?.as_bytes())?;
+        let trusted_comment = lines.next()?.strip_prefix("trusted comment: ")?;
+        let global = pem::base64_decode(lines.next()?.as_bytes())?;
+        if blob.len() != 74 || &blob[2..10] != key_id {
+            return None;
+        }
+        let signature = &blob[10..];
+
+        let good = match blob[..2].try_into().unwrap() {
+            Minisign::ED25519 => Ed25519::verify(public_key, message, signature),
+            Minisign::ED25519_HASHED => Ed25519::verify(public_key, &Blake2b512::digest(message), signature),
+            _ => false,
+        };
+        let mut signed = signature.to_vec();
+        signed.extend(trusted_comment.as_bytes());
+        if !good || !Ed25519::verify(public_key, &signed, &global) {
+            return None;
+        }
+
+        Some(trusted_comment.to_string())
+    }
+
+    fn checksum(&self) -> Vec<u8> {
+        let mut hash = Blake2b::<U32>::new();
+        hash.update(Minisign::ED25519);
+        hash.update(self.key_id);
+        hash.update(&*self.key.to_keypair());
+        hash.finalize().to_vec()
+    }
+}
+
+// crypto_pwhash_scryptsalsa208sha256, 104 bytes to xor the key ID, secret key and checksum with.
+// libsodium picks N, r and p from opslimit and memlimit; None when they make no valid parameters.
+fn kdf(password: &str, salt: &[u8], opslimit: u64, memlimit: u64) -> Option<Secret> {
+    let opslimit = opslimit.max(32768);
+    let r = 8u64;
+    let (log_n, p) = if opslimit < memlimit / 32 {
+        let max_n = opslimit / (r * 4);
+        (log2_below(max_n), 1)
+    } else {
+        let max_n = memlimit / (r * 128);
+        let log_n = log2_below(max_n);
+        let max_rp = ((opslimit / 4) >> log_n).min(0x3fffffff);
+        (log_n, max_rp / r)
+    };
+
+    if log_n >= 32 || p == 0 {
+        return None;
+    }
+
+    Some(scrypt::scrypt(password.as_bytes(), salt, log_n, r as usize, p as usize, 104))
+}
+
+// the smallest n from 1 on with 2^n > max / 2, at most 63
+fn log2_below(max: u64) -> u32 {
+    (1..63).find(|&n| 1u64 << n > max / 2).unwrap_or(63)
+}
+
+// base64 line after an untrusted comment line
+fn read_file(data: &[u8]) -> Option<Secret> {
+    let mut lines = std::str::from_utf8(data).ok()?.lines();
+    lines.next()?.strip_prefix("untrusted comment: ")?;
+    pem::base64_decode(lines.next()?.trim().as_bytes())
+}
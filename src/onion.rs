@@ -0,0 +1,89 @@
+use crate::sha3;
+use crate::ed25519::Ed25519;
+use crate::secret::Secret;
+
+// Tor v3 onion service keys, the files of a HiddenServiceDir:
+// hs_ed25519_secret_key, hs_ed25519_public_key and hostname
+pub struct OnionService {
+    expanded_key: Secret,
+    public_key: Vec<u8>,
+}
+
+impl OnionService {
+    // 32 byte headers of tor's crypto_write_tagged_contents_to_file, the tag padded with zeros
+    const SECRET_KEY_HEADER: &'static [u8; 32] = b"== ed25519v1-secret: type0 ==\0\0\0";
+    const PUBLIC_KEY_HEADER: &'static [u8; 32] = b"== ed25519v1-public: type0 ==\0\0\0";
+
+    pub fn new(key: &Ed25519) -> Self {
+        OnionService{
+            expanded_key: key.to_expanded(),
+            public_key: key.gen_public_key(),
+        }
+    }
+
+    // <56 base32 characters>.onion
+    pub fn address(&self) -> String {
+        format!("{}.onion", onion_address(&self.public_key))
+    }
+
+    // header || the expanded secret key, 96 bytes.
+    // tor keeps the clamped scalar and the nonce prefix, not the seed.
+    pub fn to_secret_key(&self) -> Secret {
+        let mut out = Secret::with_capacity(96);
+        out.extend(OnionService::SECRET_KEY_HEADER);
+        out.extend(&self.expanded_key);
+        out
+    }
+
+    // header || public key, 64 bytes
+    pub fn to_public_key(&self) -> Vec<u8> {
+        let mut out = OnionService::PUBLIC_KEY_HEADER.to_vec();
+        out.extend(&self.public_key);
+        out
+    }
+
+    // the address on a line
+    pub fn to_hostname(&self) -> Vec<u8> {
+        format!("{}\n", self.address()).into_bytes()
+    }
+}
+
+// rend-spec-v3 section 6, encoding onion addresses, without ".onion"
+// onion_address = base32(PUBKEY | CHECKSUM | VERSION)
+// CHECKSUM = SHA3-256(".onion checksum" | PUBKEY | VERSION)[:2], VERSION = 3
+pub fn onion_address(public_key: &[u8]) -> String {
+    const VERSION: u8 = 3;
+    assert_eq!(public_key.len(), 32, "an ed25519 public key is 32 bytes");
+
+    let mut data = b".onion checksum".to_vec();
+    data.extend(public_key);
+    data.push(VERSION);
+    let checksum = sha3::sha3_256(&data);
+
+    let mut out = public_key.to_vec();
+    out.extend(&checksum[..2]);
+    out.push(VERSION);
+    base32(&out)
+}
+
+// RFC 4648 section 6 in lowercase as tor writes it, without padding
+fn base32(data: &[u8]) -> String {
+    const ALPHABET: &[u8; 32] = b"abcdefghijklmnopqrstuvwxyz234567";
+
+    let mut out = String::with_capacity((data.len() * 8).div_ceil(5));
+    let mut acc = 0u32;
+    let mut bits = 0;
+    for &x in data {
+        acc = acc << 8 | x as u32;
+        bits += 8;
+        while bits >= 5 {
+            bits -= 5;
+            out.push(ALPHABET[(acc >> bits) as usize & 31] as char);
+        }
+        acc &= (1 << bits) - 1;
+    }
+    if bits > 0 {
+        out.push(ALPHABET[(acc << (5 - bits)) as usize & 31] as char);
+    }
+    out
+}
@@ -0,0 +1,364 @@
+use rand::Rng;
+use aes::Aes256;
+use aes::cipher::{Block, BlockEncrypt, KeyInit};
+use sha2::{Sha256, Digest};
+use crate::pem;
+use crate::hash::Hash;
+use crate::key::Key;
+use crate::rsa::RsaAlgorithm;
+use crate::secret::Secret;
+
+pub trait OpenPgpFormat {
+    // RFC 9580 section 9.1 public key algorithm
+    fn pgp_algorithm(&self) -> u8;
+    // algorithm-specific fields of the public key (RFC 9580 section 5.5.5)
+    fn gen_public_fields(&self) -> Vec<u8>;
+    // algorithm-specific fields of the secret key (RFC 9580 section 5.5.5), in the clear
+    fn gen_secret_fields(&self) -> Secret;
+}
+
+// RFC 9580 section 9.1
+pub const RSA: u8 = 1;
+pub const ECDH: u8 = 18;
+pub const EDDSA_LEGACY: u8 = 22;
+
+// RFC 9580 section 10.2, a primary key with one User ID and optionally an encryption subkey.
+// Version 4 keys with the EdDSALegacy and Curve25519Legacy encodings,
+// which GnuPG 2.2 and every other OpenPGP implementation in use reads.
+pub struct TransferableSecretKey {
+    primary: Key,
+    user_id: String,
+    created: u32,
+    expiration: Option<u32>,
+    subkey: Option<KeyMaterial>,
+}
+
+struct KeyMaterial {
+    algorithm: u8,
+    public_fields: Vec<u8>,
+    secret_fields: Secret,
+}
+
+impl TransferableSecretKey {
+    // RFC 9580 section 5.5.1, packet tags
+    const SIGNATURE: u8 = 2;
+    const SECRET_KEY: u8 = 5;
+    const SECRET_SUBKEY: u8 = 7;
+    const USER_ID: u8 = 13;
+
+    // RFC 9580 section 5.2.1, signature types
+    const POSITIVE_CERTIFICATION: u8 = 0x13;
+    const SUBKEY_BINDING: u8 = 0x18;
+
+    // RFC 9580 section 5.2.3.7, subpacket types
+    const CREATION_TIME: u8 = 2;
+    const KEY_EXPIRATION_TIME: u8 = 9;
+    const PREFERRED_SYMMETRIC: u8 = 11;
+    const ISSUER_KEY_ID: u8 = 16;
+    const PREFERRED_HASH: u8 = 21;
+    const KEY_FLAGS: u8 = 27;
+    const FEATURES: u8 = 30;
+    const ISSUER_FINGERPRINT: u8 = 33;
+
+    // RFC 9580 section 5.2.3.29, certify and sign for the primary key,
+    // encrypt communications and storage for the subkey
+    const PRIMARY_FLAGS: u8 = 0x03;
+    const SUBKEY_FLAGS: u8 = 0x0c;
+
+    // RFC 9580 section 9.3 AES-256, AES-192, AES-128 and section 9.5 SHA2-512, SHA2-384, SHA2-256
+    const SYMMETRIC_ALGORITHMS: [u8; 3] = [9, 8, 7];
+    const HASH_ALGORITHMS: [u8; 3] = [10, 9, 8];
+    const AES256: u8 = 9;
+    const SHA256: u8 = 8;
+
+    // RFC 9580 section 3.7.1.3, the coded count of 65011712 bytes GnuPG uses at most
+    const S2K_COUNT: u8 = 0xff;
+
+    // created is the key creation time in seconds since the epoch, also that of the self-signatures.
+    // None for an rsa key restricted to PSS, which OpenPGP has no algorithm for,
+    // and for a multi-prime rsa key, which it has no fields for.
+    pub fn new(primary: Key, user_id: &str, created: u64) -> Option<Self> {
+        if matches!(&primary, Key::Rsa(key) if key.algorithm() != RsaAlgorithm::Rsa || key.primes() > 2) {
+            return None;
+        }
+
+        Some(TransferableSecretKey{
+            primary,
+            user_id: user_id.to_string(),
+            created: created.try_into().expect("OpenPGP times end in 2106"),
+            expiration: None,
+            subkey: None,
+        })
+    }
+
+    // RFC 9580 section 5.2.3.13, the keys expire this many seconds after their creation
+    pub fn with_expiration(mut self, seconds: u64) -> Self {
+        assert!(seconds > 0, "a key expiration time of 0 means no expiration");
+        self.expiration = Some(seconds.try_into().expect("OpenPGP times end in 2106"));
+        self
+    }
+
+    // an rsa or x25519 key for encryption
+    pub fn with_subkey(mut self, subkey: &dyn OpenPgpFormat) -> Self {
+        let algorithm = subkey.pgp_algorithm();
+        assert!(algorithm == RSA || algorithm == ECDH, "the subkey must be an encryption key");
+        self.subkey = Some(KeyMaterial{
+            algorithm,
+            public_fields: subkey.gen_public_fields(),
+            secret_fields: subkey.gen_secret_fields(),
+        });
+        self
+    }
+
+    // RFC 9580 section 5.5.4.2, SHA-1 over 0x99, the two-octet length and the public key packet body
+    pub fn fingerprint(&self) -> Vec<u8> {
+        Hash::Sha1.digest(&hashed_key(&self.primary_public_key()))
+    }
+
+    // Secret-Key, User ID, positive certification, and Secret-Subkey with its binding signature.
+    // With a passphrase the secret fields are encrypted with AES-256 in CFB mode (S2K usage 254)
+    // under an iterated and salted SHA-256 S2K, rng makes the salts and IVs.
+    pub fn to_pgp<R: Rng + ?Sized>(&self, passphrase: Option<&str>, rng: &mut R) -> Vec<u8> {
+        let primary = self.primary_public_key();
+        let fingerprint = self.fingerprint();
+
+        let mut out = Vec::new();
+        out.extend(packet(TransferableSecretKey::SECRET_KEY, &secret_key(&primary, &self.primary.gen_secret_fields(), passphrase, rng)));
+        out.extend(packet(TransferableSecretKey::USER_ID, self.user_id.as_bytes()));
+
+        let mut data = hashed_key(&primary);
+        data.push(0xb4);
+        data.extend((self.user_id.len() as u32).to_be_bytes());
+        data.extend(self.user_id.as_bytes());
+        let mut subpackets = self.gen_subpackets(&fingerprint, TransferableSecretKey::PRIMARY_FLAGS);
+        subpackets.extend(subpacket(TransferableSecretKey::PREFERRED_SYMMETRIC, &TransferableSecretKey::SYMMETRIC_ALGORITHMS));
+        subpackets.extend(subpacket(TransferableSecretKey::PREFERRED_HASH, &TransferableSecretKey::HASH_ALGORITHMS));
+        // version 1 Symmetrically Encrypted and Integrity Protected Data
+        subpackets.extend(subpacket(TransferableSecretKey::FEATURES, &[0x01]));
+        out.extend(packet(TransferableSecretKey::SIGNATURE, &self.sign(TransferableSecretKey::POSITIVE_CERTIFICATION, data, &subpackets, &fingerprint)));
+
+        if let Some(subkey) = &self.subkey {
+            let public_key = public_key(self.created, subkey.algorithm, &subkey.public_fields);
+            out.extend(packet(TransferableSecretKey::SECRET_SUBKEY, &secret_key(&public_key, &subkey.secret_fields, passphrase, rng)));
+
+            let mut data = hashed_key(&primary);
+            data.extend(hashed_key(&public_key));
+            let subpackets = self.gen_subpackets(&fingerprint, TransferableSecretKey::SUBKEY_FLAGS);
+            out.extend(packet(TransferableSecretKey::SIGNATURE, &self.sign(TransferableSecretKey::SUBKEY_BINDING, data, &subpackets, &fingerprint)));
+        }
+
+        out
+    }
+
+    fn primary_public_key(&self) -> Vec<u8> {
+        public_key(self.created, self.primary.pgp_algorithm(), &self.primary.gen_public_fields())
+    }
+
+    // issuer fingerprint, signature creation time, key flags and key expiration time
+    fn gen_subpackets(&self, fingerprint: &[u8], flags: u8) -> Vec<u8> {
+        let mut issuer = vec![4];
+        issuer.extend(fingerprint);
+
+        let mut out = Vec::new();
+        out.extend(subpacket(TransferableSecretKey::ISSUER_FINGERPRINT, &issuer));
+        out.extend(subpacket(TransferableSecretKey::CREATION_TIME, &self.created.to_be_bytes()));
+        out.extend(subpacket(TransferableSecretKey::KEY_FLAGS, &[flags]));
+        if let Some(expiration) = self.expiration {
+            out.extend(subpacket(TransferableSecretKey::KEY_EXPIRATION_TIME, &expiration.to_be_bytes()));
+        }
+
+        out
+    }
+
+    // RFC 9580 section 5.2.3, a version 4 signature with SHA2-256 over
+    // data || version, type, algorithms, hashed subpackets || 0x04, 0xff, length of the hashed part.
+    // EdDSALegacy signs the digest, as MPI R and MPI S (section 5.2.3.3).
+    fn sign(&self, signature_type: u8, mut data: Vec<u8>, subpackets: &[u8], fingerprint: &[u8]) -> Vec<u8> {
+        let mut hashed = vec![4, signature_type, self.primary.pgp_algorithm(), TransferableSecretKey::SHA256];
+        hashed.extend((subpackets.len() as u16).to_be_bytes());
+        hashed.extend(subpackets);
+        data.extend(&hashed);
+        data.extend([4, 0xff]);
+        data.extend((hashed.len() as u32).to_be_bytes());
+        let digest = Hash::Sha256.digest(&data);
+
+        let signature = match &self.primary {
+            Key::Rsa(key) => mpi(&key.sign_pkcs1_v1_5(Hash::Sha256, &data)),
+            Key::Ed25519(key) => {
+                let signature = key.sign(&digest);
+                let mut out = mpi(&signature[..32]);
+                out.extend(mpi(&signature[32..]));
+                out
+            },
+        };
+        let unhashed = subpacket(TransferableSecretKey::ISSUER_KEY_ID, &fingerprint[12..]);
+
+        let mut out = hashed;
+        out.extend((unhashed.len() as u16).to_be_bytes());
+        out.extend(unhashed);
+        out.extend(&digest[..2]);
+        out.extend(signature);
+
+        out
+    }
+}
+
+// RFC 9580 section 5.5.2, version 4: version, four-octet creation time, algorithm, fields
+fn public_key(created: u32, algorithm: u8, fields: &[u8]) -> Vec<u8> {
+    let mut out = vec![4];
+    out.extend(created.to_be_bytes());
+    out.push(algorithm);
+    out.extend(fields);
+
+    out
+}
+
+// 0x99, two-octet length, public key packet body
+fn hashed_key(public_key: &[u8]) -> Vec<u8> {
+    let mut out = vec![0x99];
+    out.extend((public_key.len() as u16).to_be_bytes());
+    out.extend(public_key);
+
+    out
+}
+
+// RFC 9580 section 5.5.3, the public key followed by
+// S2K usage 0, the fields and their two-octet checksum, or
+// S2K usage 254, AES-256, the S2K specifier, the IV and the fields with their SHA-1 hash, encrypted
+fn secret_key<R: Rng + ?Sized>(public_key: &[u8], fields: &[u8], passphrase: Option<&str>, rng: &mut R) -> Secret {
+    let mut out = Secret::from(public_key.to_vec());
+    match passphrase {
+        None => {
+            out.push(0);
+            out.extend(fields);
+            let checksum = fields.iter().fold(0u16, |acc, x| acc.wrapping_add(*x as u16));
+            out.extend(checksum.to_be_bytes());
+        },
+        Some(passphrase) => {
+            let mut salt = [0u8; 8];
+            rng.fill(&mut salt);
+            let mut iv = [0u8; 16];
+            rng.fill(&mut iv);
+            let key = s2k(passphrase.as_bytes(), &salt, TransferableSecretKey::S2K_COUNT);
+            let mut data = Secret::from(fields.to_vec());
+            data.extend(Hash::Sha1.digest(fields));
+            let mut data = data.to_vec();
+            cfb_encrypt(&key, &iv, &mut data);
+
+            out.extend([254, TransferableSecretKey::AES256, 3, TransferableSecretKey::SHA256]);
+            out.extend(salt);
+            out.push(TransferableSecretKey::S2K_COUNT);
+            out.extend(iv);
+            out.extend(data);
+        },
+    }
+
+    out
+}
+
+// RFC 9580 section 3.7.1.3, SHA2-256 over salt || passphrase repeated
+// until count octets have been hashed, and at least once
+fn s2k(passphrase: &[u8], salt: &[u8], coded_count: u8) -> Secret {
+    let count = (16usize + (coded_count & 15) as usize) << ((coded_count >> 4) + 6);
+    let mut data = Secret::from(salt.to_vec());
+    data.extend(passphrase);
+    let count = count.max(data.len());
+
+    let mut hasher = Sha256::new();
+    for _ in 0..count / data.len() {
+        hasher.update(&data);
+    }
+    hasher.update(&data[..count % data.len()]);
+
+    Secret::from(hasher.finalize().to_vec())
+}
+
+// NIST SP 800-38A section 6.3 with 128 bit segments, the last block may be short
+fn cfb_encrypt(key: &[u8], iv: &[u8], data: &mut [u8]) {
+    let cipher = Aes256::new_from_slice(key).unwrap();
+    let mut register = Block::<Aes256>::clone_from_slice(iv);
+    for chunk in data.chunks_mut(16) {
+        cipher.encrypt_block(&mut register);
+        for (x, y) in chunk.iter_mut().zip(register.iter()) {
+            *x ^= y;
+        }
+        register[..chunk.len()].copy_from_slice(chunk);
+    }
+}
+
+// RFC 9580 section 4.2.1, OpenPGP packet format with a one, two or five octet length
+fn packet(tag: u8, body: &[u8]) -> Vec<u8> {
+    let mut out = vec![0xc0 | tag];
+    match body.len() {
+        len @ 0..=191 => out.push(len as u8),
+        len @ 192..=8383 => {
+            out.push((((len - 192) >> 8) + 192) as u8);
+            out.push((len - 192) as u8);
+        },
+        len => {
+            out.push(0xff);
+            out.extend((len as u32).to_be_bytes());
+        },
+    }
+    out.extend(body);
+
+    out
+}
+
+// RFC 9580 section 5.2.3.7, one-octet length of type and data, type, data
+fn subpacket(kind: u8, data: &[u8]) -> Vec<u8> {
+    let mut out = vec![data.len() as u8 + 1, kind];
+    out.extend(data);
+
+    out
+}
+
+// RFC 9580 section 3.2, two-octet length in bits then the big-endian value without leading zeros
+pub fn mpi(data: &[u8]) -> Vec<u8> {
+    mpi_secret(data).to_vec()
+}
+
+pub fn mpi_secret(data: &[u8]) -> Secret {
+    let start = data.iter().position(|x| *x != 0).unwrap_or(data.len());
+    let value = &data[start..];
+    let bits = value.first().map_or(0, |x| (value.len() - 1) * 8 + (8 - x.leading_zeros() as usize));
+
+    let mut out = Secret::with_capacity(2 + value.len());
+    out.extend((bits as u16).to_be_bytes());
+    out.extend(value);
+
+    out
+}
+
+// RFC 9580 section 6.2, 64 characters a line and the CRC-24 checksum of section 6.1,
+// which RFC 9580 only keeps for implementations that require it, like those of RFC 4880.
+// -----BEGIN label-----
+// (empty line)
+// base64
+// =checksum
+// -----END label-----
+pub fn armor(label: &str, data: &[u8]) -> Vec<u8> {
+    let mut crc: u32 = 0xb704ce;
+    for x in data.iter() {
+        crc ^= (*x as u32) << 16;
+        for _ in 0..8 {
+            crc <<= 1;
+            if crc & 0x1000000 != 0 {
+                crc ^= 0x1864cfb;
+            }
+        }
+    }
+    let base64 = Secret::from(pem::base64(data));
+
+    let mut out = Vec::with_capacity(base64.len() + base64.len() / 64 + 2 * label.len() + 48);
+    out.extend(format!("-----BEGIN {}-----\n\n", label).as_bytes());
+    for line in base64.chunks(64) {
+        out.extend(line);
+        out.push(b'\n');
+    }
+    out.push(b'=');
+    out.extend(pem::base64(&crc.to_be_bytes()[1..]));
+    out.extend(format!("\n-----END {}-----\n", label).as_bytes());
+
+    out
+}
@@ -1,3 +1,6 @@
+use zeroize::Zeroize;
+use crate::secret::Secret;
+
 // RFC 5958 section 5
 pub trait PemEncode {
     fn to_pem(&self) -> Vec<u8>;
@@ -5,9 +8,10 @@ pub trait PemEncode {
 
 // RFC 4648
 // no line feed
-pub fn base64(data: Vec<u8>) -> Vec<u8> {
+pub fn base64<T: AsRef<[u8]>>(data: T) -> Vec<u8> {
+    let data = data.as_ref();
     let base64_table = b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789+/";
-    let mut out = Vec::new();
+    let mut out = Vec::with_capacity(data.len().div_ceil(3) * 4);
     // 8 8 8 bit -> 6 6 6 6 bit
     for i in 0..(data.len()/3) {
         out.push(base64_table[(data[i*3]>>2) as usize]);
@@ -20,15 +24,125 @@ pub fn base64(data: Vec<u8>) -> Vec<u8> {
         out.push(base64_table[(data[data.len()-2]>>2) as usize]);
         out.push(base64_table[(((data[data.len()-2]&0b11)<<4)|(data[data.len()-1]>>4)) as usize]);
         out.push(base64_table[((data[data.len()-1]&0b1111)<<2) as usize]);
-        out.push('=' as u8);
+        out.push(b'=');
     }
     // 8 bit -> 6 2 bit + 0b0000 + '=' + '='
     if data.len() % 3 == 1 {
         out.push(base64_table[(data[data.len()-1]>>2) as usize]);
         out.push(base64_table[((data[data.len()-1]&0b11)<<4) as usize]);
-        out.push('=' as u8);
-        out.push('=' as u8);
+        out.push(b'=');
+        out.push(b'=');
+    }
+
+    out
+}
+
+// RFC 4648
+// line feeds and other whitespace are skipped
+pub fn base64_decode(data: &[u8]) -> Option<Secret> {
+    let mut out = Secret::with_capacity(data.len() / 4 * 3);
+    let mut acc: u32 = 0;
+    let mut bits = 0;
+    let mut padding = 0;
+    for c in data.iter() {
+        let v = match c {
+            b'A'..=b'Z' => c - b'A',
+            b'a'..=b'z' => c - b'a' + 26,
+            b'0'..=b'9' => c - b'0' + 52,
+            b'+' => 62,
+            b'/' => 63,
+            b'=' => {
+                padding += 1;
+                continue;
+            },
+            b' ' | b'\t' | b'\r' | b'\n' => continue,
+            _ => return None,
+        };
+        if padding > 0 {
+            return None;
+        }
+        acc = (acc << 6) | v as u32;
+        bits += 6;
+        if bits >= 8 {
+            bits -= 8;
+            out.push((acc >> bits) as u8);
+        }
+    }
+    acc.zeroize();
+
+    Some(out)
+}
+
+// RFC 4648 section 5, '-' and '_' for '+' and '/', without padding (RFC 7515 section 2)
+pub fn base64url<T: AsRef<[u8]>>(data: T) -> Vec<u8> {
+    let mut out = base64(data);
+    while out.last() == Some(&b'=') {
+        out.pop();
+    }
+    for c in out.iter_mut() {
+        match c {
+            b'+' => *c = b'-',
+            b'/' => *c = b'_',
+            _ => {},
+        }
     }
 
     out
 }
+
+// inverse of base64url, padding and whitespace are not allowed
+pub fn base64url_decode(data: &[u8]) -> Option<Secret> {
+    if data.len() % 4 == 1 {
+        return None;
+    }
+    let mut standard = Secret::with_capacity(data.len());
+    for c in data.iter() {
+        standard.push(match c {
+            b'-' => b'+',
+            b'_' => b'/',
+            b'A'..=b'Z' | b'a'..=b'z' | b'0'..=b'9' => *c,
+            _ => return None,
+        });
+    }
+
+    base64_decode(&standard)
+}
+
+// -----BEGIN label-----
+// base64 folded every width characters
+// -----END label-----
+pub fn encapsulate(label: &[u8], data: &[u8], width: usize) -> Vec<u8> {
+    let base64 = Secret::from(base64(data));
+
+    let mut out = Vec::with_capacity(base64.len() + base64.len() / width + 2 * label.len() + 32);
+    out.extend(b"-----BEGIN ");
+    out.extend(label);
+    out.extend(b"-----\n");
+    for (i, line) in base64.chunks(width).enumerate() {
+        if i > 0 {
+            out.extend(b"\n");
+        }
+        out.extend(line);
+    }
+    out.extend(b"\n-----END ");
+    out.extend(label);
+    out.extend(b"-----\n");
+
+    out
+}
+
+// inverse of encapsulate, returns the label and the decoded body
+pub fn decapsulate(data: &[u8]) -> Option<(Vec<u8>, Secret)> {
+    let begin = b"-----BEGIN ";
+    let start = data.windows(begin.len()).position(|w| w == begin)? + begin.len();
+    let label_len = data[start..].windows(5).position(|w| w == b"-----")?;
+    let label = data[start..start + label_len].to_vec();
+    let body_start = start + label_len + 5;
+
+    let mut end = b"-----END ".to_vec();
+    end.extend(&label);
+    let body_len = data[body_start..].windows(end.len()).position(|w| w == &end[..])?;
+    let body = base64_decode(&data[body_start..body_start + body_len])?;
+
+    Some((label, body))
+}
@@ -0,0 +1,285 @@
+use num_bigint::BigUint;
+use crate::der;
+use crate::der::Tag;
+use crate::hash::Hash;
+use crate::secret::Secret;
+
+// RFC 8017 section 4.1 I2OSP, None when x needs more than len bytes
+pub fn i2osp(x: &BigUint, len: usize) -> Option<Vec<u8>> {
+    let bytes = x.to_bytes_be();
+    if bytes.len() > len {
+        return None;
+    }
+    let mut out = vec![0u8; len - bytes.len()];
+    out.extend(bytes);
+
+    Some(out)
+}
+
+// same as i2osp, for secret values
+pub fn i2osp_secret(x: &BigUint, len: usize) -> Secret {
+    let bytes = Secret::from(x.to_bytes_be());
+    assert!(bytes.len() <= len, "integer too large");
+    let mut out = Secret::with_capacity(len);
+    for _ in bytes.len()..len {
+        out.push(0);
+    }
+    out.extend(&bytes);
+
+    out
+}
+
+// RFC 8017 Appendix B.2.1
+pub fn mgf1(hash: Hash, seed: &[u8], len: usize) -> Vec<u8> {
+    let mut out = Vec::with_capacity(len + hash.output_len());
+    let mut counter: u32 = 0;
+    while out.len() < len {
+        let mut data = seed.to_vec();
+        data.extend(counter.to_be_bytes());
+        out.extend(hash.digest(&data));
+        counter += 1;
+    }
+    out.truncate(len);
+
+    out
+}
+
+// RFC 8017 section 9.2
+// DigestInfo ::= SEQUENCE {
+//   digestAlgorithm DigestAlgorithm,
+//   digest OCTET STRING
+// }
+// EM = 0x00 || 0x01 || PS || 0x00 || T, PS is at least 8 bytes of 0xff
+pub fn emsa_pkcs1_v1_5_encode(hash: Hash, message: &[u8], em_len: usize) -> Option<Vec<u8>> {
+    let mut t = hash.gen_algorithm_identifier();
+    t.extend(der::encode(Tag::OctetString, hash.digest(message)));
+    let t = der::encode(Tag::Sequence, t);
+    if em_len < t.len() + 11 {
+        return None;
+    }
+
+    let mut out = Vec::with_capacity(em_len);
+    out.push(0x00);
+    out.push(0x01);
+    out.resize(em_len - t.len() - 1, 0xff);
+    out.push(0x00);
+    out.extend(t);
+
+    Some(out)
+}
+
+// RFC 8017 section 9.1.1
+// M' = (0x)00 00 00 00 00 00 00 00 || mHash || salt, H = Hash(M')
+// DB = PS || 0x01 || salt
+// EM = (DB xor MGF(H)) || H || 0xbc
+pub fn emsa_pss_encode(hash: Hash, mgf1_hash: Hash, message: &[u8], salt: &[u8], em_bits: usize) -> Option<Vec<u8>> {
+    let h_len = hash.output_len();
+    let em_len = em_bits.div_ceil(8);
+    if em_len < h_len + salt.len() + 2 {
+        return None;
+    }
+
+    let h = pss_hash(hash, message, salt);
+    let mut db = vec![0u8; em_len - salt.len() - h_len - 2];
+    db.push(0x01);
+    db.extend(salt);
+    let mask = mgf1(mgf1_hash, &h, em_len - h_len - 1);
+    for (x, m) in db.iter_mut().zip(mask.iter()) {
+        *x ^= m;
+    }
+    db[0] &= 0xff >> (8 * em_len - em_bits);
+
+    let mut out = db;
+    out.extend(h);
+    out.push(0xbc);
+
+    Some(out)
+}
+
+// RFC 8017 section 9.1.2, em is emLen bytes long
+pub fn emsa_pss_verify(hash: Hash, mgf1_hash: Hash, message: &[u8], em: &[u8], em_bits: usize, salt_length: usize) -> bool {
+    let h_len = hash.output_len();
+    let em_len = em_bits.div_ceil(8);
+    if em.len() != em_len || em_len < h_len + salt_length + 2 || em[em_len - 1] != 0xbc {
+        return false;
+    }
+
+    let (masked_db, rest) = em.split_at(em_len - h_len - 1);
+    let h = &rest[..h_len];
+    let top = 0xffu8 >> (8 * em_len - em_bits);
+    if masked_db[0] & !top != 0 {
+        return false;
+    }
+
+    let mask = mgf1(mgf1_hash, h, masked_db.len());
+    let mut db: Vec<u8> = masked_db.iter().zip(mask.iter()).map(|(x, m)| x ^ m).collect();
+    db[0] &= top;
+
+    // PS must be zeros followed by 0x01
+    let ps_len = em_len - h_len - salt_length - 2;
+    if db[..ps_len].iter().any(|x| *x != 0) || db[ps_len] != 0x01 {
+        return false;
+    }
+    let salt = &db[ps_len + 1..];
+
+    pss_hash(hash, message, salt) == h
+}
+
+fn pss_hash(hash: Hash, message: &[u8], salt: &[u8]) -> Vec<u8> {
+    let mut m = vec![0u8; 8];
+    m.extend(hash.digest(message));
+    m.extend(salt);
+
+    hash.digest(&m)
+}
+
+// 1 when a == b, 0 otherwise, without branching on the values
+fn ct_eq(a: u8, b: u8) -> u32 {
+    ((a ^ b) as u32).wrapping_sub(1) >> 31
+}
+
+// x when choice is 1, y when it is 0
+fn ct_select(choice: u32, x: u32, y: u32) -> u32 {
+    let mask = 0u32.wrapping_sub(choice);
+    (x & mask) | (y & !mask)
+}
+
+// RFC 8017 section 7.1.1 step 2
+// DB = lHash || PS || 0x01 || M
+// EM = 0x00 || (seed xor MGF(maskedDB)) || (DB xor MGF(seed))
+pub fn eme_oaep_encode(hash: Hash, label: &[u8], message: &[u8], seed: &[u8], k: usize) -> Option<Vec<u8>> {
+    let h_len = hash.output_len();
+    if k < 2 * h_len + 2 || message.len() > k - 2 * h_len - 2 {
+        return None;
+    }
+
+    let mut db = hash.digest(label);
+    db.resize(k - message.len() - h_len - 2, 0);
+    db.push(0x01);
+    db.extend(message);
+    let db_mask = mgf1(hash, seed, k - h_len - 1);
+    for (x, m) in db.iter_mut().zip(db_mask.iter()) {
+        *x ^= m;
+    }
+    let seed_mask = mgf1(hash, &db, h_len);
+
+    let mut out = Vec::with_capacity(k);
+    out.push(0x00);
+    out.extend(seed.iter().zip(seed_mask.iter()).map(|(x, m)| x ^ m));
+    out.extend(db);
+
+    Some(out)
+}
+
+// RFC 8017 section 7.1.2 step 3
+// Every check runs on every input and they all end in the same None,
+// so a failure does not tell which part of EM was wrong (Manger's attack).
+pub fn eme_oaep_decode(hash: Hash, label: &[u8], em: &[u8]) -> Option<Secret> {
+    let h_len = hash.output_len();
+    if em.len() < 2 * h_len + 2 {
+        return None;
+    }
+
+    let (masked_seed, masked_db) = em[1..].split_at(h_len);
+    let seed_mask = mgf1(hash, masked_db, h_len);
+    let mut seed = Secret::with_capacity(h_len);
+    for (x, m) in masked_seed.iter().zip(seed_mask.iter()) {
+        seed.push(x ^ m);
+    }
+    let db_mask = mgf1(hash, &seed, masked_db.len());
+    let mut db = Secret::with_capacity(masked_db.len());
+    for (x, m) in masked_db.iter().zip(db_mask.iter()) {
+        db.push(x ^ m);
+    }
+
+    let l_hash = hash.digest(label);
+    let mut bad = 1 ^ ct_eq(em[0], 0);
+    for (x, l) in db.iter().zip(l_hash.iter()) {
+        bad |= 1 ^ ct_eq(*x, *l);
+    }
+    // PS is zeros up to the first 0x01
+    let mut looking = 1;
+    let mut index = 0;
+    for (i, x) in db.iter().enumerate().skip(h_len) {
+        let is_one = ct_eq(*x, 1);
+        let is_zero = ct_eq(*x, 0);
+        index = ct_select(looking & is_one, i as u32, index);
+        bad |= looking & (1 ^ is_one) & (1 ^ is_zero);
+        looking &= 1 ^ is_one;
+    }
+    bad |= looking;
+
+    if bad != 0 {
+        return None;
+    }
+    let mut out = Secret::with_capacity(db.len() - index as usize - 1);
+    out.extend(&db[index as usize + 1..]);
+
+    Some(out)
+}
+
+// RFC 8017 section 7.2.2 step 3 with implicit rejection
+// (draft-irtf-cfrg-rsa-guidance, as in OpenSSL 3.2 and later).
+// EM = 0x00 || 0x02 || PS || 0x00 || M with PS at least 8 bytes.
+// When the padding is wrong, a message derived from kdk and as long as
+// a pseudo-random length is returned instead of an error. The choice
+// between the two is made without branching.
+pub fn eme_pkcs1_v1_5_decode(em: &[u8], kdk: &[u8]) -> Secret {
+    let k = em.len();
+    let max_sep_offset = (k - 2 - 8) as u32;
+
+    // 128 candidate lengths, the last one below max_sep_offset is used
+    let candidate_lengths = implicit_rejection_prf(kdk, b"length", 256);
+    let synthetic = implicit_rejection_prf(kdk, b"message", k);
+    let mut mask = max_sep_offset;
+    mask |= mask >> 1;
+    mask |= mask >> 2;
+    mask |= mask >> 4;
+    mask |= mask >> 8;
+    let mut synthetic_length = 0;
+    for candidate in candidate_lengths.chunks(2) {
+        let length = (((candidate[0] as u32) << 8) | candidate[1] as u32) & mask;
+        let below = length.wrapping_sub(max_sep_offset) >> 31;
+        synthetic_length = ct_select(below, length, synthetic_length);
+    }
+
+    let mut good = ct_eq(em[0], 0) & ct_eq(em[1], 2);
+    let mut looking = 1;
+    let mut zero_index = 0;
+    for (i, x) in em.iter().enumerate().skip(2) {
+        let is_zero = ct_eq(*x, 0);
+        zero_index = ct_select(looking & is_zero, i as u32, zero_index);
+        looking &= 1 ^ is_zero;
+    }
+    good &= 1 ^ looking;
+    // PS is at least 8 bytes, so the separator is at index 10 or later
+    good &= 1 ^ (zero_index.wrapping_sub(10) >> 31);
+
+    let index = ct_select(good, zero_index + 1, k as u32 - synthetic_length) as usize;
+    let mask = 0u8.wrapping_sub(good as u8);
+    let mut out = Secret::with_capacity(k - index);
+    for (x, y) in em.iter().zip(synthetic.iter()).skip(index) {
+        out.push((x & mask) | (y & !mask));
+    }
+
+    out
+}
+
+// PRF(key, label, length): HMAC-SHA256(key, I || label || bitLength) for I = 0, 1, ...
+// with I and bitLength as 16-bit big-endian integers, truncated to length bytes
+fn implicit_rejection_prf(key: &[u8], label: &[u8], length: usize) -> Secret {
+    let mut out = Secret::with_capacity(length + 32);
+    let mut i: u16 = 0;
+    while out.len() < length {
+        let mut data = Vec::with_capacity(label.len() + 4);
+        data.extend(i.to_be_bytes());
+        data.extend(label);
+        data.extend(((length * 8) as u16).to_be_bytes());
+        out.extend(Hash::Sha256.hmac(key, &data));
+        i += 1;
+    }
+
+    let mut truncated = Secret::with_capacity(length);
+    truncated.extend(&out[..length]);
+    truncated
+}
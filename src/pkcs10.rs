@@ -0,0 +1,105 @@
+use crate::der;
+use crate::der::{Tag, DerEncode};
+use crate::pem;
+use crate::pem::PemEncode;
+use crate::secret::Secret;
+use crate::spki::Spki;
+use crate::key::Key;
+use crate::x509;
+use crate::x509::{Name, GeneralName};
+
+// RFC 2986 section 4.1, a request not yet signed, sign makes the CertificationRequest
+pub struct CertificationRequestInfo {
+    subject: Name,
+    public_key: Vec<u8>,
+    alt_names: Vec<GeneralName>,
+}
+
+impl CertificationRequestInfo {
+    // pkcs-9-at-extensionRequest OBJECT IDENTIFIER ::= { pkcs-9 14 } (RFC 2985 section 5.4.2)
+    const EXTENSION_REQUEST: [u8; 9] = [0x2a,0x86,0x48,0x86,0xf7,0x0d,0x01,0x09,0x0e];
+
+    pub fn new(subject: Name, public_key: &Spki) -> Self {
+        CertificationRequestInfo{
+            subject,
+            public_key: public_key.to_der().to_vec(),
+            alt_names: Vec::new(),
+        }
+    }
+
+    // asked for in an extensionRequest attribute
+    pub fn with_subject_alt_names(mut self, alt_names: Vec<GeneralName>) -> Self {
+        self.alt_names = alt_names;
+        self
+    }
+
+    // signs with the key of the subject, which proves the requester holds it
+    pub fn sign(&self, key: &Key) -> CertificationRequest {
+        let info = self.to_der();
+        let signature_algorithm = x509::signature_algorithm(key);
+        let signature = x509::sign(key, &info);
+
+        CertificationRequest{
+            info,
+            signature_algorithm,
+            signature,
+        }
+    }
+
+    // CertificationRequestInfo ::= SEQUENCE {
+    //   version INTEGER { v1(0) },
+    //   subject Name,
+    //   subjectPKInfo SubjectPublicKeyInfo,
+    //   attributes [0] IMPLICIT SET OF Attribute
+    // }
+    // Attribute ::= SEQUENCE { type OBJECT IDENTIFIER, values SET SIZE(1..MAX) OF ANY }
+    fn to_der(&self) -> Vec<u8> {
+        assert!(!self.subject.is_empty() || !self.alt_names.is_empty(), "a request with an empty subject needs subject alternative names");
+
+        let mut attributes = Vec::new();
+        if !self.alt_names.is_empty() {
+            // Extensions ::= SEQUENCE SIZE (1..MAX) OF Extension
+            let extensions = der::encode(Tag::Sequence, x509::subject_alt_name(&self.alt_names, self.subject.is_empty()));
+            let mut attribute = der::encode(Tag::ObjectIdentifier, CertificationRequestInfo::EXTENSION_REQUEST);
+            attribute.extend(der::encode(Tag::Set, extensions));
+            attributes.extend(der::encode(Tag::Sequence, attribute));
+        }
+
+        let mut value = Vec::new();
+        value.extend(der::encode(Tag::Integer, [0]));
+        value.extend(self.subject.to_der());
+        value.extend(&self.public_key);
+        value.extend(der::encode(Tag::Context(0), attributes));
+
+        der::encode(Tag::Sequence, value)
+    }
+}
+
+// RFC 2986 section 4.2
+// CertificationRequest ::= SEQUENCE {
+//   certificationRequestInfo CertificationRequestInfo,
+//   signatureAlgorithm AlgorithmIdentifier,
+//   signature BIT STRING
+// }
+pub struct CertificationRequest {
+    info: Vec<u8>,
+    signature_algorithm: Vec<u8>,
+    signature: Vec<u8>,
+}
+
+impl DerEncode for CertificationRequest {
+    fn to_der(&self) -> Secret {
+        let mut value = Secret::new();
+        value.extend(&self.info);
+        value.extend(&self.signature_algorithm);
+        value.extend(der::encode(Tag::BitString, der::to_bit_string(&self.signature)));
+
+        der::encode_secret(Tag::Sequence, &value)
+    }
+}
+
+impl PemEncode for CertificationRequest {
+    fn to_pem(&self) -> Vec<u8> {
+        pem::encapsulate(b"CERTIFICATE REQUEST", &self.to_der(), 64)
+    }
+}
@@ -0,0 +1,266 @@
+use rand::Rng;
+use zeroize::Zeroize;
+use crate::der;
+use crate::der::{Tag, DerEncode};
+use crate::hash::Hash;
+use crate::cipher::Cipher;
+use crate::pkcs5;
+use crate::pkcs8::Pkcs8;
+use crate::spki::Spki;
+use crate::secret::Secret;
+use crate::x509::Certificate;
+
+// RFC 7292, a private key and optionally its certificate
+// in a password-protected PFX (.p12 / .pfx)
+pub struct Pkcs12 {
+    private_key: Secret,
+    local_key_id: Vec<u8>,
+    certificate: Option<Vec<u8>>,
+    friendly_name: Option<String>,
+    legacy: bool,
+}
+
+impl Pkcs12 {
+    // pkcs-12 OBJECT IDENTIFIER ::= { 1 2 840 113549 1 12 }
+    const PBE_WITH_SHA_AND_3_KEY_TRIPLE_DES_CBC: [u8; 10] = [0x2a,0x86,0x48,0x86,0xf7,0x0d,0x01,0x0c,0x01,0x03];
+    const PKCS8_SHROUDED_KEY_BAG: [u8; 11] = [0x2a,0x86,0x48,0x86,0xf7,0x0d,0x01,0x0c,0x0a,0x01,0x02];
+    const CERT_BAG: [u8; 11] = [0x2a,0x86,0x48,0x86,0xf7,0x0d,0x01,0x0c,0x0a,0x01,0x03];
+    // pkcs-7 OBJECT IDENTIFIER ::= { 1 2 840 113549 1 7 }
+    const DATA: [u8; 9] = [0x2a,0x86,0x48,0x86,0xf7,0x0d,0x01,0x07,0x01];
+    const ENCRYPTED_DATA: [u8; 9] = [0x2a,0x86,0x48,0x86,0xf7,0x0d,0x01,0x07,0x06];
+    // pkcs-9 OBJECT IDENTIFIER ::= { 1 2 840 113549 1 9 }
+    const FRIENDLY_NAME: [u8; 9] = [0x2a,0x86,0x48,0x86,0xf7,0x0d,0x01,0x09,0x14];
+    const LOCAL_KEY_ID: [u8; 9] = [0x2a,0x86,0x48,0x86,0xf7,0x0d,0x01,0x09,0x15];
+    const X509_CERTIFICATE: [u8; 10] = [0x2a,0x86,0x48,0x86,0xf7,0x0d,0x01,0x09,0x16,0x01];
+    // same as OpenSSL
+    const ITERATIONS: u32 = 2048;
+    const SALT_LEN: usize = 8;
+
+    // the localKeyId pairing the key with its certificate is the SHA-1 of the certificate,
+    // as OpenSSL makes it, or of the public key while there is no certificate
+    pub fn new(private_key: &Pkcs8, public_key: &Spki) -> Self {
+        Pkcs12{
+            private_key: private_key.to_der(),
+            local_key_id: Hash::Sha1.digest(&public_key.to_der()),
+            certificate: None,
+            friendly_name: None,
+            legacy: false,
+        }
+    }
+
+    pub fn with_certificate(mut self, certificate: &Certificate) -> Self {
+        let certificate = certificate.to_der().to_vec();
+        self.local_key_id = Hash::Sha1.digest(&certificate);
+        self.certificate = Some(certificate);
+        self
+    }
+
+    // shown for the key when it is imported, e.g. in the Windows certificate store
+    pub fn with_friendly_name(mut self, friendly_name: &str) -> Self {
+        self.friendly_name = Some(friendly_name.to_string());
+        self
+    }
+
+    // pbeWithSHAAnd3-KeyTripleDES-CBC and an HMAC-SHA-1 MAC instead of PBES2 and HMAC-SHA-256,
+    // for importers older than OpenSSL 1.1.1 and Windows Server 2019
+    pub fn with_legacy_encryption(mut self) -> Self {
+        self.legacy = true;
+        self
+    }
+
+    // PFX ::= SEQUENCE {
+    //   version INTEGER {v3(3)}(v3,...),
+    //   authSafe ContentInfo,
+    //   macData MacData OPTIONAL
+    // }
+    // AuthenticatedSafe ::= SEQUENCE OF ContentInfo
+    // The key goes in a data ContentInfo, being shrouded already,
+    // the certificate in an encryptedData one. rng makes the salts and IVs.
+    pub fn to_pfx<R: Rng + ?Sized>(&self, password: &str, rng: &mut R) -> Vec<u8> {
+        let attributes = self.gen_attributes();
+
+        let (algorithm_identifier, encrypted_key) = self.encrypt(password, &self.private_key, rng);
+        // EncryptedPrivateKeyInfo ::= SEQUENCE {
+        //   encryptionAlgorithm AlgorithmIdentifier,
+        //   encryptedData OCTET STRING
+        // }
+        let mut shrouded = algorithm_identifier;
+        shrouded.extend(der::encode(Tag::OctetString, encrypted_key));
+        let key_bag = safe_bag(&Pkcs12::PKCS8_SHROUDED_KEY_BAG, &der::encode(Tag::Sequence, shrouded), &attributes);
+        let key_contents = der::encode(Tag::Sequence, key_bag);
+
+        let mut authenticated_safe = Vec::new();
+        if let Some(ref certificate) = self.certificate {
+            // CertBag ::= SEQUENCE { certId BAG-TYPE.&id, certValue [0] EXPLICIT BAG-TYPE.&Type }
+            let mut cert = der::encode(Tag::ObjectIdentifier, Pkcs12::X509_CERTIFICATE);
+            cert.extend(der::encode(Tag::Context(0), der::encode(Tag::OctetString, certificate)));
+            let cert_bag = safe_bag(&Pkcs12::CERT_BAG, &der::encode(Tag::Sequence, cert), &attributes);
+            let cert_contents = der::encode(Tag::Sequence, cert_bag);
+
+            // EncryptedData ::= SEQUENCE { version INTEGER 0, encryptedContentInfo EncryptedContentInfo }
+            // EncryptedContentInfo ::= SEQUENCE {
+            //   contentType ContentType,
+            //   contentEncryptionAlgorithm ContentEncryptionAlgorithmIdentifier,
+            //   encryptedContent [0] IMPLICIT EncryptedContent OPTIONAL
+            // }
+            let (algorithm_identifier, encrypted_contents) = self.encrypt(password, &cert_contents, rng);
+            let mut info = der::encode(Tag::ObjectIdentifier, Pkcs12::DATA);
+            info.extend(algorithm_identifier);
+            info.extend(der::encode(Tag::ContextPrimitive(0), encrypted_contents));
+            let mut encrypted_data = der::encode(Tag::Integer, [0]);
+            encrypted_data.extend(der::encode(Tag::Sequence, info));
+            authenticated_safe.extend(content_info(&Pkcs12::ENCRYPTED_DATA, &der::encode(Tag::Sequence, encrypted_data)));
+        }
+        authenticated_safe.extend(content_info(&Pkcs12::DATA, &der::encode(Tag::OctetString, key_contents)));
+        let authenticated_safe = der::encode(Tag::Sequence, authenticated_safe);
+
+        let mut value = der::encode(Tag::Integer, [3]);
+        value.extend(content_info(&Pkcs12::DATA, &der::encode(Tag::OctetString, &authenticated_safe)));
+        value.extend(self.gen_mac_data(password, &authenticated_safe, rng));
+
+        der::encode(Tag::Sequence, value)
+    }
+
+    // SafeBag attributes, SET OF PKCS12Attribute
+    // friendlyName BMPString (RFC 2985 section 5.5.1), localKeyId OCTET STRING (section 5.5.2)
+    fn gen_attributes(&self) -> Vec<u8> {
+        let mut out = Vec::new();
+        if let Some(ref friendly_name) = self.friendly_name {
+            let name: Vec<u8> = friendly_name.encode_utf16().flat_map(|x| x.to_be_bytes()).collect();
+            let mut attribute = der::encode(Tag::ObjectIdentifier, Pkcs12::FRIENDLY_NAME);
+            attribute.extend(der::encode(Tag::Set, der::encode(Tag::BmpString, name)));
+            out.extend(der::encode(Tag::Sequence, attribute));
+        }
+        let mut attribute = der::encode(Tag::ObjectIdentifier, Pkcs12::LOCAL_KEY_ID);
+        attribute.extend(der::encode(Tag::Set, der::encode(Tag::OctetString, &self.local_key_id)));
+        out.extend(der::encode(Tag::Sequence, attribute));
+
+        out
+    }
+
+    // returns the AlgorithmIdentifier and the ciphertext
+    fn encrypt<R: Rng + ?Sized>(&self, password: &str, data: &[u8], rng: &mut R) -> (Vec<u8>, Vec<u8>) {
+        let mut salt = [0u8; Pkcs12::SALT_LEN];
+        rng.fill_bytes(&mut salt);
+
+        if self.legacy {
+            // pkcs-12PbeParams ::= SEQUENCE { salt OCTET STRING, iterations INTEGER }
+            let cipher = Cipher::DesEde3Cbc;
+            let password = bmp_password(password);
+            let key = derive(Hash::Sha1, 1, &password, &salt, Pkcs12::ITERATIONS, cipher.key_len());
+            let iv = derive(Hash::Sha1, 2, &password, &salt, Pkcs12::ITERATIONS, cipher.block_len());
+
+            let mut params = der::encode(Tag::OctetString, salt);
+            params.extend(der::encode(Tag::Integer, der::to_integer_u32(Pkcs12::ITERATIONS)));
+            let mut value = der::encode(Tag::ObjectIdentifier, Pkcs12::PBE_WITH_SHA_AND_3_KEY_TRIPLE_DES_CBC);
+            value.extend(der::encode(Tag::Sequence, params));
+
+            (der::encode(Tag::Sequence, value), cipher.encrypt(&key, &iv, data))
+        } else {
+            // PBES2 takes the password as it is, not as a BMPString
+            let mut iv = [0u8; 16];
+            rng.fill_bytes(&mut iv);
+
+            pkcs5::pbes2_encrypt(password.as_bytes(), &salt, Pkcs12::ITERATIONS, &iv, data)
+        }
+    }
+
+    // MacData ::= SEQUENCE {
+    //   mac DigestInfo,
+    //   macSalt OCTET STRING,
+    //   iterations INTEGER DEFAULT 1
+    // }
+    // HMAC over the AuthenticatedSafe with a key from derive (RFC 7292 Appendix B.4)
+    fn gen_mac_data<R: Rng + ?Sized>(&self, password: &str, data: &[u8], rng: &mut R) -> Vec<u8> {
+        let hash = if self.legacy { Hash::Sha1 } else { Hash::Sha256 };
+        let mut salt = [0u8; Pkcs12::SALT_LEN];
+        rng.fill_bytes(&mut salt);
+        let key = derive(hash, 3, &bmp_password(password), &salt, Pkcs12::ITERATIONS, hash.output_len());
+
+        // DigestInfo ::= SEQUENCE { digestAlgorithm DigestAlgorithmIdentifier, digest Digest }
+        let mut digest_info = hash.gen_algorithm_identifier();
+        digest_info.extend(der::encode(Tag::OctetString, hash.hmac(&key, data)));
+        let mut value = der::encode(Tag::Sequence, digest_info);
+        value.extend(der::encode(Tag::OctetString, salt));
+        value.extend(der::encode(Tag::Integer, der::to_integer_u32(Pkcs12::ITERATIONS)));
+
+        der::encode(Tag::Sequence, value)
+    }
+}
+
+// SafeBag ::= SEQUENCE {
+//   bagId BAG-TYPE.&id ({PKCS12BagSet}),
+//   bagValue [0] EXPLICIT BAG-TYPE.&Type({PKCS12BagSet}{@bagId}),
+//   bagAttributes SET OF PKCS12Attribute OPTIONAL
+// }
+fn safe_bag(bag_id: &[u8], value: &[u8], attributes: &[u8]) -> Vec<u8> {
+    let mut out = der::encode(Tag::ObjectIdentifier, bag_id);
+    out.extend(der::encode(Tag::Context(0), value));
+    out.extend(der::encode(Tag::Set, attributes));
+
+    der::encode(Tag::Sequence, out)
+}
+
+// ContentInfo ::= SEQUENCE { contentType ContentType, content [0] EXPLICIT ANY DEFINED BY contentType }
+fn content_info(content_type: &[u8], content: &[u8]) -> Vec<u8> {
+    let mut out = der::encode(Tag::ObjectIdentifier, content_type);
+    out.extend(der::encode(Tag::Context(0), content));
+
+    der::encode(Tag::Sequence, out)
+}
+
+// RFC 7292 Appendix B.1, a BMPString with two zero bytes at the end
+fn bmp_password(password: &str) -> Secret {
+    let mut out = Secret::with_capacity(2 * password.len() + 2);
+    for x in password.encode_utf16() {
+        out.extend(x.to_be_bytes());
+    }
+    out.extend([0, 0]);
+
+    out
+}
+
+// RFC 7292 Appendix B.2, n bytes of key material for purpose id
+// (1 encryption key, 2 IV, 3 MAC key) from a BMPString password
+pub fn derive(hash: Hash, id: u8, password: &[u8], salt: &[u8], iterations: u32, n: usize) -> Secret {
+    let v = hash.block_len();
+    let d = vec![id; v];
+    // S and P are the salt and the password repeated to a multiple of v bytes
+    let fill = |x: &[u8]| -> Secret {
+        let len = x.len().div_ceil(v) * v;
+        Secret::from(x.iter().cycle().take(len).copied().collect::<Vec<u8>>())
+    };
+    let mut i = Secret::new();
+    i.extend(fill(salt));
+    i.extend(fill(password));
+    let mut i = i.to_vec();
+
+    let mut out = Secret::with_capacity(n);
+    loop {
+        let mut a = d.clone();
+        a.extend(&i);
+        let mut a = Secret::from(a);
+        for _ in 0..iterations {
+            a = Secret::from(hash.digest(&a));
+        }
+        let take = (n - out.len()).min(a.len());
+        out.extend(&a[..take]);
+        if out.len() == n {
+            break;
+        }
+
+        // I_j = (I_j + B + 1) mod 2^(8v) for each v-byte block, B being A repeated to v bytes
+        let b: Vec<u8> = a.iter().cycle().take(v).copied().collect();
+        for block in i.chunks_mut(v) {
+            let mut carry = 1u16;
+            for (x, y) in block.iter_mut().zip(b.iter()).rev() {
+                let sum = *x as u16 + *y as u16 + carry;
+                *x = sum as u8;
+                carry = sum >> 8;
+            }
+        }
+    }
+    i.zeroize();
+
+    out
+}
@@ -0,0 +1,71 @@
+use crate::der;
+use crate::der::Tag;
+use crate::hash::Hash;
+use crate::cipher::Cipher;
+use crate::secret::Secret;
+
+// RFC 8018 section 5.2
+// T_i = U_1 xor ... xor U_c, U_1 = PRF(P, S || INT(i)), U_j = PRF(P, U_(j-1))
+pub fn pbkdf2(hash: Hash, password: &[u8], salt: &[u8], iterations: u32, len: usize) -> Secret {
+    assert!(iterations > 0, "PBKDF2 needs at least one iteration");
+    let mut out = Secret::with_capacity(len);
+    let mut i: u32 = 1;
+    while out.len() < len {
+        let mut u = salt.to_vec();
+        u.extend(i.to_be_bytes());
+        let mut t = Secret::from(vec![0u8; hash.output_len()]);
+        for _ in 0..iterations {
+            u = hash.hmac(password, &u);
+            t = Secret::from(t.iter().zip(u.iter()).map(|(x, y)| x ^ y).collect::<Vec<u8>>());
+        }
+        let take = (len - out.len()).min(t.len());
+        out.extend(&t[..take]);
+        i += 1;
+    }
+
+    out
+}
+
+// RFC 8018 section 6.2 PBES2 with PBKDF2 and HMAC-SHA-256 (RFC 8018 Appendix A.2, B.1.2)
+// and AES-256-CBC (Appendix B.2.5).
+// Returns the AlgorithmIdentifier and the ciphertext.
+//
+// PBES2-params ::= SEQUENCE {
+//   keyDerivationFunc AlgorithmIdentifier {{PBES2-KDFs}},
+//   encryptionScheme AlgorithmIdentifier {{PBES2-Encs}}
+// }
+// PBKDF2-params ::= SEQUENCE {
+//   salt OCTET STRING,
+//   iterationCount INTEGER (1..MAX),
+//   keyLength INTEGER (1..MAX) OPTIONAL,
+//   prf AlgorithmIdentifier {{PBKDF2-PRFs}} DEFAULT algid-hmacWithSHA1
+// }
+pub fn pbes2_encrypt(password: &[u8], salt: &[u8], iterations: u32, iv: &[u8], data: &[u8]) -> (Vec<u8>, Vec<u8>) {
+    // id-PBES2 OBJECT IDENTIFIER ::= { pkcs-5 13 }
+    const PBES2: [u8; 9] = [0x2a,0x86,0x48,0x86,0xf7,0x0d,0x01,0x05,0x0d];
+    // id-PBKDF2 OBJECT IDENTIFIER ::= { pkcs-5 12 }
+    const PBKDF2: [u8; 9] = [0x2a,0x86,0x48,0x86,0xf7,0x0d,0x01,0x05,0x0c];
+    // id-hmacWithSHA256 OBJECT IDENTIFIER ::= { digestAlgorithm 9 }, parameters NULL
+    const HMAC_WITH_SHA256: [u8; 14] = [0x30,0x0c,0x06,0x08,0x2a,0x86,0x48,0x86,0xf7,0x0d,0x02,0x09,0x05,0x00];
+    // aes256-CBC-PAD OBJECT IDENTIFIER ::= { aes 42 }
+    const AES256_CBC_PAD: [u8; 9] = [0x60,0x86,0x48,0x01,0x65,0x03,0x04,0x01,0x2a];
+
+    let cipher = Cipher::Aes256Cbc;
+    let key = pbkdf2(Hash::Sha256, password, salt, iterations, cipher.key_len());
+    let ciphertext = cipher.encrypt(&key, iv, data);
+
+    let mut params = der::encode(Tag::OctetString, salt);
+    params.extend(der::encode(Tag::Integer, der::to_integer_u32(iterations)));
+    params.extend(HMAC_WITH_SHA256);
+    let mut kdf = der::encode(Tag::ObjectIdentifier, PBKDF2);
+    kdf.extend(der::encode(Tag::Sequence, params));
+    let mut scheme = der::encode(Tag::ObjectIdentifier, AES256_CBC_PAD);
+    scheme.extend(der::encode(Tag::OctetString, iv));
+
+    let mut pbes2 = der::encode(Tag::Sequence, kdf);
+    pbes2.extend(der::encode(Tag::Sequence, scheme));
+    let mut value = der::encode(Tag::ObjectIdentifier, PBES2);
+    value.extend(der::encode(Tag::Sequence, pbes2));
+
+    (der::encode(Tag::Sequence, value), ciphertext)
+}
@@ -0,0 +1,336 @@
+use rand::Rng;
+use argon2::{Argon2, Algorithm, Version, Params};
+use crate::pem;
+use crate::ssh;
+use crate::ssh::SshFormat;
+use crate::hash::Hash;
+use crate::cipher::Cipher;
+use crate::key::Key;
+use crate::secret::Secret;
+
+// PuTTY private key file, PuTTY manual Appendix C.
+// The public key is the SSH public key blob, the private key blob holds
+// mpint d, mpint p, mpint q, mpint iqmp for "ssh-rsa" and
+// the seed as a little-endian unsigned integer in a string for "ssh-ed25519".
+pub struct Ppk {
+    version: u8,
+    key_type: Vec<u8>,
+    public_key: Vec<u8>,
+    private_key: Secret,
+    comment: String,
+}
+
+impl Ppk {
+    // PuTTY 0.52 to 0.74, HMAC-SHA-1 and a SHA-1 key derivation
+    pub const V2: u8 = 2;
+    // PuTTY 0.75 and later, Argon2id and HMAC-SHA-256
+    pub const V3: u8 = 3;
+
+    // PuTTYgen defaults, apart from the passes which it times
+    const ARGON2_MEMORY: u32 = 8192;
+    const ARGON2_PASSES: u32 = 13;
+    const ARGON2_PARALLELISM: u32 = 1;
+
+    // the blobs are taken out of the OpenSSH layouts of the key
+    pub fn new(version: u8, key: &dyn SshFormat, comment: String) -> Self {
+        assert!(version == Ppk::V2 || version == Ppk::V3, "PPK versions 2 and 3 are supported");
+        let public_key = key.gen_public_key();
+        let openssh = key.gen_private_key(0, String::new());
+        let mut data = &openssh[8..];
+        let key_type = ssh::read_string(&mut data).unwrap().to_vec();
+
+        let mut private_key = Secret::new();
+        match &key_type[..] {
+            // n, e, d, iqmp, p, q
+            b"ssh-rsa" => {
+                let fields: Vec<&[u8]> = (0..6).map(|_| ssh::read_string(&mut data).unwrap()).collect();
+                for i in [2, 4, 5, 3] {
+                    private_key.extend(ssh::to_string_secret(fields[i]));
+                }
+            },
+            // public key, seed || public key
+            b"ssh-ed25519" => {
+                ssh::read_string(&mut data).unwrap();
+                let seed = &ssh::read_string(&mut data).unwrap()[..32];
+                let len = seed.iter().rposition(|x| *x != 0).map_or(0, |i| i + 1);
+                private_key.extend(ssh::to_string_secret(&seed[..len]));
+            },
+            _ => unreachable!(),
+        }
+
+        Ppk{
+            version,
+            key_type,
+            public_key,
+            private_key,
+            comment,
+        }
+    }
+
+    pub fn comment(&self) -> &str {
+        &self.comment
+    }
+
+    // the key the blobs make up, None when the private key does not match the public key
+    pub fn to_key(&self) -> Option<Key> {
+        let mut data = &self.private_key[..];
+        let mut public_key = &self.public_key[..];
+        ssh::read_string(&mut public_key)?;
+
+        let mut openssh = Secret::new();
+        openssh.extend(ssh::to_string(&self.key_type));
+        match &self.key_type[..] {
+            b"ssh-rsa" => {
+                let e = ssh::read_string(&mut public_key)?;
+                let n = ssh::read_string(&mut public_key)?;
+                let d = ssh::read_string(&mut data)?;
+                let p = ssh::read_string(&mut data)?;
+                let q = ssh::read_string(&mut data)?;
+                let iqmp = ssh::read_string(&mut data)?;
+                for field in [n, e, d, iqmp, p, q] {
+                    openssh.extend(ssh::to_string_secret(field));
+                }
+            },
+            b"ssh-ed25519" => {
+                let public_key = ssh::read_string(&mut public_key)?;
+                let seed = ssh::read_string(&mut data)?;
+                if seed.len() > 32 {
+                    return None;
+                }
+                let mut private_key = Secret::from(seed.to_vec());
+                private_key.extend(vec![0u8; 32 - seed.len()]);
+                private_key.extend(public_key);
+                openssh.extend(ssh::to_string(public_key));
+                openssh.extend(ssh::to_string_secret(&private_key));
+            },
+            _ => return None,
+        }
+
+        let key = Key::from_ssh(&openssh)?;
+        if key.public_key().to_ssh() != self.public_key {
+            return None;
+        }
+        Some(key)
+    }
+
+    // PuTTY-User-Key-File-3: <key type>
+    // Encryption: none | aes256-cbc
+    // Comment: <comment>
+    // Public-Lines: <n>, then n lines of base64
+    // Key-Derivation: Argon2id, Argon2-Memory, Argon2-Passes, Argon2-Parallelism, Argon2-Salt (v3, encrypted only)
+    // Private-Lines: <n>, then n lines of base64
+    // Private-MAC: <hex>
+    pub fn to_ppk<R: Rng + ?Sized>(&self, passphrase: Option<&str>, rng: &mut R) -> Vec<u8> {
+        let encryption = if passphrase.is_some() { "aes256-cbc" } else { "none" };
+        let mut salt = [0u8; 16];
+        rng.fill(&mut salt);
+
+        let mut header = String::new();
+        if let (Ppk::V3, Some(_)) = (self.version, passphrase) {
+            header.push_str("Key-Derivation: Argon2id\n");
+            header.push_str(&format!("Argon2-Memory: {}\n", Ppk::ARGON2_MEMORY));
+            header.push_str(&format!("Argon2-Passes: {}\n", Ppk::ARGON2_PASSES));
+            header.push_str(&format!("Argon2-Parallelism: {}\n", Ppk::ARGON2_PARALLELISM));
+            header.push_str(&format!("Argon2-Salt: {}\n", to_hex(&salt)));
+        }
+        let keys = Ppk::derive(self.version, passphrase, Algorithm::Argon2id, Ppk::ARGON2_MEMORY, Ppk::ARGON2_PASSES, Ppk::ARGON2_PARALLELISM, &salt).unwrap();
+
+        // padded to the cipher block with the SHA-1 of the blob, as PuTTY does
+        let mut private_key = Secret::from(self.private_key.to_vec());
+        if passphrase.is_some() {
+            let pad = (16 - private_key.len() % 16) % 16;
+            private_key.extend(&Hash::Sha1.digest(&self.private_key)[..pad]);
+        }
+        let mac = self.mac(encryption, &keys.mac_key, &private_key);
+        let mut private_key = private_key.to_vec();
+        if passphrase.is_some() {
+            Cipher::Aes256Cbc.encrypt_blocks(&keys.cipher_key, &keys.iv, &mut private_key);
+        }
+        let private_key = Secret::from(private_key);
+
+        let mut out = Vec::new();
+        out.extend(format!("PuTTY-User-Key-File-{}: {}\n", self.version, String::from_utf8_lossy(&self.key_type)).as_bytes());
+        out.extend(format!("Encryption: {}\n", encryption).as_bytes());
+        out.extend(format!("Comment: {}\n", self.comment).as_bytes());
+        out.extend(base64_lines("Public", &self.public_key).iter());
+        out.extend(header.as_bytes());
+        out.extend(base64_lines("Private", &private_key).iter());
+        out.extend(format!("Private-MAC: {}\n", to_hex(&mac)).as_bytes());
+
+        out
+    }
+
+    // None when the file is malformed, the passphrase is wrong or missing, or the MAC does not match
+    pub fn from_ppk(data: &[u8], passphrase: Option<&str>) -> Option<Self> {
+        let data = std::str::from_utf8(data).ok()?;
+        let mut lines = data.lines().map(|x| x.trim_end_matches('\r'));
+
+        let (version, key_type) = lines.next()?.strip_prefix("PuTTY-User-Key-File-")?.split_once(": ")?;
+        let version = match version {
+            "2" => Ppk::V2,
+            "3" => Ppk::V3,
+            _ => return None,
+        };
+        let encryption = header(&mut lines, "Encryption")?;
+        let comment = header(&mut lines, "Comment")?;
+        let public_key = read_base64_lines(&mut lines, "Public")?.to_vec();
+
+        let passphrase = match (encryption, passphrase) {
+            ("none", _) => None,
+            ("aes256-cbc", Some(passphrase)) => Some(passphrase),
+            _ => return None,
+        };
+        let keys = if let (Ppk::V3, Some(_)) = (version, passphrase) {
+            let algorithm = match header(&mut lines, "Key-Derivation")? {
+                "Argon2d" => Algorithm::Argon2d,
+                "Argon2i" => Algorithm::Argon2i,
+                "Argon2id" => Algorithm::Argon2id,
+                _ => return None,
+            };
+            let memory = header(&mut lines, "Argon2-Memory")?.parse().ok()?;
+            let passes = header(&mut lines, "Argon2-Passes")?.parse().ok()?;
+            let parallelism = header(&mut lines, "Argon2-Parallelism")?.parse().ok()?;
+            let salt = from_hex(header(&mut lines, "Argon2-Salt")?)?;
+            Ppk::derive(version, passphrase, algorithm, memory, passes, parallelism, &salt)?
+        } else {
+            Ppk::derive(version, passphrase, Algorithm::Argon2id, 0, 0, 0, &[])?
+        };
+
+        let mut private_key = read_base64_lines(&mut lines, "Private")?.to_vec();
+        let mac = from_hex(header(&mut lines, "Private-MAC")?)?;
+        if passphrase.is_some() {
+            if private_key.len() % 16 != 0 {
+                return None;
+            }
+            Cipher::Aes256Cbc.decrypt_blocks(&keys.cipher_key, &keys.iv, &mut private_key);
+        }
+        let private_key = Secret::from(private_key);
+
+        let mut ppk = Ppk{
+            version,
+            key_type: key_type.as_bytes().to_vec(),
+            public_key,
+            private_key,
+            comment: comment.to_string(),
+        };
+        let expected = ppk.mac(encryption, &keys.mac_key, &ppk.private_key);
+        if expected.len() != mac.len() || expected.iter().zip(mac.iter()).fold(0, |acc, (x, y)| acc | (x ^ y)) != 0 {
+            return None;
+        }
+        // the padding goes, the blob is read up to its last field
+        let len = ppk.private_key_len()?;
+        ppk.private_key = Secret::from(ppk.private_key[..len].to_vec());
+
+        Some(ppk)
+    }
+
+    // The Argon2 parameters only count for v3 with a passphrase.
+    // v3: Argon2 over the passphrase gives 80 bytes, the AES-256 key, the IV and the MAC key.
+    // Without a passphrase the MAC key is empty.
+    // v2: the AES-256 key is SHA-1(uint32 0 || passphrase) || SHA-1(uint32 1 || passphrase) cut to 32 bytes,
+    // the IV is zero and the MAC key is SHA-1("putty-private-key-file-mac-key" || passphrase).
+    fn derive(version: u8, passphrase: Option<&str>, algorithm: Algorithm, memory: u32, passes: u32, parallelism: u32, salt: &[u8]) -> Option<Keys> {
+        match (version, passphrase) {
+            (Ppk::V3, Some(passphrase)) => {
+                let params = Params::new(memory, passes, parallelism, Some(80)).ok()?;
+                let mut out = vec![0u8; 80];
+                Argon2::new(algorithm, Version::V0x13, params).hash_password_into(passphrase.as_bytes(), salt, &mut out).ok()?;
+                let out = Secret::from(out);
+                Some(Keys{
+                    cipher_key: Secret::from(out[..32].to_vec()),
+                    iv: out[32..48].to_vec(),
+                    mac_key: Secret::from(out[48..].to_vec()),
+                })
+            },
+            (Ppk::V3, None) => Some(Keys{
+                cipher_key: Secret::new(),
+                iv: Vec::new(),
+                mac_key: Secret::new(),
+            }),
+            (_, passphrase) => {
+                let passphrase = passphrase.unwrap_or("").as_bytes();
+                let mut cipher_key = Secret::new();
+                for i in 0u32..2 {
+                    let mut data = Secret::from(i.to_be_bytes().to_vec());
+                    data.extend(passphrase);
+                    cipher_key.extend(Hash::Sha1.digest(&data));
+                }
+                let mut mac_key = Secret::from(b"putty-private-key-file-mac-key".to_vec());
+                mac_key.extend(passphrase);
+                Some(Keys{
+                    cipher_key: Secret::from(cipher_key[..32].to_vec()),
+                    iv: vec![0u8; 16],
+                    mac_key: Secret::from(Hash::Sha1.digest(&mac_key)),
+                })
+            },
+        }
+    }
+
+    // HMAC over string key type, string encryption, string comment,
+    // string public key blob and string private key blob with its padding;
+    // HMAC-SHA-256 for v3, HMAC-SHA-1 for v2
+    fn mac(&self, encryption: &str, key: &[u8], private_key: &[u8]) -> Vec<u8> {
+        let mut data = Secret::new();
+        data.extend(ssh::to_string(&self.key_type));
+        data.extend(ssh::to_string(encryption.as_bytes()));
+        data.extend(ssh::to_string(self.comment.as_bytes()));
+        data.extend(ssh::to_string(&self.public_key));
+        data.extend(ssh::to_string_secret(private_key));
+        let hash = if self.version == Ppk::V2 { Hash::Sha1 } else { Hash::Sha256 };
+
+        hash.hmac(key, &data)
+    }
+
+    // length of the private key blob without padding, 4 strings for rsa and 1 for ed25519
+    fn private_key_len(&self) -> Option<usize> {
+        let fields = if self.key_type == b"ssh-rsa" { 4 } else { 1 };
+        let mut data = &self.private_key[..];
+        for _ in 0..fields {
+            ssh::read_string(&mut data)?;
+        }
+        Some(self.private_key.len() - data.len())
+    }
+}
+
+struct Keys {
+    cipher_key: Secret,
+    iv: Vec<u8>,
+    mac_key: Secret,
+}
+
+// "<name>: <value>" on the next line
+fn header<'a>(lines: &mut impl Iterator<Item = &'a str>, name: &str) -> Option<&'a str> {
+    lines.next()?.strip_prefix(name)?.strip_prefix(": ")
+}
+
+// "<kind>-Lines: <n>" and n lines of base64 of 64 characters
+fn base64_lines(kind: &str, data: &[u8]) -> Secret {
+    let base64 = Secret::from(pem::base64(data));
+    let mut out = Secret::new();
+    out.extend(format!("{}-Lines: {}\n", kind, base64.len().div_ceil(64)).as_bytes());
+    for line in base64.chunks(64) {
+        out.extend(line);
+        out.push(b'\n');
+    }
+    out
+}
+
+fn read_base64_lines<'a>(lines: &mut impl Iterator<Item = &'a str>, kind: &str) -> Option<Secret> {
+    let count: usize = header(lines, &format!("{}-Lines", kind))?.parse().ok()?;
+    let mut base64 = Secret::new();
+    for _ in 0..count {
+        base64.extend(lines.next()?.as_bytes());
+    }
+    pem::base64_decode(&base64)
+}
+
+fn to_hex(data: &[u8]) -> String {
+    data.iter().map(|x| format!("{:02x}", x)).collect()
+}
+
+fn from_hex(data: &str) -> Option<Vec<u8>> {
+    if !data.len().is_multiple_of(2) || !data.bytes().all(|x| x.is_ascii_hexdigit()) {
+        return None;
+    }
+    (0..data.len()).step_by(2).map(|i| u8::from_str_radix(&data[i..i + 2], 16).ok()).collect()
+}
@@ -0,0 +1,217 @@
+use rand::Rng;
+use num_bigint::{BigUint, RandBigInt};
+use num_traits::{One, Zero, ToPrimitive};
+
+const SMALL_PRIMES: [u32; 168] = [
+	2, 3, 5, 7, 11, 13, 17, 19, 23, 29, 31, 37, 41, 43, 47, 53, 59, 61, 67, 71, 73, 79, 83, 89, 97,
+	101, 103, 107, 109, 113, 127, 131, 137, 139, 149, 151, 157, 163, 167, 173, 179, 181, 191, 193,
+	197, 199, 211, 223, 227, 229, 233, 239, 241, 251, 257, 263, 269, 271, 277, 281, 283, 293, 307,
+	311, 313, 317, 331, 337, 347, 349, 353, 359, 367, 373, 379, 383, 389, 397, 401, 409, 419, 421,
+	431, 433, 439, 443, 449, 457, 461, 463, 467, 479, 487, 491, 499, 503, 509, 521, 523, 541, 547,
+	557, 563, 569, 571, 577, 587, 593, 599, 601, 607, 613, 617, 619, 631, 641, 643, 647, 653, 659,
+	661, 673, 677, 683, 691, 701, 709, 719, 727, 733, 739, 743, 751, 757, 761, 769, 773, 787, 797,
+	809, 811, 821, 823, 827, 829, 839, 853, 857, 859, 863, 877, 881, 883, 887, 907, 911, 919, 929,
+	937, 941, 947, 953, 967, 971, 977, 983, 991, 997,
+];
+
+// how far the sieve walks from one random start before drawing a new one
+const SIEVE_RANGE: u32 = 1 << 16;
+
+// Random prime in [low, high) with gcd(prime - 1, e) = 1.
+// high is a power of two and sets the size of the prime,
+// low is chosen by the caller so the product of the primes has the wanted size.
+//
+// A random odd start is reduced once modulo every small prime and e,
+// then the candidates start + 2k are sieved using those residues alone.
+// Only survivors reach the big-number tests.
+pub fn gen_prime<R: Rng + ?Sized>(low: &BigUint, high: &BigUint, e: u32, rng: &mut R) -> BigUint {
+    let bits = high.bits() as usize - 1;
+
+    loop {
+        let start = rng.gen_biguint_range(low, high) | BigUint::one();
+        let residues: Vec<u32> = SMALL_PRIMES.iter()
+            .map(|p| (&start % p).to_u32().unwrap())
+            .collect();
+        let residue_e = (&start % e).to_u32().unwrap();
+
+        let mut delta = 0;
+        while delta < SIEVE_RANGE {
+            let composite = residues.iter().zip(SMALL_PRIMES.iter())
+                .any(|(r, p)| (r + delta) % p == 0);
+            let bad_e = (residue_e + delta) % e == 1;
+            if !composite && !bad_e {
+                let candidate = &start + delta;
+                if candidate >= *high {
+                    break;
+                }
+                if is_prime(&candidate, bits, rng) {
+                    return candidate;
+                }
+            }
+            delta += 2;
+        }
+    }
+}
+
+// FIPS 186-5 Table B.1, M-R tests only column.
+// The strong Lucas test run afterwards is extra margin on top of it.
+pub fn miller_rabin_rounds(bits: usize) -> usize {
+    if bits * 2 >= 3072 {
+        4
+    } else {
+        5
+    }
+}
+
+// Baillie-PSW: trial division, Miller-Rabin to base 2,
+// the FIPS 186-5 number of random-base rounds, then a strong Lucas test.
+pub fn is_prime<R: Rng + ?Sized>(n: &BigUint, bits: usize, rng: &mut R) -> bool {
+    let two = BigUint::from(2u8);
+    if *n < two {
+        return false;
+    }
+    for p in SMALL_PRIMES.iter() {
+        if n == &BigUint::from(*p) {
+            return true;
+        }
+        if n % p == BigUint::zero() {
+            return false;
+        }
+    }
+
+    if !miller_rabin(n, &two) {
+        return false;
+    }
+    for _ in 0..miller_rabin_rounds(bits) {
+        let a = rng.gen_biguint_range(&two, &(n - 2u8));
+        if !miller_rabin(n, &a) {
+            return false;
+        }
+    }
+    strong_lucas(n)
+}
+
+fn miller_rabin(n: &BigUint, a: &BigUint) -> bool {
+    let one = BigUint::one();
+    let n1 = n - 1u8;
+    let mut k = 0;
+    let mut d = n1.clone();
+    while !d.bit(0) {
+        k += 1;
+        d >>= 1;
+    }
+
+    let mut b = a.modpow(&d, n);
+    if b == one || b == n1 {
+        return true;
+    }
+    for _ in 1..k {
+        b = &b * &b % n;
+        if b == n1 {
+            return true;
+        }
+    }
+    false
+}
+
+// FIPS 186-5 Appendix B.3.3, with D chosen by Selfridge's method A
+pub fn strong_lucas(n: &BigUint) -> bool {
+    // no D with (D/n) = -1 exists for a perfect square
+    if n.sqrt().pow(2) == *n {
+        return false;
+    }
+
+    let mut d: i64 = 5;
+    loop {
+        match jacobi(&signed_mod(d, n), n) {
+            -1 => break,
+            0 if BigUint::from(d.unsigned_abs()) != *n => return false,
+            _ => {},
+        }
+        d = if d > 0 { -(d + 2) } else { -d + 2 };
+    }
+    let dn = signed_mod(d, n);
+    // P = 1, Q = (1 - D) / 4
+    let q = signed_mod((1 - d) / 4, n);
+
+    // n + 1 = k * 2^s with k odd
+    let n1 = n + 1u8;
+    let mut s = 0;
+    while !n1.bit(s) {
+        s += 1;
+    }
+    let k = &n1 >> s;
+
+    let half = |x: BigUint| -> BigUint {
+        if x.bit(0) {
+            (x + n) >> 1
+        } else {
+            x >> 1
+        }
+    };
+
+    let mut u = BigUint::one();
+    let mut v = BigUint::one();
+    let mut qk = q.clone();
+    for i in (0..k.bits() - 1).rev() {
+        // (U, V, Q^k) for 2k
+        u = &u * &v % n;
+        v = (&v * &v + n * 2u8 - (&qk * 2u8) % n) % n;
+        qk = &qk * &qk % n;
+        if k.bit(i) {
+            // (U, V, Q^k) for k + 1
+            let u1 = half(&u + &v) % n;
+            let v1 = half(&dn * &u + &v) % n;
+            u = u1;
+            v = v1;
+            qk = &qk * &q % n;
+        }
+    }
+
+    if u.is_zero() || v.is_zero() {
+        return true;
+    }
+    for _ in 1..s {
+        v = (&v * &v + n * 2u8 - (&qk * 2u8) % n) % n;
+        if v.is_zero() {
+            return true;
+        }
+        qk = &qk * &qk % n;
+    }
+    false
+}
+
+fn signed_mod(a: i64, n: &BigUint) -> BigUint {
+    let r = BigUint::from(a.unsigned_abs()) % n;
+    if a < 0 && !r.is_zero() {
+        n - r
+    } else {
+        r
+    }
+}
+
+// Jacobi symbol (a/n) for odd n
+pub fn jacobi(a: &BigUint, n: &BigUint) -> i32 {
+    let mut a = a % n;
+    let mut n = n.clone();
+    let mut t = 1;
+    while !a.is_zero() {
+        while !a.bit(0) {
+            a >>= 1;
+            let r = (&n % 8u8).to_u8().unwrap();
+            if r == 3 || r == 5 {
+                t = -t;
+            }
+        }
+        std::mem::swap(&mut a, &mut n);
+        if (&a % 4u8).to_u8() == Some(3) && (&n % 4u8).to_u8() == Some(3) {
+            t = -t;
+        }
+        a %= &n;
+    }
+    if n.is_one() {
+        t
+    } else {
+        0
+    }
+}
@@ -0,0 +1,92 @@
+use crate::hash::Hash;
+use crate::pkcs5;
+use crate::secret::Secret;
+use zeroize::Zeroizing;
+
+// RFC 7914 section 6, N = 2^log_n.
+// B = PBKDF2-HMAC-SHA256(P, S, 1, p * 128 * r), each 128 * r bytes of B through scryptROMix,
+// DK = PBKDF2-HMAC-SHA256(P, B, 1, dkLen).
+// Needs 128 * r * N bytes of memory.
+pub fn scrypt(password: &[u8], salt: &[u8], log_n: u32, r: usize, p: usize, len: usize) -> Secret {
+    assert!((1..32).contains(&log_n) && r > 0 && p > 0, "malformed scrypt parameters");
+    let n = 1usize << log_n;
+    let words = 32 * r;
+
+    let b = pkcs5::pbkdf2(Hash::Sha256, password, salt, 1, p * 128 * r);
+    let mut x = Zeroizing::new(vec![0u32; p * words]);
+    for (word, bytes) in x.iter_mut().zip(b.chunks(4)) {
+        *word = u32::from_le_bytes(bytes.try_into().unwrap());
+    }
+
+    let mut v = Zeroizing::new(vec![0u32; n * words]);
+    let mut t = Zeroizing::new(vec![0u32; words]);
+    for block in x.chunks_mut(words) {
+        ro_mix(block, &mut v, &mut t, n);
+    }
+
+    let mut b = Secret::with_capacity(p * 128 * r);
+    for word in x.iter() {
+        b.extend(word.to_le_bytes());
+    }
+    pkcs5::pbkdf2(Hash::Sha256, password, &b, 1, len)
+}
+
+// RFC 7914 section 5, scryptROMix
+fn ro_mix(x: &mut [u32], v: &mut [u32], t: &mut [u32], n: usize) {
+    let words = x.len();
+    for i in 0..n {
+        v[i * words..(i + 1) * words].copy_from_slice(x);
+        block_mix(x, t);
+    }
+    for _ in 0..n {
+        // Integerify mod N, the low word of the last 64 byte block as N is below 2^32
+        let j = x[words - 16] as usize & (n - 1);
+        for (a, b) in x.iter_mut().zip(&v[j * words..(j + 1) * words]) {
+            *a ^= b;
+        }
+        block_mix(x, t);
+    }
+}
+
+// RFC 7914 section 4, scryptBlockMix with Salsa20/8.
+// Y_i = Salsa(Y_(i-1) xor B_i), B' = (Y_0, Y_2, ..., Y_(2r-2), Y_1, Y_3, ..., Y_(2r-1))
+fn block_mix(b: &mut [u32], y: &mut [u32]) {
+    let blocks = b.len() / 16;
+    let mut x = Zeroizing::new([0u32; 16]);
+    x.copy_from_slice(&b[(blocks - 1) * 16..]);
+    for i in 0..blocks {
+        for (a, c) in x.iter_mut().zip(&b[i * 16..(i + 1) * 16]) {
+            *a ^= c;
+        }
+        salsa20_8(&mut x);
+        let dest = (i / 2 + (i % 2) * (blocks / 2)) * 16;
+        y[dest..dest + 16].copy_from_slice(&*x);
+    }
+    b.copy_from_slice(y);
+}
+
+// RFC 7914 section 3, Salsa20/8 Core, 4 double rounds and the input added back
+fn salsa20_8(b: &mut [u32; 16]) {
+    let mut x = *b;
+    let quarter = |x: &mut [u32; 16], a: usize, b: usize, c: usize, d: usize| {
+        x[b] ^= x[a].wrapping_add(x[d]).rotate_left(7);
+        x[c] ^= x[b].wrapping_add(x[a]).rotate_left(9);
+        x[d] ^= x[c].wrapping_add(x[b]).rotate_left(13);
+        x[a] ^= x[d].wrapping_add(x[c]).rotate_left(18);
+    };
+    for _ in 0..4 {
+        // columns
+        quarter(&mut x, 0, 4, 8, 12);
+        quarter(&mut x, 5, 9, 13, 1);
+        quarter(&mut x, 10, 14, 2, 6);
+        quarter(&mut x, 15, 3, 7, 11);
+        // rows
+        quarter(&mut x, 0, 1, 2, 3);
+        quarter(&mut x, 5, 6, 7, 4);
+        quarter(&mut x, 10, 11, 8, 9);
+        quarter(&mut x, 15, 12, 13, 14);
+    }
+    for (a, c) in b.iter_mut().zip(x.iter()) {
+        *a = a.wrapping_add(*c);
+    }
+}
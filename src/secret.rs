@@ -0,0 +1,142 @@
+use std::ops::Deref;
+use std::sync::atomic::{AtomicBool, Ordering};
+use num_bigint::BigUint;
+use zeroize::Zeroize;
+
+static MLOCK: AtomicBool = AtomicBool::new(false);
+
+// Ask for every Secret allocated from now on to be locked into RAM
+// so it never reaches swap. Silently ignored where mlock is unavailable.
+pub fn enable_mlock() {
+    MLOCK.store(true, Ordering::Relaxed);
+}
+
+// Buffer for secret key material.
+// Zeroed on drop, and zeroed before being moved when it has to grow,
+// so no stale copy is left behind in freed memory.
+// Deliberately not Clone.
+pub struct Secret {
+    bytes: Vec<u8>,
+    locked: bool,
+}
+
+impl Secret {
+    pub fn new() -> Self {
+        Secret::with_capacity(0)
+    }
+
+    pub fn with_capacity(capacity: usize) -> Self {
+        Secret::from(Vec::with_capacity(capacity))
+    }
+
+    pub fn extend<T: AsRef<[u8]>>(&mut self, data: T) {
+        let data = data.as_ref();
+        let needed = self.bytes.len() + data.len();
+        if needed > self.bytes.capacity() {
+            let mut grown = Vec::with_capacity(needed.max(self.bytes.capacity() * 2));
+            grown.extend_from_slice(&self.bytes);
+            let old = std::mem::replace(self, Secret::from(grown));
+            drop(old);
+        }
+        self.bytes.extend_from_slice(data);
+    }
+
+    pub fn push(&mut self, byte: u8) {
+        self.extend([byte]);
+    }
+}
+
+impl Default for Secret {
+    fn default() -> Self {
+        Secret::new()
+    }
+}
+
+impl From<Vec<u8>> for Secret {
+    fn from(bytes: Vec<u8>) -> Self {
+        let locked = MLOCK.load(Ordering::Relaxed) && lock(&bytes);
+
+        Secret{
+            bytes,
+            locked,
+        }
+    }
+}
+
+impl Deref for Secret {
+    type Target = [u8];
+
+    fn deref(&self) -> &[u8] {
+        &self.bytes
+    }
+}
+
+impl AsRef<[u8]> for Secret {
+    fn as_ref(&self) -> &[u8] {
+        &self.bytes
+    }
+}
+
+impl Drop for Secret {
+    fn drop(&mut self) {
+        // zeroize clears the whole capacity but keeps the allocation,
+        // so the same range can still be unlocked afterwards
+        self.bytes.zeroize();
+        if self.locked {
+            unlock(&self.bytes);
+        }
+    }
+}
+
+// BigUint intermediates holding secret values (p, q, d, ...).
+// num-bigint has no zeroize support, so the digits are overwritten
+// in place with zeros of the same length before the memory is freed.
+pub struct SecretInt(BigUint);
+
+impl SecretInt {
+    pub fn new(n: BigUint) -> Self {
+        SecretInt(n)
+    }
+
+    pub fn to_bytes_be(&self) -> Secret {
+        Secret::from(self.0.to_bytes_be())
+    }
+}
+
+impl Deref for SecretInt {
+    type Target = BigUint;
+
+    fn deref(&self) -> &BigUint {
+        &self.0
+    }
+}
+
+impl Drop for SecretInt {
+    fn drop(&mut self) {
+        let zeros = vec![0u32; self.0.iter_u32_digits().len()];
+        self.0.assign_from_slice(&zeros);
+    }
+}
+
+#[cfg(unix)]
+fn lock(bytes: &Vec<u8>) -> bool {
+    if bytes.capacity() == 0 {
+        return false;
+    }
+    unsafe { libc::mlock(bytes.as_ptr() as *const libc::c_void, bytes.capacity()) == 0 }
+}
+
+#[cfg(unix)]
+fn unlock(bytes: &Vec<u8>) {
+    unsafe {
+        libc::munlock(bytes.as_ptr() as *const libc::c_void, bytes.capacity());
+    }
+}
+
+#[cfg(not(unix))]
+fn lock(_bytes: &Vec<u8>) -> bool {
+    false
+}
+
+#[cfg(not(unix))]
+fn unlock(_bytes: &Vec<u8>) {}
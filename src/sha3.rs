@@ -0,0 +1,79 @@
+// FIPS 202, SHA3-256: Keccak-f[1600] sponge with a rate of 136 bytes
+// and the message padded with the domain bits 01, then 10*1
+pub fn sha3_256(data: &[u8]) -> [u8; 32] {
+    const RATE: usize = 136;
+    let mut state = [0u64; 25];
+
+    let mut blocks = data.chunks_exact(RATE);
+    for block in blocks.by_ref() {
+        absorb(&mut state, block);
+    }
+    let rest = blocks.remainder();
+    let mut last = [0u8; RATE];
+    last[..rest.len()].copy_from_slice(rest);
+    last[rest.len()] ^= 0x06;
+    last[RATE - 1] ^= 0x80;
+    absorb(&mut state, &last);
+
+    let mut out = [0u8; 32];
+    for (bytes, lane) in out.chunks_mut(8).zip(state.iter()) {
+        bytes.copy_from_slice(&lane.to_le_bytes());
+    }
+    out
+}
+
+// lanes are little-endian
+fn absorb(state: &mut [u64; 25], block: &[u8]) {
+    for (lane, bytes) in state.iter_mut().zip(block.chunks(8)) {
+        *lane ^= u64::from_le_bytes(bytes.try_into().unwrap());
+    }
+    keccak_f(state);
+}
+
+// FIPS 202 section 3.3, 24 rounds of theta, rho and pi, chi, iota
+fn keccak_f(a: &mut [u64; 25]) {
+    const RC: [u64; 24] = [
+        0x0000000000000001, 0x0000000000008082, 0x800000000000808a, 0x8000000080008000,
+        0x000000000000808b, 0x0000000080000001, 0x8000000080008081, 0x8000000000008009,
+        0x000000000000008a, 0x0000000000000088, 0x0000000080008009, 0x000000008000000a,
+        0x000000008000808b, 0x800000000000008b, 0x8000000000008089, 0x8000000000008003,
+        0x8000000000008002, 0x8000000000000080, 0x000000000000800a, 0x800000008000000a,
+        0x8000000080008081, 0x8000000000008080, 0x0000000080000001, 0x8000000080008008,
+    ];
+    // rho offsets along the path of pi, starting from lane 1
+    const ROTATION: [u32; 24] = [1, 3, 6, 10, 15, 21, 28, 36, 45, 55, 2, 14, 27, 41, 56, 8, 25, 43, 62, 18, 39, 61, 20, 44];
+    const PI: [usize; 24] = [10, 7, 11, 17, 18, 3, 5, 16, 8, 21, 24, 4, 15, 23, 19, 13, 12, 2, 20, 14, 22, 9, 6, 1];
+
+    for rc in RC {
+        // theta
+        let mut c = [0u64; 5];
+        for x in 0..5 {
+            c[x] = a[x] ^ a[x + 5] ^ a[x + 10] ^ a[x + 15] ^ a[x + 20];
+        }
+        for x in 0..5 {
+            let d = c[(x + 4) % 5] ^ c[(x + 1) % 5].rotate_left(1);
+            for y in (0..25).step_by(5) {
+                a[y + x] ^= d;
+            }
+        }
+
+        // rho and pi
+        let mut t = a[1];
+        for (&j, &r) in PI.iter().zip(ROTATION.iter()) {
+            let next = a[j];
+            a[j] = t.rotate_left(r);
+            t = next;
+        }
+
+        // chi
+        for y in (0..25).step_by(5) {
+            let row = [a[y], a[y + 1], a[y + 2], a[y + 3], a[y + 4]];
+            for x in 0..5 {
+                a[y + x] = row[x] ^ (!row[(x + 1) % 5] & row[(x + 2) % 5]);
+            }
+        }
+
+        // iota
+        a[0] ^= rc;
+    }
+}
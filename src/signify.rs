@@ -0,0 +1,155 @@
+use rand::Rng;
+use sha2::{Sha512, Digest};
+use crate::pem;
+use crate::bcrypt;
+use crate::ed25519::Ed25519;
+use crate::secret::Secret;
+
+// OpenBSD signify(1) keys and signatures, Ed25519 keys named by a random
+// 8 byte key number that signatures carry too
+pub struct Signify {
+    key: Ed25519,
+    key_number: [u8; 8],
+    comment: String,
+}
+
+impl Signify {
+    const ED25519: [u8; 2] = *b"Ed";
+    const KDF_BCRYPT: [u8; 2] = *b"BK";
+    // bcrypt_pbkdf rounds of signify -G
+    const ROUNDS: u32 = 42;
+
+    // the comment goes in the untrusted comment lines of both keys,
+    // "<comment> secret key" and "<comment> public key"; signify -G uses "signify"
+    pub fn new<R: Rng + ?Sized>(key: Ed25519, comment: String, rng: &mut R) -> Self {
+        assert!(!comment.contains(['\r', '\n']), "an untrusted comment is one line");
+        Signify{
+            key,
+            key_number: rng.gen(),
+            comment,
+        }
+    }
+
+    pub fn key(&self) -> &Ed25519 {
+        &self.key
+    }
+
+    pub fn comment(&self) -> &str {
+        &self.comment
+    }
+
+    // untrusted comment line, then base64 of
+    // "Ed", "BK", rounds (4, big-endian), salt (16), checksum SHA-512(secret key)[..8] (8),
+    // key number (8), secret key seed || public key xored with bcrypt_pbkdf(password, salt, rounds) (64).
+    // Without a password, or with an empty one, rounds is 0 and the secret key is not xored,
+    // as with signify -n.
+    pub fn to_secret_key<R: Rng + ?Sized>(&self, password: Option<&str>, rng: &mut R) -> Secret {
+        let password = password.filter(|x| !x.is_empty());
+        let salt: [u8; 16] = rng.gen();
+        let secret_key = self.key.to_keypair();
+        let rounds = if password.is_some() { Signify::ROUNDS } else { 0 };
+
+        let mut blob = Secret::with_capacity(104);
+        blob.extend(Signify::ED25519);
+        blob.extend(Signify::KDF_BCRYPT);
+        blob.extend(rounds.to_be_bytes());
+        blob.extend(salt);
+        blob.extend(&Sha512::digest(&*secret_key)[..8]);
+        blob.extend(self.key_number);
+        match password {
+            Some(password) => {
+                let stream = bcrypt::bcrypt_pbkdf(password.as_bytes(), &salt, rounds, 64);
+                blob.extend(secret_key.iter().zip(stream.iter()).map(|(x, y)| x ^ y).collect::<Vec<u8>>());
+            },
+            None => blob.extend(&secret_key),
+        }
+
+        let mut out = Secret::new();
+        out.extend(format!("untrusted comment: {} secret key\n", self.comment).as_bytes());
+        out.extend(pem::base64(&blob));
+        out.push(b'\n');
+
+        out
+    }
+
+    // None when the key is encrypted and the password is missing or wrong
+    pub fn from_secret_key(data: &[u8], password: Option<&str>) -> Option<Self> {
+        let (comment, blob) = read_file(data)?;
+        if blob.len() != 104 || blob[..2] != Signify::ED25519 || blob[2..4] != Signify::KDF_BCRYPT {
+            return None;
+        }
+        let rounds = u32::from_be_bytes(blob[4..8].try_into().unwrap());
+        let salt = &blob[8..24];
+        let checksum = &blob[24..32];
+        let secret_key = match rounds {
+            0 => Secret::from(blob[40..].to_vec()),
+            _ => {
+                let password = password.filter(|x| !x.is_empty())?;
+                let stream = bcrypt::bcrypt_pbkdf(password.as_bytes(), salt, rounds, 64);
+                Secret::from(blob[40..].iter().zip(stream.iter()).map(|(x, y)| x ^ y).collect::<Vec<u8>>())
+            },
+        };
+        if Sha512::digest(&*secret_key)[..8] != *checksum {
+            return None;
+        }
+
+        let key = Ed25519::from_private_key(secret_key[..32].to_vec());
+        if key.gen_public_key() != secret_key[32..] {
+            return None;
+        }
+
+        Some(Signify{
+            key,
+            key_number: blob[32..40].try_into().unwrap(),
+            comment: comment.strip_suffix(" secret key").unwrap_or(&comment).to_string(),
+        })
+    }
+
+    // untrusted comment line, then base64 of "Ed" || key number || public key
+    pub fn to_public_key(&self) -> Vec<u8> {
+        let mut blob = Signify::ED25519.to_vec();
+        blob.extend(self.key_number);
+        blob.extend(self.key.gen_public_key());
+
+        let mut out = format!("untrusted comment: {} public key\n", self.comment).into_bytes();
+        out.extend(pem::base64(&blob));
+        out.push(b'\n');
+        out
+    }
+
+    // signify -S, the signature file (.sig), Ed25519 over the message itself
+    // untrusted comment: verify with <public key file>
+    // base64("Ed" || key number || signature)
+    pub fn sign(&self, message: &[u8], public_key_file: &str) -> Vec<u8> {
+        let mut blob = Signify::ED25519.to_vec();
+        blob.extend(self.key_number);
+        blob.extend(self.key.sign(message));
+
+        let mut out = format!("untrusted comment: verify with {}\n", public_key_file).into_bytes();
+        out.extend(pem::base64(&blob));
+        out.push(b'\n');
+        out
+    }
+
+    // signify -V, the key numbers of the public key and the signature must match
+    pub fn verify(public_key: &[u8], message: &[u8], signature: &[u8]) -> bool {
+        let (public_key, signature) = match (read_file(public_key), read_file(signature)) {
+            (Some((_, x)), Some((_, y))) => (x, y),
+            _ => return false,
+        };
+        if public_key.len() != 42 || signature.len() != 74 || public_key[..2] != Signify::ED25519 || signature[..2] != Signify::ED25519 {
+            return false;
+        }
+
+        public_key[2..10] == signature[2..10] && Ed25519::verify(&public_key[10..], message, &signature[10..])
+    }
+}
+
+// untrusted comment line and the base64 line after it
+fn read_file(data: &[u8]) -> Option<(String, Secret)> {
+    let mut lines = std::str::from_utf8(data).ok()?.lines();
+    let comment = lines.next()?.strip_prefix("untrusted comment: ")?.to_string();
+    let blob = pem::base64_decode(lines.next()?.trim().as_bytes())?;
+
+    Some((comment, blob))
+}
@@ -0,0 +1,63 @@
+use crate::der;
+use crate::der::{Tag, DerEncode};
+use crate::pem;
+use crate::pem::PemEncode;
+use crate::secret::Secret;
+
+pub trait SpkiFormat {
+    fn gen_algorithm_identifier(&self) -> Vec<u8>;
+    fn gen_public_key(&self) -> Vec<u8>;
+}
+
+pub struct Spki {
+    algorithm_identifier: Vec<u8>,
+    public_key: Vec<u8>,
+}
+
+impl Spki {
+    pub fn new(key: &dyn SpkiFormat) -> Self {
+        let algorithm_identifier = key.gen_algorithm_identifier();
+        let public_key = key.gen_public_key();
+
+        Spki{
+            algorithm_identifier,
+            public_key,
+        }
+    }
+}
+
+// RFC 5280 section 4.1
+// returns the whole algorithm TLV and the subjectPublicKey bits
+pub fn read_public_key(data: &[u8]) -> Option<(&[u8], &[u8])> {
+    let (data, _) = der::decode(Tag::Sequence, data)?;
+    let (_, rest) = der::decode(Tag::Sequence, data)?;
+    let algorithm_identifier = &data[..data.len() - rest.len()];
+    let (public_key, _) = der::decode(Tag::BitString, rest)?;
+    // keys are whole bytes, no unused bits
+    if *public_key.first()? != 0 {
+        return None;
+    }
+
+    Some((algorithm_identifier, &public_key[1..]))
+}
+
+// RFC 5280 section 4.1
+// SubjectPublicKeyInfo ::= SEQUENCE {
+//   algorithm AlgorithmIdentifier,
+//   subjectPublicKey BIT STRING
+// }
+impl DerEncode for Spki {
+    fn to_der(&self) -> Secret {
+        let mut value = Secret::new();
+        value.extend(&self.algorithm_identifier);
+        value.extend(der::encode(Tag::BitString, der::to_bit_string(&self.public_key)));
+
+        der::encode_secret(Tag::Sequence, &value)
+    }
+}
+
+impl PemEncode for Spki {
+    fn to_pem(&self) -> Vec<u8> {
+        pem::encapsulate(b"PUBLIC KEY", &self.to_der(), 64)
+    }
+}
@@ -0,0 +1,315 @@
+use rand::prelude::*;
+use crate::ssh;
+use crate::ssh::SshFormat;
+use crate::key::PublicKey;
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum CertType {
+    User,
+    Host,
+}
+
+// PROTOCOL.certkeys
+// an unsigned certificate, sign makes the blob
+pub struct SshCertificate {
+    public_key: Vec<u8>,
+    serial: u64,
+    cert_type: CertType,
+    key_id: String,
+    principals: Vec<String>,
+    valid_after: u64,
+    valid_before: u64,
+    critical_options: Vec<(String, Option<String>)>,
+    extensions: Vec<(String, Option<String>)>,
+}
+
+impl SshCertificate {
+    // extensions ssh-keygen -s gives a user certificate unless told otherwise
+    const DEFAULT_EXTENSIONS: [&'static str; 5] = [
+        "permit-X11-forwarding",
+        "permit-agent-forwarding",
+        "permit-port-forwarding",
+        "permit-pty",
+        "permit-user-rc",
+    ];
+
+    // public_key is the "ssh-ed25519" or "ssh-rsa" blob of the key being certified.
+    // Valid always, for any principal, with the default extensions of the type.
+    pub fn new(public_key: &[u8], cert_type: CertType, key_id: &str) -> Self {
+        assert!(PublicKey::from_ssh(public_key).is_some(), "only ssh-ed25519 and ssh-rsa keys can be certified");
+        let extensions = match cert_type {
+            CertType::User => SshCertificate::DEFAULT_EXTENSIONS.iter().map(|x| (x.to_string(), None)).collect(),
+            CertType::Host => Vec::new(),
+        };
+
+        SshCertificate{
+            public_key: public_key.to_vec(),
+            serial: 0,
+            cert_type,
+            key_id: key_id.to_string(),
+            principals: Vec::new(),
+            valid_after: 0,
+            valid_before: u64::MAX,
+            critical_options: Vec::new(),
+            extensions,
+        }
+    }
+
+    // reads a signed certificate blob back, with the blob of the CA key that signed it.
+    // The signature is not checked.
+    pub fn from_ssh(mut data: &[u8]) -> Option<(Self, Vec<u8>)> {
+        let key_type = match ssh::read_string(&mut data)? {
+            b"ssh-ed25519-cert-v01@openssh.com" => &b"ssh-ed25519"[..],
+            b"ssh-rsa-cert-v01@openssh.com" => &b"ssh-rsa"[..],
+            _ => return None,
+        };
+        ssh::read_string(&mut data)?;
+        let mut public_key = ssh::to_string(key_type);
+        public_key.extend(ssh::to_string(ssh::read_string(&mut data)?));
+        if key_type == b"ssh-rsa" {
+            public_key.extend(ssh::to_string(ssh::read_string(&mut data)?));
+        }
+        let serial = ssh::read_u64(&mut data)?;
+        let cert_type = match ssh::read_u32(&mut data)? {
+            1 => CertType::User,
+            2 => CertType::Host,
+            _ => return None,
+        };
+        let key_id = String::from_utf8(ssh::read_string(&mut data)?.to_vec()).ok()?;
+        let mut principals_data = ssh::read_string(&mut data)?;
+        let mut principals = Vec::new();
+        while !principals_data.is_empty() {
+            principals.push(String::from_utf8(ssh::read_string(&mut principals_data)?.to_vec()).ok()?);
+        }
+        let valid_after = ssh::read_u64(&mut data)?;
+        let valid_before = ssh::read_u64(&mut data)?;
+        let critical_options = read_options(ssh::read_string(&mut data)?)?;
+        let extensions = read_options(ssh::read_string(&mut data)?)?;
+        ssh::read_string(&mut data)?;
+        let signature_key = ssh::read_string(&mut data)?;
+        ssh::read_string(&mut data)?;
+        if !data.is_empty() || PublicKey::from_ssh(&public_key).is_none() {
+            return None;
+        }
+
+        Some((SshCertificate{
+            public_key,
+            serial,
+            cert_type,
+            key_id,
+            principals,
+            valid_after,
+            valid_before,
+            critical_options,
+            extensions,
+        }, signature_key.to_vec()))
+    }
+
+    // blob of the certified key
+    pub fn public_key(&self) -> &[u8] {
+        &self.public_key
+    }
+
+    pub fn serial(&self) -> u64 {
+        self.serial
+    }
+
+    pub fn key_id(&self) -> &str {
+        &self.key_id
+    }
+
+    pub fn with_serial(mut self, serial: u64) -> Self {
+        self.serial = serial;
+        self
+    }
+
+    // user names for a user certificate, host names for a host certificate
+    pub fn with_principals(mut self, principals: Vec<String>) -> Self {
+        self.principals = principals;
+        self
+    }
+
+    // seconds since the epoch, valid_after <= t < valid_before
+    pub fn with_validity(mut self, valid_after: u64, valid_before: u64) -> Self {
+        assert!(valid_after < valid_before, "empty validity interval");
+        self.valid_after = valid_after;
+        self.valid_before = valid_before;
+        self
+    }
+
+    // force-command, source-address or verify-required, user certificates only.
+    // An option given again takes the new value.
+    pub fn with_critical_option(mut self, name: &str, value: Option<&str>) -> Self {
+        assert!(self.cert_type == CertType::User, "host certificates have no critical options");
+        set_option(&mut self.critical_options, name, value);
+        self
+    }
+
+    // a flag extension when value is None; an extension given again, such as one of
+    // the defaults, takes the new value
+    pub fn with_extension(mut self, name: &str, value: Option<&str>) -> Self {
+        set_option(&mut self.extensions, name, value);
+        self
+    }
+
+    // drops the extensions given so far, the defaults included
+    pub fn without_extensions(mut self) -> Self {
+        self.extensions.clear();
+        self
+    }
+
+    pub fn sign(&self, ca: &dyn SshFormat) -> Vec<u8> {
+        let mut nonce = [0u8; 32];
+        rand::thread_rng().fill_bytes(&mut nonce);
+
+        self.sign_with_nonce(ca, &nonce)
+    }
+
+    // string    "ssh-ed25519-cert-v01@openssh.com" or "ssh-rsa-cert-v01@openssh.com"
+    // string    nonce
+    // ...       public key fields (string pk, or mpint e, mpint n)
+    // uint64    serial
+    // uint32    type
+    // string    key id
+    // string    valid principals
+    // uint64    valid after
+    // uint64    valid before
+    // string    critical options
+    // string    extensions
+    // string    reserved
+    // string    signature key
+    // string    signature
+    pub fn sign_with_nonce(&self, ca: &dyn SshFormat, nonce: &[u8]) -> Vec<u8> {
+        let mut key_fields = &self.public_key[..];
+        let key_type = ssh::read_string(&mut key_fields).unwrap();
+        let mut cert_type_name = key_type.to_vec();
+        cert_type_name.extend(b"-cert-v01@openssh.com");
+
+        let mut principals = Vec::new();
+        for principal in self.principals.iter() {
+            principals.extend(ssh::to_string(principal.as_bytes()));
+        }
+
+        let mut out = Vec::new();
+        out.extend(ssh::to_string(&cert_type_name));
+        out.extend(ssh::to_string(nonce));
+        out.extend(key_fields);
+        out.extend(self.serial.to_be_bytes());
+        out.extend(match self.cert_type {
+            CertType::User => 1u32,
+            CertType::Host => 2u32,
+        }.to_be_bytes());
+        out.extend(ssh::to_string(self.key_id.as_bytes()));
+        out.extend(ssh::to_string(&principals));
+        out.extend(self.valid_after.to_be_bytes());
+        out.extend(self.valid_before.to_be_bytes());
+        out.extend(ssh::to_string(&options(&self.critical_options)));
+        out.extend(ssh::to_string(&options(&self.extensions)));
+        out.extend(ssh::to_string(b""));
+        out.extend(ssh::to_string(&ca.gen_public_key()));
+        let signature = ca.gen_signature(&out).expect("the CA key can not make ssh signatures");
+        out.extend(ssh::to_string(&signature));
+
+        out
+    }
+}
+
+// adds name, or sets its value when it is there already
+fn set_option(options: &mut Vec<(String, Option<String>)>, name: &str, value: Option<&str>) {
+    let value = value.map(|x| x.to_string());
+    match options.iter_mut().find(|(x, _)| x == name) {
+        Some(option) => option.1 = value,
+        None => options.push((name.to_string(), value)),
+    }
+}
+
+// string name, string data, sorted by name.
+// data holds a string with the value, or nothing for a flag.
+fn options(options: &[(String, Option<String>)]) -> Vec<u8> {
+    let mut sorted: Vec<_> = options.iter().collect();
+    sorted.sort_by(|(x, _), (y, _)| x.cmp(y));
+
+    let mut out = Vec::new();
+    for (name, value) in sorted {
+        out.extend(ssh::to_string(name.as_bytes()));
+        match value {
+            Some(value) => out.extend(ssh::to_string(&ssh::to_string(value.as_bytes()))),
+            None => out.extend(ssh::to_string(b"")),
+        }
+    }
+
+    out
+}
+
+// inverse of options
+fn read_options(mut data: &[u8]) -> Option<Vec<(String, Option<String>)>> {
+    let mut out = Vec::new();
+    while !data.is_empty() {
+        let name = String::from_utf8(ssh::read_string(&mut data)?.to_vec()).ok()?;
+        let mut value = ssh::read_string(&mut data)?;
+        let value = if value.is_empty() {
+            None
+        } else {
+            Some(String::from_utf8(ssh::read_string(&mut value)?.to_vec()).ok()?)
+        };
+        out.push((name, value));
+    }
+
+    Some(out)
+}
+
+// ssh-keygen -V: "start:end" or "+end" starting now.
+// start may be "always", end may be "forever", either may be YYYYMMDD[HHMM[SS]][Z]
+// or a time relative to now like "-1d" or "+52w1d" (units s, m, h, d, w).
+// Returns (valid after, valid before).
+pub fn parse_validity(data: &str, now: u64) -> Option<(u64, u64)> {
+    let (start, end) = match data.split_once(':') {
+        Some((start, end)) => (start, end),
+        None if data.starts_with('+') => return Some((now, relative_time(data, now)?)),
+        None => return None,
+    };
+    let start = match start {
+        "always" => 0,
+        x if x.starts_with(['+', '-']) => relative_time(x, now)?,
+        x => ssh::parse_time(x)?,
+    };
+    let end = match end {
+        "forever" => u64::MAX,
+        x if x.starts_with(['+', '-']) => relative_time(x, now)?,
+        x => ssh::parse_time(x)?,
+    };
+
+    if start < end { Some((start, end)) } else { None }
+}
+
+fn relative_time(data: &str, now: u64) -> Option<u64> {
+    let (sign, data) = data.split_at(1);
+    if data.is_empty() {
+        return None;
+    }
+    let mut seconds: u64 = 0;
+    let mut number: Option<u64> = None;
+    for c in data.chars() {
+        let unit = match c {
+            '0'..='9' => {
+                number = Some(number.unwrap_or(0).checked_mul(10)?.checked_add(c as u64 - '0' as u64)?);
+                continue;
+            },
+            's' | 'S' => 1,
+            'm' | 'M' => 60,
+            'h' | 'H' => 3600,
+            'd' | 'D' => 86400,
+            'w' | 'W' => 604800,
+            _ => return None,
+        };
+        seconds = seconds.checked_add(number.take()?.checked_mul(unit)?)?;
+    }
+    if let Some(number) = number {
+        seconds = seconds.checked_add(number)?;
+    }
+
+    match sign {
+        "+" => now.checked_add(seconds),
+        _ => now.checked_sub(seconds),
+    }
+}
@@ -0,0 +1,279 @@
+use crate::pem;
+use crate::pem::PemEncode;
+use crate::ssh;
+use crate::ssh::SshFormat;
+use crate::hash::Hash;
+use crate::key::PublicKey;
+
+// PROTOCOL.sshsig
+pub struct SshSig {
+    public_key: Vec<u8>,
+    namespace: String,
+    hash: Hash,
+    signature: Vec<u8>,
+}
+
+impl SshSig {
+    const MAGIC_PREAMBLE: [u8; 6] = *b"SSHSIG";
+    const SIG_VERSION: [u8; 4] = [0,0,0,1];
+
+    // the namespace keeps a signature made for one purpose (e.g. "git" or "file")
+    // from being accepted for another.
+    // None when the namespace is empty, the hash is not sha256 or sha512
+    // or the key can not make ssh signatures, e.g. an rsa key restricted to PSS.
+    pub fn new(key: &dyn SshFormat, namespace: &str, hash: Hash, message: &[u8]) -> Option<Self> {
+        if namespace.is_empty() || !matches!(hash, Hash::Sha256 | Hash::Sha512) {
+            return None;
+        }
+        let data = SshSig::signed_data(namespace, hash, message);
+
+        Some(SshSig{
+            public_key: key.gen_public_key(),
+            namespace: namespace.to_string(),
+            hash,
+            signature: key.gen_signature(&data)?,
+        })
+    }
+
+    // byte[6]   MAGIC_PREAMBLE
+    // uint32    SIG_VERSION
+    // string    publickey
+    // string    namespace
+    // string    reserved
+    // string    hash_algorithm
+    // string    signature
+    pub fn from_pem(data: &[u8]) -> Option<Self> {
+        let (label, body) = pem::decapsulate(data)?;
+        if label != b"SSH SIGNATURE" {
+            return None;
+        }
+        let mut data = body.strip_prefix(&SshSig::MAGIC_PREAMBLE[..])?.strip_prefix(&SshSig::SIG_VERSION[..])?;
+        let public_key = ssh::read_string(&mut data)?;
+        let namespace = ssh::read_string(&mut data)?;
+        ssh::read_string(&mut data)?;
+        let hash = match ssh::read_string(&mut data)? {
+            b"sha256" => Hash::Sha256,
+            b"sha512" => Hash::Sha512,
+            _ => return None,
+        };
+        let signature = ssh::read_string(&mut data)?;
+        if !data.is_empty() {
+            return None;
+        }
+
+        Some(SshSig{
+            public_key: public_key.to_vec(),
+            namespace: String::from_utf8(namespace.to_vec()).ok()?,
+            hash,
+            signature: signature.to_vec(),
+        })
+    }
+
+    // public key blob of the signer
+    pub fn public_key(&self) -> &[u8] {
+        &self.public_key
+    }
+
+    // ssh-keygen -l style fingerprint of the signer
+    pub fn fingerprint(&self) -> String {
+        ssh::fingerprint(&self.public_key)
+    }
+
+    pub fn namespace(&self) -> &str {
+        &self.namespace
+    }
+
+    pub fn hash(&self) -> Hash {
+        self.hash
+    }
+
+    // checks the signature with the key it carries;
+    // whether that key may sign for anyone is up to AllowedSigners
+    pub fn verify(&self, namespace: &str, message: &[u8]) -> bool {
+        if self.namespace != namespace {
+            return false;
+        }
+        let public_key = match PublicKey::from_ssh(&self.public_key) {
+            Some(public_key) => public_key,
+            None => return false,
+        };
+
+        public_key.verify_ssh(&SshSig::signed_data(namespace, self.hash, message), &self.signature)
+    }
+
+    // byte[6]   MAGIC_PREAMBLE
+    // string    namespace
+    // string    reserved
+    // string    hash_algorithm
+    // string    H(message)
+    fn signed_data(namespace: &str, hash: Hash, message: &[u8]) -> Vec<u8> {
+        let mut out = Vec::new();
+        out.extend(SshSig::MAGIC_PREAMBLE);
+        out.extend(ssh::to_string(namespace.as_bytes()));
+        out.extend(ssh::to_string(b""));
+        out.extend(ssh::to_string(hash.name().as_bytes()));
+        out.extend(ssh::to_string(&hash.digest(message)));
+
+        out
+    }
+}
+
+impl PemEncode for SshSig {
+    fn to_pem(&self) -> Vec<u8> {
+        let mut dump = Vec::new();
+        dump.extend(SshSig::MAGIC_PREAMBLE);
+        dump.extend(SshSig::SIG_VERSION);
+        dump.extend(ssh::to_string(&self.public_key));
+        dump.extend(ssh::to_string(self.namespace.as_bytes()));
+        dump.extend(ssh::to_string(b""));
+        dump.extend(ssh::to_string(self.hash.name().as_bytes()));
+        dump.extend(ssh::to_string(&self.signature));
+
+        pem::encapsulate(b"SSH SIGNATURE", &dump, 70)
+    }
+}
+
+// ssh-keygen(1) ALLOWED SIGNERS
+//
+// principals [options] keytype base64-key [comment]
+//
+// principals and the namespaces option are comma-separated patterns.
+// valid-after and valid-before take YYYYMMDD[HHMM[SS]], read as UTC
+// with or without a trailing Z.
+pub struct AllowedSigners {
+    signers: Vec<AllowedSigner>,
+}
+
+struct AllowedSigner {
+    principals: String,
+    cert_authority: bool,
+    namespaces: Option<String>,
+    valid_after: Option<u64>,
+    valid_before: Option<u64>,
+    public_key: Vec<u8>,
+}
+
+impl AllowedSigners {
+    // None when a line is malformed or has an unknown option
+    pub fn parse(data: &str) -> Option<Self> {
+        let mut signers = Vec::new();
+        for line in data.lines() {
+            let line = line.trim();
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+            signers.push(AllowedSigner::parse(line)?);
+        }
+
+        Some(AllowedSigners{
+            signers,
+        })
+    }
+
+    // true when a line names the identity and the key, and allows namespace at time now
+    // (seconds since the epoch). cert-authority lines are only for certificates
+    // and never match a plain key.
+    pub fn is_allowed(&self, identity: &str, namespace: &str, public_key: &[u8], now: u64) -> bool {
+        self.signers.iter().any(|signer| {
+            !signer.cert_authority
+                && signer.public_key == public_key
+                && match_pattern_list(identity, &signer.principals)
+                && signer.namespaces.as_ref().is_none_or(|x| match_pattern_list(namespace, x))
+                && signer.valid_after.is_none_or(|x| now >= x)
+                && signer.valid_before.is_none_or(|x| now <= x)
+        })
+    }
+}
+
+impl AllowedSigner {
+    fn parse(line: &str) -> Option<Self> {
+        let fields = split_unquoted(line, |c| c == ' ' || c == '\t');
+        let principals = unquote(fields.first()?);
+        // the options field is there unless the second field is the key type of the third
+        let (options, key) = match (fields.get(1), fields.get(2), fields.get(3)) {
+            (Some(key_type), Some(key), _) if is_key(key_type, key) => ("", key),
+            (Some(options), Some(key_type), Some(key)) if is_key(key_type, key) => (*options, key),
+            _ => return None,
+        };
+
+        let mut signer = AllowedSigner{
+            principals: principals.to_string(),
+            cert_authority: false,
+            namespaces: None,
+            valid_after: None,
+            valid_before: None,
+            public_key: pem::base64_decode(key.as_bytes())?.to_vec(),
+        };
+        for option in split_unquoted(options, |c| c == ',') {
+            let (name, value) = match option.split_once('=') {
+                Some((name, value)) => (name, Some(unquote(value))),
+                None => (option, None),
+            };
+            match (name.to_ascii_lowercase().as_str(), value) {
+                ("cert-authority", None) => signer.cert_authority = true,
+                ("namespaces", Some(value)) => signer.namespaces = Some(value.to_string()),
+                ("valid-after", Some(value)) => signer.valid_after = Some(ssh::parse_time(value)?),
+                ("valid-before", Some(value)) => signer.valid_before = Some(ssh::parse_time(value)?),
+                _ => return None,
+            }
+        }
+
+        Some(signer)
+    }
+}
+
+// whether key is the base64 of a public key blob of type key_type
+fn is_key(key_type: &str, key: &str) -> bool {
+    match pem::base64_decode(key.as_bytes()) {
+        Some(blob) => ssh::read_string(&mut &blob[..]) == Some(key_type.as_bytes()),
+        None => false,
+    }
+}
+
+// splits at separators outside double quotes, dropping empty pieces
+fn split_unquoted(data: &str, separator: impl Fn(char) -> bool) -> Vec<&str> {
+    let mut out = Vec::new();
+    let mut quoted = false;
+    let mut start = 0;
+    for (i, c) in data.char_indices() {
+        if c == '"' {
+            quoted = !quoted;
+        } else if !quoted && separator(c) {
+            out.push(&data[start..i]);
+            start = i + c.len_utf8();
+        }
+    }
+    out.push(&data[start..]);
+    out.retain(|x| !x.is_empty());
+
+    out
+}
+
+fn unquote(data: &str) -> &str {
+    data.strip_prefix('"').and_then(|x| x.strip_suffix('"')).unwrap_or(data)
+}
+
+// match.c match_pattern_list: a comma-separated list of patterns,
+// a pattern starting with ! rejects whatever it matches
+fn match_pattern_list(s: &str, list: &str) -> bool {
+    let mut matched = false;
+    for pattern in list.split(',') {
+        match pattern.strip_prefix('!') {
+            Some(pattern) if match_pattern(s.as_bytes(), pattern.as_bytes()) => return false,
+            Some(_) => {},
+            None => matched |= match_pattern(s.as_bytes(), pattern.as_bytes()),
+        }
+    }
+
+    matched
+}
+
+// * matches any run of characters and ? any one character
+fn match_pattern(s: &[u8], pattern: &[u8]) -> bool {
+    match pattern.split_first() {
+        None => s.is_empty(),
+        Some((b'*', rest)) => (0..=s.len()).any(|i| match_pattern(&s[i..], rest)),
+        Some((b'?', rest)) => !s.is_empty() && match_pattern(&s[1..], rest),
+        Some((c, rest)) => s.first() == Some(c) && match_pattern(&s[1..], rest),
+    }
+}
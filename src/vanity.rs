@@ -0,0 +1,187 @@
+use std::sync::Mutex;
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+use std::thread;
+use std::time::{Duration, Instant};
+use rand::Rng;
+use regex::Regex;
+use crate::ssh;
+use crate::pem;
+use crate::ed25519::Ed25519;
+
+// keys a thread makes before it looks for a match, their public keys sharing one inversion
+const BATCH: usize = 256;
+
+// what a pattern is matched against
+#[derive(Clone, Copy)]
+pub enum VanityTarget {
+    // the base64 of the ssh-ed25519 public key blob as in a .pub line, without the
+    // 25 characters "AAAAC3NzaC1lZDI1NTE5AAAAI" every key starts with: 43 characters,
+    // the first one of A to P
+    Key,
+    // the SHA256: fingerprint without "SHA256:": 43 characters, the last one
+    // of A E I M Q U Y c g k o s w 0 4 8
+    Fingerprint,
+}
+
+enum Kind {
+    Prefix(String),
+    Suffix(String),
+    Regex(Regex),
+}
+
+pub struct Pattern {
+    kind: Kind,
+    target: VanityTarget,
+}
+
+impl Pattern {
+    // the base64 text of every ssh-ed25519 key is this long, and so is its fingerprint
+    const LENGTH: usize = 43;
+    const KEY_HEADER: usize = 25;
+
+    // None when no key can match, a character that is not base64 or
+    // not possible at its place, or a pattern longer than the text
+    pub fn prefix(prefix: &str, target: VanityTarget) -> Option<Self> {
+        if !Pattern::possible(prefix, 0, target) {
+            return None;
+        }
+
+        Some(Pattern{
+            kind: Kind::Prefix(prefix.to_string()),
+            target,
+        })
+    }
+
+    pub fn suffix(suffix: &str, target: VanityTarget) -> Option<Self> {
+        let start = Pattern::LENGTH.checked_sub(suffix.len())?;
+        if !Pattern::possible(suffix, start, target) {
+            return None;
+        }
+
+        Some(Pattern{
+            kind: Kind::Suffix(suffix.to_string()),
+            target,
+        })
+    }
+
+    // anywhere in the text unless anchored with ^ or $; None when it does not compile
+    pub fn regex(regex: &str, target: VanityTarget) -> Option<Self> {
+        Some(Pattern{
+            kind: Kind::Regex(Regex::new(regex).ok()?),
+            target,
+        })
+    }
+
+    // the text the pattern is matched against for an Ed25519 public key
+    pub fn text(&self, public_key: &[u8]) -> String {
+        let mut blob = ssh::to_string(b"ssh-ed25519");
+        blob.extend(ssh::to_string(public_key));
+
+        match self.target {
+            VanityTarget::Key => String::from_utf8(pem::base64(&blob)[Pattern::KEY_HEADER..].to_vec()).unwrap(),
+            VanityTarget::Fingerprint => ssh::fingerprint(&blob)["SHA256:".len()..].to_string(),
+        }
+    }
+
+    pub fn matches(&self, public_key: &[u8]) -> bool {
+        let text = self.text(public_key);
+
+        match self.kind {
+            Kind::Prefix(ref x) => text.starts_with(x.as_str()),
+            Kind::Suffix(ref x) => text.ends_with(x.as_str()),
+            Kind::Regex(ref x) => x.is_match(&text),
+        }
+    }
+
+    // the chance that a random key matches, exact for a prefix or a suffix.
+    // None for a regex, whose chance only sample can tell.
+    pub fn probability(&self) -> Option<f64> {
+        let (pattern, start) = match self.kind {
+            Kind::Prefix(ref x) => (x, 0),
+            Kind::Suffix(ref x) => (x, Pattern::LENGTH - x.len()),
+            Kind::Regex(_) => return None,
+        };
+
+        Some((start..start + pattern.len()).map(|i| 1.0 / Pattern::choices(i, self.target) as f64).product())
+    }
+
+    // the share of that many random texts the pattern matches. No Ed25519 keys are made:
+    // random bytes encode as random public keys do, and a fingerprint is a random hash.
+    pub fn sample<R: Rng + ?Sized>(&self, samples: u64, rng: &mut R) -> f64 {
+        let hits = (0..samples).filter(|_| self.matches(&rng.gen::<[u8; 32]>())).count();
+
+        hits as f64 / samples as f64
+    }
+
+    // the number of characters possible at a place of the text, see possible
+    fn choices(i: usize, target: VanityTarget) -> usize {
+        match (target, i) {
+            // 4 bits of the first byte of the public key after the 0x20 of its length
+            (VanityTarget::Key, 0) => 16,
+            // the last 4 bits of the digest, padded with two zero bits
+            (VanityTarget::Fingerprint, 42) => 16,
+            _ => 64,
+        }
+    }
+
+    fn possible(pattern: &str, start: usize, target: VanityTarget) -> bool {
+        const ALPHABET: &[u8; 64] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789+/";
+
+        start + pattern.len() <= Pattern::LENGTH && pattern.bytes().enumerate().all(|(i, x)| {
+            let value = match ALPHABET.iter().position(|&y| y == x) {
+                Some(value) => value,
+                None => return false,
+            };
+            match (target, start + i) {
+                (VanityTarget::Key, 0) => value < 16,
+                (VanityTarget::Fingerprint, 42) => value % 4 == 0,
+                _ => true,
+            }
+        })
+    }
+}
+
+// generates Ed25519 keys on that many threads until the public key of one matches,
+// returns it and the number of keys tried. progress is called about once a second
+// on the calling thread with the keys tried so far.
+pub fn search<F, P>(threads: usize, matches: F, mut progress: P) -> (Ed25519, u64)
+where
+    F: Fn(&[u8]) -> bool + Sync,
+    P: FnMut(u64),
+{
+    assert!(threads > 0, "a search needs at least one thread");
+    let found = AtomicBool::new(false);
+    let attempts = AtomicU64::new(0);
+    let result = Mutex::new(None);
+    let caller = thread::current();
+
+    thread::scope(|s| {
+        for _ in 0..threads {
+            s.spawn(|| {
+                while !found.load(Ordering::Relaxed) {
+                    let keys: Vec<Ed25519> = (0..BATCH).map(|_| Ed25519::new()).collect();
+                    let public_keys = Ed25519::gen_public_keys(&keys);
+                    attempts.fetch_add(BATCH as u64, Ordering::Relaxed);
+
+                    if let Some(i) = public_keys.iter().position(|x| matches(x)) {
+                        found.store(true, Ordering::Relaxed);
+                        // the first key found wins when threads find one at once
+                        result.lock().unwrap().get_or_insert(keys.into_iter().nth(i).unwrap());
+                        caller.unpark();
+                    }
+                }
+            });
+        }
+
+        let mut next = Instant::now() + Duration::from_secs(1);
+        while !found.load(Ordering::Relaxed) {
+            thread::park_timeout(next.saturating_duration_since(Instant::now()));
+            if Instant::now() >= next {
+                progress(attempts.load(Ordering::Relaxed));
+                next += Duration::from_secs(1);
+            }
+        }
+    });
+
+    (result.into_inner().unwrap().unwrap(), attempts.into_inner())
+}
@@ -0,0 +1,71 @@
+use rand::Rng;
+use crate::pem;
+use crate::x25519::X25519;
+use crate::secret::Secret;
+
+// WireGuard keys as wg(8) writes them, base64 of 32 bytes on a line of its own:
+// the private key of wg genkey, the public key of wg pubkey and the preshared key of wg genpsk
+pub struct WireGuard {
+    private_key: Secret,
+    public_key: Vec<u8>,
+    preshared_key: Secret,
+}
+
+impl WireGuard {
+    // the private key is written clamped, as wg genkey does;
+    // rng makes the preshared key
+    pub fn new<R: Rng + ?Sized>(key: &X25519, rng: &mut R) -> Self {
+        let mut preshared_key = Secret::with_capacity(32);
+        for _ in 0..32 {
+            preshared_key.push(rng.gen());
+        }
+
+        WireGuard{
+            private_key: key.clamped_private_key(),
+            public_key: key.gen_public_key(),
+            preshared_key,
+        }
+    }
+
+    pub fn private_key(&self) -> Secret {
+        line(&self.private_key)
+    }
+
+    pub fn public_key(&self) -> Vec<u8> {
+        line(&self.public_key).to_vec()
+    }
+
+    pub fn preshared_key(&self) -> Secret {
+        line(&self.preshared_key)
+    }
+
+    // wg-quick(8) configuration for this end of a tunnel.
+    // The addresses, the port and the peer are placeholders to edit;
+    // the public key goes in the [Peer] section at the other end, as does the preshared key.
+    pub fn to_config(&self) -> Secret {
+        let private_key = Secret::from(pem::base64(&self.private_key));
+        let preshared_key = Secret::from(pem::base64(&self.preshared_key));
+
+        let mut out = Secret::new();
+        out.extend(b"[Interface]\n");
+        out.extend(format!("# PublicKey = {}\n", String::from_utf8(pem::base64(&self.public_key)).unwrap()).as_bytes());
+        out.extend(b"PrivateKey = ");
+        out.extend(&private_key);
+        out.extend(b"\nAddress = 10.0.0.1/24\n");
+        out.extend(b"ListenPort = 51820\n");
+        out.extend(b"\n[Peer]\n");
+        out.extend(b"PublicKey = <public key of the peer>\n");
+        out.extend(b"PresharedKey = ");
+        out.extend(&preshared_key);
+        out.extend(b"\nAllowedIPs = 10.0.0.2/32\n");
+        out.extend(b"Endpoint = vpn.example.com:51820\n");
+
+        out
+    }
+}
+
+fn line(key: &[u8]) -> Secret {
+    let mut out = Secret::from(pem::base64(key));
+    out.push(b'\n');
+    out
+}
@@ -0,0 +1,156 @@
+use rand::prelude::*;
+use crate::curve25519::FieldElement;
+use crate::openpgp;
+use crate::openpgp::OpenPgpFormat;
+use crate::secret::Secret;
+use zeroize::Zeroizing;
+
+// RFC 7748 section 5, a 32 byte private scalar; clamped when it is used
+pub struct X25519 {
+    private_key: Secret,
+}
+
+impl X25519 {
+    // u = 9
+    const BASE_POINT: [u8; 32] = [9, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0];
+
+    // (486662 - 2) / 4, little-endian
+    const A24: [u8; 32] = [0x41, 0xdb, 0x01, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0];
+
+    // RFC 9580 section 9.2, Curve25519Legacy 1.3.6.1.4.1.3029.1.5.1 without the DER tag and length
+    const PGP_OID: [u8; 10] = [0x2b,0x06,0x01,0x04,0x01,0x97,0x55,0x01,0x05,0x01];
+    // RFC 9580 section 5.5.5.6, KDF parameters: reserved 1, SHA2-256, AES-128 as GnuPG has them
+    const PGP_KDF: [u8; 4] = [0x03,0x01,0x08,0x07];
+
+    pub fn new() -> Self {
+        let mut private_key = Secret::with_capacity(32);
+        let mut rng = rand::thread_rng();
+        for _ in 0..32 {
+            private_key.push(rng.gen());
+        }
+        X25519{
+            private_key
+        }
+    }
+
+    pub fn from_private_key(private_key: Vec<u8>) -> Self {
+        assert_eq!(private_key.len(), 32, "an x25519 private key is 32 bytes");
+        let private_key = Secret::from(private_key);
+        X25519{
+            private_key
+        }
+    }
+
+    // RFC 7748 section 5, decodeScalar25519 of the private key
+    pub fn clamped_private_key(&self) -> Secret {
+        let mut scalar = Zeroizing::new([0u8; 32]);
+        scalar.copy_from_slice(&self.private_key);
+        clamp(&mut scalar);
+
+        Secret::from(scalar.to_vec())
+    }
+
+    // X25519(k, 9)
+    pub fn gen_public_key(&self) -> Vec<u8> {
+        let mut scalar = Zeroizing::new([0u8; 32]);
+        scalar.copy_from_slice(&self.private_key);
+
+        x25519(&scalar, &X25519::BASE_POINT).to_vec()
+    }
+
+    // RFC 7748 section 6.1, None when the shared secret is all zero
+    // (a public key of small order)
+    pub fn diffie_hellman(&self, public_key: &[u8]) -> Option<Secret> {
+        let u: [u8; 32] = public_key.try_into().ok()?;
+        let mut scalar = Zeroizing::new([0u8; 32]);
+        scalar.copy_from_slice(&self.private_key);
+        let shared = Secret::from(x25519(&scalar, &u).to_vec());
+        if shared.iter().fold(0, |acc, x| acc | x) == 0 {
+            return None;
+        }
+
+        Some(shared)
+    }
+}
+
+impl Default for X25519 {
+    fn default() -> Self {
+        X25519::new()
+    }
+}
+
+// RFC 9580 section 5.5.5.6, ECDH over Curve25519Legacy
+impl OpenPgpFormat for X25519 {
+    fn pgp_algorithm(&self) -> u8 {
+        openpgp::ECDH
+    }
+
+    // one-octet OID length, OID, MPI of 0x40 || public key, KDF parameters
+    fn gen_public_fields(&self) -> Vec<u8> {
+        let mut point = vec![0x40];
+        point.extend(self.gen_public_key());
+
+        let mut out = vec![X25519::PGP_OID.len() as u8];
+        out.extend(X25519::PGP_OID);
+        out.extend(openpgp::mpi(&point));
+        out.extend(X25519::PGP_KDF);
+
+        out
+    }
+
+    // MPI of the clamped scalar in reverse octet order, big-endian like other MPIs
+    fn gen_secret_fields(&self) -> Secret {
+        let mut scalar = self.clamped_private_key().to_vec();
+        scalar.reverse();
+
+        openpgp::mpi_secret(&Secret::from(scalar))
+    }
+}
+
+// RFC 7748 section 5
+// k[0] &= 248, k[31] &= 127, k[31] |= 64
+fn clamp(k: &mut [u8; 32]) {
+    k[0] &= 248;
+    k[31] &= 127;
+    k[31] |= 64;
+}
+
+// RFC 7748 section 5, the Montgomery ladder over bits 254 down to 0 of the clamped scalar.
+// The points are swapped with masks, so the running time does not depend on the scalar.
+pub fn x25519(scalar: &[u8; 32], u: &[u8; 32]) -> [u8; 32] {
+    let mut k = Zeroizing::new(*scalar);
+    clamp(&mut k);
+    let a24 = FieldElement::from_bytes(&X25519::A24);
+
+    let x1 = FieldElement::from_bytes(u);
+    let mut x2 = FieldElement::ONE;
+    let mut z2 = FieldElement::ZERO;
+    let mut x3 = x1;
+    let mut z3 = FieldElement::ONE;
+    let mut swap = 0u8;
+    for t in (0..255).rev() {
+        let bit = (k[t / 8] >> (t % 8)) & 1;
+        swap ^= bit;
+        FieldElement::swap(&mut x2, &mut x3, swap);
+        FieldElement::swap(&mut z2, &mut z3, swap);
+        swap = bit;
+
+        let a = &x2 + &z2;
+        let aa = a.square();
+        let b = &x2 - &z2;
+        let bb = b.square();
+        let e = &aa - &bb;
+        let c = &x3 + &z3;
+        let d = &x3 - &z3;
+        let da = &d * &a;
+        let cb = &c * &b;
+        x3 = (&da + &cb).square();
+        z3 = &x1 * &(&da - &cb).square();
+        x2 = &aa * &bb;
+        z2 = &e * &(&aa + &(&a24 * &e));
+    }
+    FieldElement::swap(&mut x2, &mut x3, swap);
+    FieldElement::swap(&mut z2, &mut z3, swap);
+
+    (&x2 * &z2.invert()).to_bytes()
+}
@@ -0,0 +1,444 @@
+use std::net::IpAddr;
+use rand::prelude::*;
+use crate::der;
+use crate::der::{Tag, DerEncode};
+use crate::pem;
+use crate::pem::PemEncode;
+use crate::secret::Secret;
+use crate::spki::Spki;
+use crate::hash::Hash;
+use crate::key::Key;
+use crate::rsa::{RsaAlgorithm, PssParams};
+use crate::ed25519::Ed25519;
+
+// RFC 5280 section 4.1.2.4, a distinguished name in the order it is written
+pub struct Name {
+    attributes: Vec<(&'static [u8], String)>,
+}
+
+impl Name {
+    // id-at OBJECT IDENTIFIER ::= { joint-iso-ccitt(2) ds(5) 4 }
+    const ATTRIBUTES: [(&'static str, &'static [u8]); 6] = [
+        ("CN", &[0x55,0x04,0x03]),
+        ("C", &[0x55,0x04,0x06]),
+        ("L", &[0x55,0x04,0x07]),
+        ("ST", &[0x55,0x04,0x08]),
+        ("O", &[0x55,0x04,0x0a]),
+        ("OU", &[0x55,0x04,0x0b]),
+    ];
+    const COUNTRY: &'static [u8] = &[0x55,0x04,0x06];
+
+    // OpenSSL -subj form, "/C=JP/O=Example/CN=example.com", where the leading slash may be left out;
+    // a backslash escapes the next character. "" or "/" is the empty name.
+    pub fn parse(data: &str) -> Option<Self> {
+        let data = data.strip_prefix('/').unwrap_or(data);
+        let mut fields = vec![String::new()];
+        let mut chars = data.chars();
+        while let Some(c) = chars.next() {
+            match c {
+                '\\' => fields.last_mut().unwrap().push(chars.next()?),
+                '/' => fields.push(String::new()),
+                c => fields.last_mut().unwrap().push(c),
+            }
+        }
+        if fields.len() == 1 && fields[0].is_empty() {
+            fields.clear();
+        }
+
+        let mut attributes = Vec::new();
+        for field in fields {
+            let (name, value) = field.split_once('=')?;
+            let (_, oid) = Name::ATTRIBUTES.iter().find(|(x, _)| x.eq_ignore_ascii_case(name))?;
+            // countryName is a two letter PrintableString
+            if *oid == Name::COUNTRY && (value.len() != 2 || !value.bytes().all(|c| c.is_ascii_alphabetic())) {
+                return None;
+            }
+            if value.is_empty() {
+                return None;
+            }
+            attributes.push((*oid, value.to_string()));
+        }
+
+        Some(Name{
+            attributes,
+        })
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.attributes.is_empty()
+    }
+
+    // Name ::= SEQUENCE OF RelativeDistinguishedName
+    // RelativeDistinguishedName ::= SET SIZE (1..MAX) OF AttributeTypeAndValue
+    // AttributeTypeAndValue ::= SEQUENCE { type OBJECT IDENTIFIER, value ANY }
+    // one attribute per RDN, UTF8String except countryName (RFC 5280 section 4.1.2.6)
+    pub fn to_der(&self) -> Vec<u8> {
+        let mut value = Vec::new();
+        for (oid, attribute) in self.attributes.iter() {
+            let string_tag = if *oid == Name::COUNTRY { Tag::PrintableString } else { Tag::Utf8String };
+            let mut pair = der::encode(Tag::ObjectIdentifier, oid);
+            pair.extend(der::encode(string_tag, attribute.as_bytes()));
+            value.extend(der::encode(Tag::Set, der::encode(Tag::Sequence, pair)));
+        }
+
+        der::encode(Tag::Sequence, value)
+    }
+}
+
+// RFC 5280 section 4.2.1.6, the GeneralName choices a certificate for a host or a person needs
+pub enum GeneralName {
+    Email(String),
+    Dns(String),
+    Ip(IpAddr),
+}
+
+impl GeneralName {
+    // OpenSSL subjectAltName form, "DNS:example.com", "IP:192.0.2.1" or "email:user@example.com",
+    // the type in any case
+    pub fn parse(data: &str) -> Option<Self> {
+        let (kind, value) = data.split_once(':')?;
+        if value.is_empty() || !value.is_ascii() {
+            return None;
+        }
+        match kind.to_ascii_uppercase().as_str() {
+            "DNS" => Some(GeneralName::Dns(value.to_string())),
+            "IP" => Some(GeneralName::Ip(value.parse().ok()?)),
+            "EMAIL" => Some(GeneralName::Email(value.to_string())),
+            _ => None,
+        }
+    }
+
+    // rfc822Name [1] IA5String, dNSName [2] IA5String, iPAddressName [7] OCTET STRING
+    fn to_der(&self) -> Vec<u8> {
+        match self {
+            GeneralName::Email(x) => der::encode(Tag::ContextPrimitive(1), x.as_bytes()),
+            GeneralName::Dns(x) => der::encode(Tag::ContextPrimitive(2), x.as_bytes()),
+            GeneralName::Ip(IpAddr::V4(x)) => der::encode(Tag::ContextPrimitive(7), x.octets()),
+            GeneralName::Ip(IpAddr::V6(x)) => der::encode(Tag::ContextPrimitive(7), x.octets()),
+        }
+    }
+}
+
+// RFC 5280 section 4.2.1.3, the number of each bit in the KeyUsage BIT STRING
+#[derive(Clone, Copy)]
+pub enum KeyUsage {
+    DigitalSignature = 0,
+    NonRepudiation = 1,
+    KeyEncipherment = 2,
+    DataEncipherment = 3,
+    KeyAgreement = 4,
+    KeyCertSign = 5,
+    CrlSign = 6,
+}
+
+// a certificate not yet signed, sign makes the Certificate
+pub struct TbsCertificate {
+    serial: Vec<u8>,
+    issuer: Option<Name>,
+    not_before: u64,
+    not_after: u64,
+    subject: Name,
+    public_key: Vec<u8>,
+    alt_names: Vec<GeneralName>,
+    basic_constraints: Option<(bool, Option<u32>)>,
+    key_usage: Vec<KeyUsage>,
+}
+
+impl TbsCertificate {
+    // id-ce OBJECT IDENTIFIER ::= { joint-iso-ccitt(2) ds(5) 29 }
+    const KEY_USAGE: [u8; 3] = [0x55,0x1d,0x0f];
+    const BASIC_CONSTRAINTS: [u8; 3] = [0x55,0x1d,0x13];
+    // 99991231235959Z, "no well-defined expiration date" (RFC 5280 section 4.1.2.5)
+    const NO_EXPIRATION: u64 = 253402300799;
+
+    // Issued by the subject itself unless with_issuer says otherwise,
+    // valid from 1970 with no expiration, with a random 127 bit serial
+    // (RFC 5280 section 4.1.2.2 allows 20 octets and no more).
+    pub fn new(subject: Name, public_key: &Spki) -> Self {
+        let mut serial = vec![0u8; 16];
+        rand::thread_rng().fill_bytes(&mut serial);
+        serial[0] = (serial[0] & 0x7f) | 0x40;
+
+        TbsCertificate{
+            serial,
+            issuer: None,
+            not_before: 0,
+            not_after: TbsCertificate::NO_EXPIRATION,
+            subject,
+            public_key: public_key.to_der().to_vec(),
+            alt_names: Vec::new(),
+            basic_constraints: None,
+            key_usage: Vec::new(),
+        }
+    }
+
+    // big-endian unsigned serial number
+    pub fn with_serial(mut self, serial: &[u8]) -> Self {
+        let start = serial.iter().position(|b| *b != 0).expect("the serial number must be positive");
+        assert!(serial.len() - start <= 20, "the serial number is longer than 20 octets");
+        self.serial = serial[start..].to_vec();
+        self
+    }
+
+    pub fn with_issuer(mut self, issuer: Name) -> Self {
+        self.issuer = Some(issuer);
+        self
+    }
+
+    // seconds since the epoch, both ends included
+    pub fn with_validity(mut self, not_before: u64, not_after: u64) -> Self {
+        assert!(not_before <= not_after, "empty validity period");
+        self.not_before = not_before;
+        self.not_after = not_after.min(TbsCertificate::NO_EXPIRATION);
+        self
+    }
+
+    pub fn with_subject_alt_names(mut self, alt_names: Vec<GeneralName>) -> Self {
+        self.alt_names = alt_names;
+        self
+    }
+
+    // path_length limits the CAs that may follow this one, CA certificates only
+    pub fn with_basic_constraints(mut self, ca: bool, path_length: Option<u32>) -> Self {
+        assert!(ca || path_length.is_none(), "only a CA has a path length constraint");
+        self.basic_constraints = Some((ca, path_length));
+        self
+    }
+
+    pub fn with_key_usage(mut self, key_usage: &[KeyUsage]) -> Self {
+        self.key_usage = key_usage.to_vec();
+        self
+    }
+
+    // signs with the issuer's key, the subject's own one for a self-signed certificate
+    pub fn sign(&self, key: &Key) -> Certificate {
+        let signature_algorithm = signature_algorithm(key);
+        let tbs = self.to_der(&signature_algorithm);
+        let signature = sign(key, &tbs);
+
+        Certificate{
+            tbs,
+            signature_algorithm,
+            signature,
+        }
+    }
+
+    // TBSCertificate ::= SEQUENCE {
+    //   version [0] EXPLICIT Version DEFAULT v1,
+    //   serialNumber CertificateSerialNumber,
+    //   signature AlgorithmIdentifier,
+    //   issuer Name,
+    //   validity Validity,
+    //   subject Name,
+    //   subjectPublicKeyInfo SubjectPublicKeyInfo,
+    //   issuerUniqueID [1] IMPLICIT UniqueIdentifier OPTIONAL,
+    //   subjectUniqueID [2] IMPLICIT UniqueIdentifier OPTIONAL,
+    //   extensions [3] EXPLICIT Extensions OPTIONAL
+    // }
+    // Validity ::= SEQUENCE { notBefore Time, notAfter Time }
+    fn to_der(&self, signature_algorithm: &[u8]) -> Vec<u8> {
+        assert!(!self.subject.is_empty() || !self.alt_names.is_empty(), "a certificate with an empty subject needs subject alternative names");
+        let issuer = self.issuer.as_ref().unwrap_or(&self.subject);
+        assert!(!issuer.is_empty(), "the issuer must not be empty");
+
+        let mut validity = to_time(self.not_before);
+        validity.extend(to_time(self.not_after));
+
+        let mut value = Vec::new();
+        value.extend(der::encode(Tag::Context(0), der::encode(Tag::Integer, [2])));
+        value.extend(der::encode(Tag::Integer, der::to_integer(&self.serial)));
+        value.extend(signature_algorithm);
+        value.extend(issuer.to_der());
+        value.extend(der::encode(Tag::Sequence, validity));
+        value.extend(self.subject.to_der());
+        value.extend(&self.public_key);
+        let extensions = self.gen_extensions();
+        if !extensions.is_empty() {
+            value.extend(der::encode(Tag::Context(3), der::encode(Tag::Sequence, extensions)));
+        }
+
+        der::encode(Tag::Sequence, value)
+    }
+
+    // Extension ::= SEQUENCE {
+    //   extnID OBJECT IDENTIFIER,
+    //   critical BOOLEAN DEFAULT FALSE,
+    //   extnValue OCTET STRING
+    // }
+    fn gen_extensions(&self) -> Vec<u8> {
+        let mut out = Vec::new();
+        if let Some((ca, path_length)) = self.basic_constraints {
+            // BasicConstraints ::= SEQUENCE { cA BOOLEAN DEFAULT FALSE, pathLenConstraint INTEGER OPTIONAL }
+            let mut value = Vec::new();
+            if ca {
+                value.extend(der::encode(Tag::Boolean, [0xff]));
+            }
+            if let Some(path_length) = path_length {
+                value.extend(der::encode(Tag::Integer, der::to_integer_u32(path_length)));
+            }
+            out.extend(extension(&TbsCertificate::BASIC_CONSTRAINTS, true, &der::encode(Tag::Sequence, value)));
+        }
+        if !self.key_usage.is_empty() {
+            let bits: Vec<usize> = self.key_usage.iter().map(|x| *x as usize).collect();
+            out.extend(extension(&TbsCertificate::KEY_USAGE, true, &der::encode(Tag::BitString, der::to_named_bits(&bits))));
+        }
+        if !self.alt_names.is_empty() {
+            // critical when the subject is empty (RFC 5280 section 4.2.1.6)
+            out.extend(subject_alt_name(&self.alt_names, self.subject.is_empty()));
+        }
+
+        out
+    }
+}
+
+// subjectAltName extension, GeneralNames ::= SEQUENCE SIZE (1..MAX) OF GeneralName
+pub fn subject_alt_name(alt_names: &[GeneralName], critical: bool) -> Vec<u8> {
+    const SUBJECT_ALT_NAME: [u8; 3] = [0x55,0x1d,0x11];
+    let names: Vec<u8> = alt_names.iter().flat_map(|x| x.to_der()).collect();
+
+    extension(&SUBJECT_ALT_NAME, critical, &der::encode(Tag::Sequence, names))
+}
+
+fn extension(oid: &[u8], critical: bool, value: &[u8]) -> Vec<u8> {
+    let mut out = der::encode(Tag::ObjectIdentifier, oid);
+    if critical {
+        out.extend(der::encode(Tag::Boolean, [0xff]));
+    }
+    out.extend(der::encode(Tag::OctetString, value));
+
+    der::encode(Tag::Sequence, out)
+}
+
+// RFC 5280 section 4.1.2.5, UTCTime YYMMDDHHMMSSZ through 2049
+// and GeneralizedTime YYYYMMDDHHMMSSZ from 2050 on
+fn to_time(time: u64) -> Vec<u8> {
+    let [year, month, day, hour, minute, second] = civil_time(time);
+
+    let rest = format!("{:02}{:02}{:02}{:02}{:02}Z", month, day, hour, minute, second);
+    if year < 2050 {
+        der::encode(Tag::UtcTime, format!("{:02}{}", year % 100, rest))
+    } else {
+        der::encode(Tag::GeneralizedTime, format!("{:04}{}", year, rest))
+    }
+}
+
+// seconds since the epoch to year, month, day, hour, minute and second in UTC,
+// the inverse of the day count in ssh::parse_time with years from March first
+pub fn civil_time(time: u64) -> [u64; 6] {
+    let days = time / 86400;
+    let seconds = time % 86400;
+
+    let z = days + 719468;
+    let era = z / 146097;
+    let doe = z % 146097;
+    let yoe = (doe - doe / 1460 + doe / 36524 - doe / 146096) / 365;
+    let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+    let mp = (5 * doy + 2) / 153;
+    let day = doy - (153 * mp + 2) / 5 + 1;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 };
+    let year = yoe + era * 400 + if month <= 2 { 1 } else { 0 };
+
+    [year, month, day, seconds / 3600, seconds / 60 % 60, seconds % 60]
+}
+
+// AlgorithmIdentifier of the signatures key makes over certificates and requests:
+// Ed25519 (RFC 8410 section 3), sha256WithRSAEncryption (RFC 4055 section 5),
+// or RSASSA-PSS (RFC 4055 section 3.1) when the key is restricted to PSS
+pub fn signature_algorithm(key: &Key) -> Vec<u8> {
+    // sha256WithRSAEncryption OBJECT IDENTIFIER ::= { pkcs-1 11 }, parameters NULL
+    const SHA256_WITH_RSA_ENCRYPTION: [u8; 15] = [0x30,0x0d,0x06,0x09,0x2a,0x86,0x48,0x86,0xf7,0x0d,0x01,0x01,0x0b,0x05,0x00];
+
+    match key {
+        Key::Ed25519(_) => Ed25519::ID.to_vec(),
+        Key::Rsa(key) => match key.algorithm() {
+            RsaAlgorithm::Rsa => SHA256_WITH_RSA_ENCRYPTION.to_vec(),
+            RsaAlgorithm::Pss(params) => RsaAlgorithm::Pss(Some(pss_params(params))).gen_algorithm_identifier(),
+        },
+    }
+}
+
+// the signature signature_algorithm names
+pub fn sign(key: &Key, data: &[u8]) -> Vec<u8> {
+    match key {
+        Key::Ed25519(key) => key.sign(data),
+        Key::Rsa(key) => match key.algorithm() {
+            RsaAlgorithm::Rsa => key.sign_pkcs1_v1_5(Hash::Sha256, data),
+            RsaAlgorithm::Pss(params) => key.sign_pss(&pss_params(params), data),
+        },
+    }
+}
+
+// a key restricted to PSS with any parameters signs with SHA-256 and a 32 byte salt
+fn pss_params(params: Option<PssParams>) -> PssParams {
+    params.unwrap_or(PssParams::new(Hash::Sha256, Hash::Sha256, 32))
+}
+
+// RFC 5280 section 4.1
+// Certificate ::= SEQUENCE {
+//   tbsCertificate TBSCertificate,
+//   signatureAlgorithm AlgorithmIdentifier,
+//   signatureValue BIT STRING
+// }
+pub struct Certificate {
+    tbs: Vec<u8>,
+    signature_algorithm: Vec<u8>,
+    signature: Vec<u8>,
+}
+
+impl Certificate {
+    // "CERTIFICATE", from genkey or anyone else; the signature is not checked
+    pub fn from_pem(data: &[u8]) -> Option<Self> {
+        let (label, body) = pem::decapsulate(data)?;
+        if label != b"CERTIFICATE" {
+            return None;
+        }
+        let (data, rest) = der::decode(Tag::Sequence, &body)?;
+        if !rest.is_empty() {
+            return None;
+        }
+        let (_, rest) = der::decode(Tag::Sequence, data)?;
+        let tbs = &data[..data.len() - rest.len()];
+        let (_, signature) = der::decode(Tag::Sequence, rest)?;
+        let signature_algorithm = &rest[..rest.len() - signature.len()];
+        let (signature, rest) = der::decode(Tag::BitString, signature)?;
+        if !rest.is_empty() || signature.first() != Some(&0) {
+            return None;
+        }
+
+        Some(Certificate{
+            tbs: tbs.to_vec(),
+            signature_algorithm: signature_algorithm.to_vec(),
+            signature: signature[1..].to_vec(),
+        })
+    }
+
+    // DER of the subjectPublicKeyInfo, comparable with Spki::to_der
+    pub fn public_key(&self) -> Option<&[u8]> {
+        let (data, _) = der::decode(Tag::Sequence, &self.tbs)?;
+        let (_, mut data) = der::decode(Tag::Context(0), data).unwrap_or((&[], data));
+        // serialNumber, signature, issuer, validity, subject
+        for t in [Tag::Integer, Tag::Sequence, Tag::Sequence, Tag::Sequence, Tag::Sequence] {
+            data = der::decode(t, data)?.1;
+        }
+        let (_, rest) = der::decode(Tag::Sequence, data)?;
+
+        Some(&data[..data.len() - rest.len()])
+    }
+}
+
+impl DerEncode for Certificate {
+    fn to_der(&self) -> Secret {
+        let mut value = Secret::new();
+        value.extend(&self.tbs);
+        value.extend(&self.signature_algorithm);
+        value.extend(der::encode(Tag::BitString, der::to_bit_string(&self.signature)));
+
+        der::encode_secret(Tag::Sequence, &value)
+    }
+}
+
+impl PemEncode for Certificate {
+    fn to_pem(&self) -> Vec<u8> {
+        pem::encapsulate(b"CERTIFICATE", &self.to_der(), 64)
+    }
+}